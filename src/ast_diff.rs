@@ -0,0 +1,420 @@
+use crate::lexer::Lexer;
+use crate::output::{Event, Phase};
+use crate::parser::expr::Expr;
+use crate::parser::features::Features;
+use crate::parser::parser::Parser;
+use crate::parser::stmt::{Accessor, Block, Stmt};
+use crate::parser::visitor::{walk_expr, walk_stmt, ExprVisitor, StmtVisitor};
+use crate::source::normalize_source;
+use crate::token::TokenType;
+
+//A node of a printed syntax tree, the tree of a program is printed with a line for each node
+//positions are left out of the text, so code which only moved between lines prints the same tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeLine {
+    //The number of nodes above this one
+    pub depth: usize,
+    pub text: String,
+    //The line of the statement holding the node
+    pub line: u32,
+}
+
+//A line of the diff between two trees
+#[derive(Debug, PartialEq)]
+pub enum Change<'a> {
+    //A node of both trees, with its line in the old and new one
+    Same(&'a TreeLine, &'a TreeLine),
+    Removed(&'a TreeLine),
+    Added(&'a TreeLine),
+}
+
+//Parse a program into the lines of its tree, the errors stopping it are returned as events
+pub fn parse_tree(source: &str, features: Features) -> Result<Vec<TreeLine>, Vec<Event>> {
+    let source = normalize_source(source);
+    let tokens = Lexer::new(&source).lex();
+    let errors: Vec<Event> = tokens
+        .iter()
+        .filter_map(|token| match &token.class {
+            TokenType::Error(error) => Some(Event::Error(
+                Phase::Lex,
+                error.get_message().to_owned(),
+                Some((token.line, token.start)),
+                error.get_fix((token.line, token.start), &source),
+            )),
+            _ => None,
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    match Parser::new(&tokens).with_features(features).parse() {
+        Ok(block) => Ok(tree_lines(&block)),
+        Err(errors) => Err(errors
+            .errors
+            .iter()
+            .map(|error| {
+                Event::Error(
+                    Phase::Parse,
+                    error.get_message(),
+                    Some(error.get_position()),
+                    error.get_fix(&source),
+                )
+            })
+            .collect()),
+    }
+}
+
+//The lines of the tree of a parsed program, each node is followed by its children
+pub fn tree_lines(block: &Block) -> Vec<TreeLine> {
+    let mut printer = TreePrinter {
+        lines: Vec::new(),
+        depth: 0,
+        line: 0,
+    };
+    for (stmt, span) in block.iter() {
+        printer.line = span.line;
+        printer.visit_stmt(stmt);
+    }
+    printer.lines
+}
+
+//Match the nodes of two trees, keeping as many nodes in common as possible
+//a node is the same if it has the same text at the same depth, wherever it is in the source
+pub fn diff<'a>(old: &'a [TreeLine], new: &'a [TreeLine]) -> Vec<Change<'a>> {
+    let same = |a: &TreeLine, b: &TreeLine| a.depth == b.depth && a.text == b.text;
+    //the nodes around the changes are usually most of the tree, they are matched before the table is made
+    let prefix = old.iter().zip(new).take_while(|(a, b)| same(a, b)).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| same(a, b))
+        .count();
+    let (middle_old, middle_new) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+    //common[i][j]: the length of the longest common subsequence of middle_old[i..] and middle_new[j..]
+    let mut common = vec![vec![0usize; middle_new.len() + 1]; middle_old.len() + 1];
+    for i in (0..middle_old.len()).rev() {
+        for j in (0..middle_new.len()).rev() {
+            common[i][j] = if same(&middle_old[i], &middle_new[j]) {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes: Vec<Change> = old[..prefix]
+        .iter()
+        .zip(&new[..prefix])
+        .map(|(a, b)| Change::Same(a, b))
+        .collect();
+    let (mut i, mut j) = (0, 0);
+    while i < middle_old.len() || j < middle_new.len() {
+        if i < middle_old.len() && j < middle_new.len() && same(&middle_old[i], &middle_new[j]) {
+            changes.push(Change::Same(&middle_old[i], &middle_new[j]));
+            i += 1;
+            j += 1;
+        } else if j == middle_new.len()
+            || (i < middle_old.len() && common[i + 1][j] >= common[i][j + 1])
+        {
+            changes.push(Change::Removed(&middle_old[i]));
+            i += 1;
+        } else {
+            changes.push(Change::Added(&middle_new[j]));
+            j += 1;
+        }
+    }
+    changes.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .zip(&new[new.len() - suffix..])
+            .map(|(a, b)| Change::Same(a, b)),
+    );
+    changes
+}
+
+//Print the changes of a diff in hunks, each with a few nodes around it and the lines it starts at
+//nothing is printed if the trees are the same
+pub fn format_diff(changes: &[Change], context: usize) -> String {
+    let changed: Vec<usize> = (0..changes.len())
+        .filter(|&i| !matches!(changes[i], Change::Same(..)))
+        .collect();
+    let mut text = String::new();
+    let mut index = 0;
+    while index < changed.len() {
+        //changes with at most twice the context between them share a hunk
+        let start = changed[index].saturating_sub(context);
+        let mut end = changed[index];
+        while index < changed.len() && changed[index] <= end + 2 * context + 1 {
+            end = changed[index];
+            index += 1;
+        }
+        let end = (end + context).min(changes.len() - 1);
+        let (old_line, new_line) = hunk_lines(&changes[start..=end]);
+        text.push_str(&format!(
+            "@@ old line {}, new line {} @@\n",
+            old_line, new_line
+        ));
+        for change in &changes[start..=end] {
+            let (sign, node) = match change {
+                Change::Same(node, _) => (' ', node),
+                Change::Removed(node) => ('-', node),
+                Change::Added(node) => ('+', node),
+            };
+            text.push_str(&format!(
+                "{} {}{}\n",
+                sign,
+                "  ".repeat(node.depth),
+                node.text
+            ));
+        }
+    }
+    text
+}
+
+//The lines of the old and new source a hunk starts at
+fn hunk_lines(changes: &[Change]) -> (u32, u32) {
+    let old = changes.iter().find_map(|change| match change {
+        Change::Same(node, _) | Change::Removed(node) => Some(node.line),
+        Change::Added(_) => None,
+    });
+    let new = changes.iter().find_map(|change| match change {
+        Change::Same(_, node) | Change::Added(node) => Some(node.line),
+        Change::Removed(_) => None,
+    });
+    (old.unwrap_or(0), new.unwrap_or(0))
+}
+
+//Adds a line for each node it visits, below the line of its parent
+struct TreePrinter {
+    lines: Vec<TreeLine>,
+    depth: usize,
+    //The line of the statement being visited
+    line: u32,
+}
+
+impl TreePrinter {
+    fn push(&mut self, text: String) {
+        self.lines.push(TreeLine {
+            depth: self.depth,
+            text,
+            line: self.line,
+        });
+    }
+
+    //Add a line for a node, with the lines of its children below it
+    fn node(&mut self, text: String, children: impl FnOnce(&mut Self)) {
+        self.push(text);
+        self.depth += 1;
+        children(self);
+        self.depth -= 1;
+    }
+}
+
+impl ExprVisitor for TreePrinter {
+    fn visit_expr(&mut self, expr: &Expr) {
+        let text = match expr {
+            Expr::Ident(name) => format!("Ident {}", name),
+            Expr::Literal(literal) => format!("Literal {:?}", literal),
+            //the body is visited as a block, so its statements keep their own lines
+            Expr::Function(function) => {
+                let text = format!("Function({})", function.params.join(", "));
+                return self.node(text, |printer| printer.visit_block(&function.body));
+            }
+            Expr::Div(..) => String::from("Div"),
+            Expr::Mul(..) => String::from("Mul"),
+            Expr::Mod(..) => String::from("Mod"),
+            Expr::FloorDiv(..) => String::from("FloorDiv"),
+            Expr::Pow(..) => String::from("Pow"),
+            Expr::Add(..) => String::from("Add"),
+            Expr::Sub(..) => String::from("Sub"),
+            Expr::Greater(..) => String::from("Greater"),
+            Expr::Less(..) => String::from("Less"),
+            Expr::GreaterEqual(..) => String::from("GreaterEqual"),
+            Expr::LessEqual(..) => String::from("LessEqual"),
+            Expr::Equal(..) => String::from("Equal"),
+            Expr::NotEqual(..) => String::from("NotEqual"),
+            Expr::And(..) => String::from("And"),
+            Expr::Or(..) => String::from("Or"),
+            Expr::Compare(_, comparisons) => {
+                let operators: Vec<String> = comparisons
+                    .iter()
+                    .map(|(operator, _)| format!("{:?}", operator))
+                    .collect();
+                format!("Compare {}", operators.join(", "))
+            }
+            Expr::Not(_) => String::from("Not"),
+            Expr::Negate(..) => String::from("Negate"),
+            Expr::Tuple(_) => String::from("Tuple"),
+            Expr::TupleIndex(_, index) => format!("TupleIndex {}", index),
+            Expr::Map(_) => String::from("Map"),
+            Expr::Record(name, fields) => {
+                let names: Vec<&str> = fields.iter().map(|(field, _)| field.as_str()).collect();
+                format!("Record {} {{ {} }}", name, names.join(", "))
+            }
+            Expr::Field(_, name) => format!("Field {}", name),
+            Expr::Index(..) => String::from("Index"),
+            //the children don't show which bound is missing
+            Expr::Slice(_, start, end) => format!(
+                "Slice{}{}",
+                if start.is_some() { " start" } else { "" },
+                if end.is_some() { " end" } else { "" }
+            ),
+            Expr::Interpolation(_) => String::from("Interpolation"),
+            Expr::Call(name, _) => format!("Call {}", name),
+            Expr::Range(_, _, inclusive) => String::from(if *inclusive {
+                "Range inclusive"
+            } else {
+                "Range"
+            }),
+            Expr::Is(_, value_type) => format!("Is {}", value_type.name()),
+            Expr::Cast(_, value_type) => format!("Cast {}", value_type.name()),
+        };
+        self.node(text, |printer| walk_expr(printer, expr));
+    }
+}
+
+impl StmtVisitor for TreePrinter {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        let text = match stmt {
+            Stmt::Expr(_) => String::from("Expr"),
+            Stmt::Print(_) => String::from("Print"),
+            Stmt::Assign(name, _) => format!("Assign {}", name),
+            Stmt::Const(name, _) => format!("Const {}", name),
+            Stmt::Destructure(names, _) => format!("Destructure {}", names.join(", ")),
+            Stmt::Reassign(name, _) => format!("Reassign {}", name),
+            //the indexes are children, the fields are only shown in the path
+            Stmt::IndexAssign(name, accessors, _) => {
+                let path: String = accessors
+                    .iter()
+                    .map(|accessor| match accessor {
+                        Accessor::Index(_) => String::from("[]"),
+                        Accessor::Field(field) => format!(".{}", field),
+                    })
+                    .collect();
+                format!("IndexAssign {}{}", name, path)
+            }
+            Stmt::Block(block) => return self.visit_block(block),
+            Stmt::FnDecl(function) => {
+                format!("FnDecl {}({})", function.name, function.params.join(", "))
+            }
+            Stmt::Struct(declared) => {
+                format!(
+                    "Struct {} {{ {} }}",
+                    declared.name,
+                    declared.fields.join(", ")
+                )
+            }
+            Stmt::Return(_) => String::from("Return"),
+            Stmt::If(_, _, otherwise) => {
+                String::from(if otherwise.is_some() { "If else" } else { "If" })
+            }
+            Stmt::While(..) => String::from("While"),
+            Stmt::DoWhile(..) => String::from("DoWhile"),
+            //the children don't show which parts of the header are missing
+            Stmt::For(init, condition, step, _) => format!(
+                "For{}{}{}",
+                if init.is_some() { " init" } else { "" },
+                if condition.is_some() {
+                    " condition"
+                } else {
+                    ""
+                },
+                if step.is_some() { " step" } else { "" }
+            ),
+            Stmt::Break => String::from("Break"),
+            Stmt::Continue => String::from("Continue"),
+            Stmt::Exit(_) => String::from("Exit"),
+            Stmt::Throw(_) => String::from("Throw"),
+            Stmt::Assert(_, message) => String::from(if message.is_some() {
+                "Assert message"
+            } else {
+                "Assert"
+            }),
+            Stmt::Try(_, name, _) => format!("Try catch {}", name),
+        };
+        self.node(text, |printer| walk_stmt(printer, stmt));
+    }
+
+    //each statement of a block is a child of the block's node
+    fn visit_block(&mut self, block: &Block) {
+        let line = self.line;
+        self.node(String::from("Block"), |printer| {
+            for (stmt, span) in block.iter() {
+                printer.line = span.line;
+                printer.visit_stmt(stmt);
+            }
+        });
+        self.line = line;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(source: &str) -> Vec<TreeLine> {
+        parse_tree(source, Features::default()).unwrap()
+    }
+
+    #[test]
+    fn print_tree() {
+        let lines = tree("let a = 1 + b\nif a > 2 {\n  print f(a)\n}");
+        let printed: Vec<String> = lines
+            .iter()
+            .map(|line| format!("{}{}:{}", "  ".repeat(line.depth), line.text, line.line))
+            .collect();
+        assert_eq!(
+            printed,
+            [
+                "Assign a:1",
+                "  Add:1",
+                "    Literal Number(1):1",
+                "    Ident b:1",
+                "If:2",
+                "  Greater:2",
+                "    Ident a:2",
+                "    Literal Number(2):2",
+                "  Block:2",
+                "    Print:3",
+                "      Call f:3",
+                "        Ident a:3",
+            ]
+        );
+
+        //formatting and comments don't change the tree
+        assert_eq!(
+            tree("let a=1+b #sum\n\n\nif (a > 2) { print f(a) }")
+                .iter()
+                .map(|line| (line.depth, &line.text))
+                .collect::<Vec<_>>(),
+            lines
+                .iter()
+                .map(|line| (line.depth, &line.text))
+                .collect::<Vec<_>>()
+        );
+        assert!(parse_tree("let a = (", Features::default()).is_err());
+    }
+
+    #[test]
+    fn diff_trees() {
+        let old = tree("let a = 1\nprint a\nprint a * 2");
+        let new = tree("let a = 1\n\nprint a\nprint a * 3\nprint a");
+        let changes = diff(&old, &new);
+        assert_eq!(
+            format_diff(&changes, 1),
+            "@@ old line 3, new line 4 @@\n      Ident a\n-     Literal Number(2)\n+     Literal Number(3)\n+ Print\n+   Ident a\n"
+        );
+        assert_eq!(format_diff(&diff(&old, &old), 1), "");
+        //with no context, the distant changes are in separate hunks
+        let old = tree("a = 1\nb = 2\nc = 3\nd = 4");
+        let new = tree("a = 0\nb = 2\nc = 3\nd = 5");
+        assert_eq!(
+            format_diff(&diff(&old, &new), 0),
+            "@@ old line 1, new line 1 @@\n-   Literal Number(1)\n+   Literal Number(0)\n@@ old line 4, new line 4 @@\n-   Literal Number(4)\n+   Literal Number(5)\n"
+        );
+    }
+}
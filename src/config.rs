@@ -0,0 +1,90 @@
+use crate::errors::ConfigError;
+use crate::lexer::Lexer;
+use crate::token::{is_identifier, Literal, TokenType, Unary};
+
+//The file in the working directory whose definitions are loaded as globals before a file is run
+pub const ENV_FILE: &str = "estel.env";
+
+//Split a definition like count=5 into the variable name and its value
+pub fn parse_define(definition: &str) -> Result<(String, Literal), ConfigError> {
+    let (name, value) = definition
+        .split_once('=')
+        .ok_or_else(|| ConfigError::MissingValueError(definition.to_owned()))?;
+    let name = name.trim();
+    if !is_identifier(name) {
+        return Err(ConfigError::InvalidNameError(name.to_owned()));
+    }
+    Ok((name.to_owned(), parse_value(value.trim())))
+}
+
+//Parse the definitions of an env file, one on each line
+//empty lines and lines starting with # are skipped, errors come with their line number
+pub fn parse_env(source: &str) -> Result<Vec<(String, Literal)>, (usize, ConfigError)> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(line, definition)| (line + 1, definition.trim()))
+        .filter(|(_, definition)| !definition.is_empty() && !definition.starts_with('#'))
+        .map(|(line, definition)| parse_define(definition).map_err(|err| (line, err)))
+        .collect()
+}
+
+//A value written like a literal in code, eg- 5, -2.5, "bob" or true, is read as that literal
+//anything else is taken as a string, as shells remove the quotes around arguments
+pub fn parse_value(value: &str) -> Literal {
+    let tokens = Lexer::new(value).lex();
+    let classes: Vec<&TokenType> = tokens.iter().map(|token| &token.class).collect();
+    match classes[..] {
+        [TokenType::Literal(literal), TokenType::Eof] => literal.clone(),
+        [TokenType::Unary(Unary::Neg), TokenType::Literal(Literal::Number(num)), TokenType::Eof] => {
+            Literal::Number(-num)
+        }
+        [TokenType::Unary(Unary::Neg), TokenType::Literal(Literal::Float(num)), TokenType::Eof] => {
+            Literal::Float(-num)
+        }
+        _ => Literal::String(value.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_definitions() {
+        let string = |value: &str| Literal::String(value.to_owned());
+        assert_eq!(parse_value("5"), Literal::Number(5));
+        assert_eq!(parse_value("-2.5"), Literal::Float(-2.5));
+        assert_eq!(parse_value("\"bob\""), string("bob"));
+        assert_eq!(parse_value("true"), Literal::Bool(true));
+        assert_eq!(parse_value("bob"), string("bob"));
+        assert_eq!(parse_value("1 + 2"), string("1 + 2"));
+        assert_eq!(parse_value(""), string(""));
+
+        assert_eq!(
+            parse_define("name = \"bob\""),
+            Ok((String::from("name"), string("bob")))
+        );
+        assert_eq!(
+            parse_define("name"),
+            Err(ConfigError::MissingValueError(String::from("name")))
+        );
+        assert_eq!(
+            parse_define("if=1"),
+            Err(ConfigError::InvalidNameError(String::from("if")))
+        );
+
+        let env = "# settings\nx=5\n\n  debug = false\n";
+        assert_eq!(
+            parse_env(env),
+            Ok(vec![
+                (String::from("x"), Literal::Number(5)),
+                (String::from("debug"), Literal::Bool(false)),
+            ])
+        );
+        assert_eq!(
+            parse_env("x=5\n2x=1"),
+            Err((2, ConfigError::InvalidNameError(String::from("2x"))))
+        );
+    }
+}
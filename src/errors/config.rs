@@ -0,0 +1,19 @@
+//Errors in the globals defined before a program runs, with --define or in an estel.env file
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    //MissingValueError(definition without an =)
+    MissingValueError(String),
+    //InvalidNameError(name)
+    InvalidNameError(String),
+}
+
+impl ConfigError {
+    pub fn get_message(&self) -> String {
+        match self {
+            Self::MissingValueError(definition) => {
+                format!("Expected a definition like name=value, got {}", definition)
+            }
+            Self::InvalidNameError(name) => format!("{} is not a valid variable name", name),
+        }
+    }
+}
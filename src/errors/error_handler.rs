@@ -1,19 +1,23 @@
 use super::{
     token::{Token, TokenType},
-    StmtErrors,
+    StmtErrors, Warning,
 };
+use crate::output::{Event, OutputMode, Phase};
 use colored::Colorize;
 
 pub struct ErrorHandler<'a> {
     source: &'a str,
     lex_errors: Vec<&'a Token>,
+    //In the Json output mode each problem is reported as an event, without a code snippet
+    output: OutputMode,
 }
 
 impl<'a> ErrorHandler<'a> {
-    pub fn new(source: &'a str) -> Self {
+    pub fn new(source: &'a str, output: OutputMode) -> Self {
         Self {
             source,
             lex_errors: Vec::new(),
+            output,
         }
     }
 
@@ -21,12 +25,9 @@ impl<'a> ErrorHandler<'a> {
     pub fn find_lexical_errors(&mut self, tokens: &'a Vec<Token>) -> bool {
         let mut had_error = false;
         for token in tokens {
-            match &token.class {
-                TokenType::Error(_) => {
-                    self.lex_errors.push(token);
-                    had_error = true;
-                }
-                _ => (),
+            if let TokenType::Error(_) = &token.class {
+                self.lex_errors.push(token);
+                had_error = true;
             }
         }
         had_error
@@ -35,41 +36,75 @@ impl<'a> ErrorHandler<'a> {
     pub fn print_lexical_errors(&self) {
         for token in &self.lex_errors {
             if let TokenType::Error(err_type) = &token.class {
-                eprintln!(
-                    "{}",
-                    format!(
-                        "Error: {} at line {} position {}",
-                        err_type.get_message(),
-                        token.line,
-                        token.start
-                    )
-                    .bright_red()
-                );
-                self.print_code_snippet(token.line, token.start, 1)
+                let message = err_type.get_message().to_owned();
+                let position = (token.line, token.start);
+                self.report(&Event::Error(
+                    Phase::Lex,
+                    message,
+                    Some(position),
+                    err_type.get_fix(position, self.source),
+                ));
             }
         }
     }
 
     pub fn print_stmt_errors(&self, errors: &'a StmtErrors) {
         for error in errors.errors.iter() {
-            let error_position = error.get_position();
-            eprintln!(
-                "{}",
-                format!(
-                    "Error: {} at line {} position {}",
-                    error.get_message(),
-                    error_position.0,
-                    error_position.1
-                )
-                .bright_red()
-            );
-            self.print_code_snippet(error_position.0, error_position.1, 1)
+            let position = Some(error.get_position());
+            let fix = error.get_fix(self.source);
+            self.report(&Event::Error(
+                Phase::Parse,
+                error.get_message(),
+                position,
+                fix,
+            ));
+        }
+    }
+
+    pub fn print_warnings(&self, warnings: &[Warning]) {
+        for warning in warnings {
+            self.report(&Event::Warning(
+                warning.get_message(),
+                warning.get_hint(),
+                warning.get_position(),
+            ));
+        }
+    }
+
+    //Reports an error or warning as a JSON event, or as text with a snippet of the code around it
+    pub fn report(&self, event: &Event) {
+        if self.output == OutputMode::Json {
+            event.emit();
+            return;
+        }
+        match event {
+            Event::Error(_, message, Some((line, start)), fix) => {
+                eprintln!(
+                    "{}",
+                    format!("Error: {} at line {} position {}", message, line, start).bright_red()
+                );
+                if let Some(fix) = fix {
+                    eprintln!("{}", format!("fix: {}", fix.message).bright_cyan());
+                }
+                self.print_code_snippet(*line, *start, 1)
+            }
+            Event::Error(_, message, None, _) => {
+                eprintln!("{}", format!("Error: {}", message).bright_red())
+            }
+            Event::Warning(message, hint, (line, start)) => {
+                eprintln!(
+                    "{}",
+                    format!("Warning: {} at line {} position {}", message, line, start).yellow()
+                );
+                eprintln!("{}", format!("help: {}", hint).bright_cyan());
+                self.print_code_snippet(*line, *start, 1)
+            }
+            Event::Print(_) | Event::Result(_) | Event::Progress(..) => {}
         }
     }
 
     //prints a code snippet around the line where the error occured and point at the error
     fn print_code_snippet(&self, line: u32, pos: u32, surround_lines: u32) {
-        let mut current_line: u32 = 1;
         eprintln!();
         //prevent overflow
         let start_line = {
@@ -84,18 +119,24 @@ impl<'a> ErrorHandler<'a> {
         //Calculate the number of characters taken by the line number
         let gap = line.to_string().len() as u32;
 
-        for code_line in self.source.lines() {
+        for (current_line, code_line) in (1..).zip(self.source.lines()) {
             if current_line == line {
+                let (shown, column) = window_line(code_line, pos as usize);
                 eprintln!(
                     "{}{}",
                     (current_line.to_string() + " | ").bright_cyan(),
-                    code_line
+                    shown
                 );
                 //make an arrow to the position
-                for _ in 0..gap + pos + 3 {
+                for _ in 0..gap as usize + column + 3 {
                     eprint!(" ");
                 }
-                eprintln!("{}", "^".bright_red());
+                //the position can't be counted by eye on a cut line, so it is shown with the arrow
+                if shown != code_line {
+                    eprintln!("{} column {}", "^".bright_red(), pos);
+                } else {
+                    eprintln!("{}", "^".bright_red());
+                }
             } else if current_line >= start_line && current_line <= end_line {
                 //equalize the gap with the line with line number
                 for _ in 0..gap {
@@ -104,11 +145,65 @@ impl<'a> ErrorHandler<'a> {
                 eprintln!(
                     "{}{}",
                     " | ".bright_cyan(),
-                    code_line.truecolor(150, 150, 150)
+                    window_line(code_line, pos as usize)
+                        .0
+                        .truecolor(150, 150, 150)
                 );
             }
-            current_line += 1;
         }
         eprintln!("\n")
     }
 }
+
+//The most characters of a line shown in a snippet, eg- of minified or generated code
+const MAX_SNIPPET_WIDTH: usize = 80;
+
+//Cut a long line to a window around the column, marking the cut ends with …
+//returns the text to show and the column within it
+fn window_line(code_line: &str, column: usize) -> (String, usize) {
+    let chars: Vec<char> = code_line.chars().collect();
+    if chars.len() <= MAX_SNIPPET_WIDTH {
+        return (code_line.to_owned(), column);
+    }
+    //keep the column in the middle of the window, unless the line ends first
+    let start = column
+        .saturating_sub(MAX_SNIPPET_WIDTH / 2)
+        .min(chars.len() - MAX_SNIPPET_WIDTH);
+    let end = start + MAX_SNIPPET_WIDTH;
+    let mut shown = String::new();
+    let mut column = column - start;
+    if start > 0 {
+        shown.push('…');
+        column += 1;
+    }
+    shown.extend(&chars[start..end]);
+    if end < chars.len() {
+        shown.push('…');
+    }
+    (shown, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippet_windows() {
+        assert_eq!(window_line("let a = 1", 4), (String::from("let a = 1"), 4));
+
+        let line = "x".repeat(100) + "y" + &"z".repeat(100);
+        let (shown, column) = window_line(&line, 100);
+        assert_eq!(shown.chars().count(), MAX_SNIPPET_WIDTH + 2);
+        assert!(shown.starts_with('…') && shown.ends_with('…'));
+        assert_eq!(shown.chars().nth(column), Some('y'));
+
+        //windows at either end of the line are only cut on the other side
+        let (shown, column) = window_line(&line, 2);
+        assert!(shown.starts_with("xxx") && shown.ends_with('…'));
+        assert_eq!(column, 2);
+        let (shown, column) = window_line(&line, 200);
+        assert!(shown.starts_with('…') && shown.ends_with('z'));
+        assert_eq!(shown.chars().nth(column), Some('z'));
+        assert_eq!(column, MAX_SNIPPET_WIDTH);
+    }
+}
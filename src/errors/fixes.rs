@@ -0,0 +1,134 @@
+use super::token::Token;
+
+//A change to the source which resolves an error, eg- closing an unterminated string
+//start and end are the positions of the replaced characters on the line, equal for an insertion
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    //What the fix does, eg- "Replace = with =="
+    pub message: String,
+    pub line: u32,
+    pub start: u32,
+    pub end: u32,
+    pub replacement: String,
+}
+
+impl Fix {
+    pub fn new(message: &str, (line, start): (u32, u32), end: u32, replacement: &str) -> Self {
+        Self {
+            message: message.to_owned(),
+            line,
+            start,
+            end,
+            replacement: replacement.to_owned(),
+        }
+    }
+
+    //End the statement before a token, in place of the spaces between it and the code before it
+    pub fn end_stmt_before(token: &Token, source: &str) -> Option<Self> {
+        let line: Vec<char> = source
+            .lines()
+            .nth(token.line as usize - 1)?
+            .chars()
+            .collect();
+        let mut start = (token.start as usize).min(line.len());
+        while start > 0 && line[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        //a token at the start of its line is already on a statement of its own
+        (start > 0).then(|| {
+            Self::new(
+                "Insert ; before the statement",
+                (token.line, start as u32),
+                token.start,
+                "; ",
+            )
+        })
+    }
+
+    //Close a string at the end of its line, the position is at its opening quote
+    //strings spanning lines and strings ending in an interpolation or escape are left alone
+    pub fn close_string((line, start): (u32, u32), source: &str) -> Option<Self> {
+        let text: Vec<char> = source.lines().nth(line as usize - 1)?.chars().collect();
+        let rest = text.get(start as usize..)?;
+        let quoted = match rest {
+            ['r', quoted @ ..] => quoted,
+            quoted => quoted,
+        };
+        let quote = *quoted.first().filter(|quote| matches!(quote, '"' | '\''))?;
+        let body: String = quoted[1..].iter().collect();
+        if body.starts_with([quote; 2]) || body.contains("${") || body.ends_with('\\') {
+            return None;
+        }
+        let end = text.len() as u32;
+        Some(Self::new(
+            &format!("Close the string with {}", quote),
+            (line, end),
+            end,
+            &quote.to_string(),
+        ))
+    }
+}
+
+//Apply fixes to a source, a fix overlapping one applied before it is skipped
+//returns the fixed source with the fixes applied
+pub fn apply_fixes(source: &str, fixes: Vec<Fix>) -> (String, Vec<Fix>) {
+    let mut lines: Vec<Vec<char>> = source.lines().map(|line| line.chars().collect()).collect();
+    //the spans of the fixes applied on each line, so overlapping fixes can be found
+    let mut spans: Vec<(u32, u32, u32)> = Vec::new();
+    let mut applied: Vec<Fix> = Vec::new();
+    for fix in fixes {
+        let overlaps = spans
+            .iter()
+            .any(|&(line, start, end)| line == fix.line && fix.start <= end && start <= fix.end);
+        let fits = lines
+            .get(fix.line as usize - 1)
+            .is_some_and(|line| fix.start <= fix.end && fix.end as usize <= line.len());
+        if !overlaps && fits {
+            spans.push((fix.line, fix.start, fix.end));
+            applied.push(fix);
+        }
+    }
+    //the fixes later on a line are applied first, so the positions of the earlier ones still hold
+    let mut ordered: Vec<&Fix> = applied.iter().collect();
+    ordered.sort_by_key(|fix| (fix.line, std::cmp::Reverse(fix.start)));
+    for fix in ordered {
+        let line = &mut lines[fix.line as usize - 1];
+        line.splice(
+            fix.start as usize..fix.end as usize,
+            fix.replacement.chars(),
+        );
+    }
+    let mut fixed: Vec<String> = lines.into_iter().map(String::from_iter).collect();
+    //lines() drops the line break ending the source
+    if source.ends_with('\n') {
+        fixed.push(String::new());
+    }
+    (fixed.join("\n"), applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_source_fixes() {
+        let source = "print a = 1\nlet s = \"hi\n";
+        let fixes = vec![
+            Fix::new("Replace = with ==", (1, 8), 9, "=="),
+            Fix::close_string((2, 8), source).unwrap(),
+            //overlaps the first fix
+            Fix::new("Insert ;", (1, 7), 8, "; "),
+        ];
+        let (fixed, applied) = apply_fixes(source, fixes);
+        assert_eq!(fixed, "print a == 1\nlet s = \"hi\"\n");
+        assert_eq!(applied.len(), 2);
+
+        //strings spanning lines can't be closed at the end of their first line
+        assert_eq!(Fix::close_string((1, 0), "\"\"\"a"), None);
+        assert_eq!(Fix::close_string((1, 0), "\"a ${b"), None);
+        assert_eq!(
+            Fix::close_string((1, 4), "a = r'b").map(|fix| fix.replacement),
+            Some(String::from("'"))
+        );
+    }
+}
@@ -1,8 +1,16 @@
+mod config;
 mod error_handler;
+mod fixes;
 mod parse_time;
 mod run_time;
+mod source;
+mod warnings;
 
 use super::parser::*;
+pub use config::*;
 pub use error_handler::*;
+pub use fixes::*;
 pub use parse_time::*;
 pub use run_time::*;
+pub use source::*;
+pub use warnings::*;
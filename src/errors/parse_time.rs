@@ -1,10 +1,20 @@
 use super::expr::ExpectType;
-use super::token::{Token, TokenType};
+use super::features::Feature;
+use super::fixes::Fix;
+use super::token::{Keyword, Token, TokenType};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum LexError {
     InvalidTokenError,
     UnterminatedStringError,
+    //A /* comment without the closing */
+    UnterminatedCommentError,
+    //Interpolations nested in each other deeper than the lexer allows
+    InterpolationDepthError,
+    //An exponent without digits, eg- 1e or 2e-
+    MalformedExponentError,
+    //A number literal too large for its type, eg- 3000000000 or 1e999
+    NumberTooLargeError,
 }
 
 impl LexError {
@@ -12,6 +22,18 @@ impl LexError {
         match self {
             Self::InvalidTokenError => "Unrecognized token",
             Self::UnterminatedStringError => "Unterminated string",
+            Self::UnterminatedCommentError => "Unterminated comment",
+            Self::InterpolationDepthError => "Interpolations are nested too deep",
+            Self::MalformedExponentError => "Expected digits after the exponent",
+            Self::NumberTooLargeError => "Number is too large",
+        }
+    }
+
+    //A fix for the error of the token at the position, if one is clear
+    pub fn get_fix(&self, position: (u32, u32), source: &str) -> Option<Fix> {
+        match self {
+            Self::UnterminatedStringError => Fix::close_string(position, source),
+            _ => None,
         }
     }
 }
@@ -21,16 +43,67 @@ pub enum ExprError {
     //ExpectedTokenError(expected, got)
     ExpectTokenError(ExpectType, Token),
     UnterminatedParenthesis(Token),
+    //A dot not followed by a field name or a tuple index
+    InvalidTupleIndex(Token),
+    //A type check or conversion not followed by a type name
+    ExpectTypeName(Token),
+    //An expression nested deeper than the parser allows, at the token exceeding the depth
+    TooDeep(Token),
+    //ExpectToken(expected: TokenType, got: Token), eg- a map key not followed by a colon
+    ExpectToken(TokenType, Token),
+    //A map or record literal without a closing brace, at its opening brace
+    UnterminatedMap(Token),
+    //A record literal giving a field twice, at the second one
+    DuplicateField(Token),
+    //A variable used or assigned before it is declared, when declarations are checked
+    UndeclaredVariable(Token),
+    //An error in the parameters or body of a function value
+    InvalidFunction(Box<StmtError>),
+    //++ or -- which does not follow a variable at the start of a statement, at the ++ or --
+    InvalidStep(Token),
+    //A statement following an expression on the same line, eg- print a print b, at the statement
+    MissingStmtEnd(Token),
+    //A = where an operator is expected, most likely meant as ==, eg- if a = 1
+    AssignInExpression(Token),
 }
 
 impl ExprError {
-    pub fn get_message(&self) -> &str {
+    pub fn get_message(&self) -> String {
         match self {
             Self::ExpectTokenError(expect_type, _) => match expect_type {
-                ExpectType::Operand => "Expected an operand",
-                ExpectType::Operator => "Expected an operator",
+                ExpectType::Operand => String::from("Expected an operand"),
+                ExpectType::Operator => String::from("Expected an operator"),
+            },
+            Self::UnterminatedParenthesis(_) => String::from("Unterminated parenthesis"),
+            Self::InvalidTupleIndex(_) => String::from("Expected a field name or tuple index"),
+            Self::ExpectTypeName(_) => String::from("Expected a type name"),
+            Self::TooDeep(_) => String::from("Expression is nested too deeply"),
+            Self::ExpectToken(expect_type, got_token) => format!(
+                "Expected {}, got {} instead",
+                expect_type.to_string(),
+                got_token.class.to_string()
+            ),
+            Self::UnterminatedMap(_) => String::from("Unterminated map"),
+            Self::DuplicateField(_) => String::from("Duplicate field name"),
+            Self::UndeclaredVariable(token) => match &token.class {
+                TokenType::Ident(name) => {
+                    format!("Variable {} is used before it is declared", name)
+                }
+                _ => String::from("Variable is used before it is declared"),
             },
-            Self::UnterminatedParenthesis(_) => "Unterminated parenthesis",
+            Self::InvalidFunction(error) => error.get_message(),
+            Self::InvalidStep(token) => format!(
+                "{} can only follow a variable, as a statement of its own, eg- i{}",
+                token.class.to_string(),
+                token.class.to_string()
+            ),
+            Self::MissingStmtEnd(token) => format!(
+                "Expected ; or a new line before {}",
+                token.class.to_string()
+            ),
+            Self::AssignInExpression(_) => {
+                String::from("Expected an operator, got = instead, use == to compare values")
+            }
         }
     }
 
@@ -38,6 +111,31 @@ impl ExprError {
         match self {
             Self::ExpectTokenError(_, token) => (token.line, token.start),
             Self::UnterminatedParenthesis(token) => (token.line, token.start),
+            Self::InvalidTupleIndex(token) => (token.line, token.start),
+            Self::ExpectTypeName(token) => (token.line, token.start),
+            Self::TooDeep(token) => (token.line, token.start),
+            Self::ExpectToken(_, token) => (token.line, token.start),
+            Self::UnterminatedMap(token) => (token.line, token.start),
+            Self::DuplicateField(token) => (token.line, token.start),
+            Self::UndeclaredVariable(token) => (token.line, token.start),
+            Self::InvalidFunction(error) => error.get_position(),
+            Self::InvalidStep(token) => (token.line, token.start),
+            Self::MissingStmtEnd(token) => (token.line, token.start),
+            Self::AssignInExpression(token) => (token.line, token.start),
+        }
+    }
+
+    pub fn get_fix(&self, source: &str) -> Option<Fix> {
+        match self {
+            Self::MissingStmtEnd(token) => Fix::end_stmt_before(token, source),
+            Self::AssignInExpression(token) => Some(Fix::new(
+                "Replace = with ==",
+                (token.line, token.start),
+                token.start + 1,
+                "==",
+            )),
+            Self::InvalidFunction(error) => error.get_fix(source),
+            _ => None,
         }
     }
 }
@@ -47,7 +145,7 @@ pub struct StmtErrors {
     pub errors: Vec<StmtError>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum StmtError {
     InvalidStartToken(Token),
     //ExpectToken(expected: TokenType, got: Token)
@@ -55,6 +153,24 @@ pub enum StmtError {
     InvalidExpression(ExprError),
     ExpectedExpression(Token),
     IncompleteStatement(Token),
+    //A block without a closing brace, at its opening brace
+    UnterminatedBlock(Token),
+    //A block nested deeper than the parser allows, at the opening brace exceeding the depth
+    TooDeep(Token),
+    //A function with two parameters of the same name, at the second one
+    DuplicateParameter(Token),
+    //A struct with two fields of the same name, at the second one
+    DuplicateField(Token),
+    //A return statement which is not inside a function body
+    ReturnOutsideFunction(Token),
+    //A break or continue which is not inside a loop of the current function
+    OutsideLoop(Token),
+    //An assignment to something other than a variable or an item of one, at the assign token
+    InvalidAssignTarget(Token),
+    //A variable declared with the name of one in a block around it, when shadowing is not allowed
+    ShadowedVariable(Token),
+    //ExperimentalFeature(Keyword, Feature), a statement of a feature which is not turned on
+    ExperimentalFeature(Token, Feature),
 }
 
 impl StmtError {
@@ -68,9 +184,37 @@ impl StmtError {
                     got_token.class.to_string()
                 )
             }
-            Self::InvalidExpression(error) => error.get_message().to_string(),
+            Self::InvalidExpression(error) => error.get_message(),
             Self::ExpectedExpression(_) => String::from("Expected an expression"),
             Self::IncompleteStatement(_) => String::from("Incomplete statement"),
+            Self::UnterminatedBlock(_) => String::from("Unterminated block"),
+            Self::TooDeep(_) => String::from("Blocks are nested too deeply"),
+            Self::DuplicateParameter(_) => String::from("Duplicate parameter name"),
+            Self::DuplicateField(_) => String::from("Duplicate field name"),
+            Self::ReturnOutsideFunction(_) => String::from("Return outside of a function"),
+            Self::OutsideLoop(token) => match token.class {
+                TokenType::Keyword(Keyword::Break) => String::from("Break outside of a loop"),
+                _ => String::from("Continue outside of a loop"),
+            },
+            Self::InvalidAssignTarget(_) => String::from("Invalid assignment target"),
+            Self::ShadowedVariable(token) => match &token.class {
+                TokenType::Ident(name) => {
+                    format!("Variable {} shadows a variable of an outer block", name)
+                }
+                _ => String::from("Variable shadows a variable of an outer block"),
+            },
+            Self::ExperimentalFeature(token, feature) => match &token.class {
+                TokenType::Keyword(keyword) => format!(
+                    "{} is experimental, turn it on with --features={}",
+                    keyword.name(),
+                    feature.name()
+                ),
+                _ => format!(
+                    "The {} feature is experimental, turn it on with --features={}",
+                    feature.name(),
+                    feature.name()
+                ),
+            },
         }
     }
 
@@ -81,6 +225,23 @@ impl StmtError {
             Self::InvalidExpression(error) => error.get_position(),
             Self::ExpectedExpression(token) => (token.line, token.start),
             Self::IncompleteStatement(token) => (token.line, token.start),
+            Self::UnterminatedBlock(token) => (token.line, token.start),
+            Self::TooDeep(token) => (token.line, token.start),
+            Self::DuplicateParameter(token) => (token.line, token.start),
+            Self::DuplicateField(token) => (token.line, token.start),
+            Self::ReturnOutsideFunction(token) => (token.line, token.start),
+            Self::OutsideLoop(token) => (token.line, token.start),
+            Self::InvalidAssignTarget(token) => (token.line, token.start),
+            Self::ShadowedVariable(token) => (token.line, token.start),
+            Self::ExperimentalFeature(token, _) => (token.line, token.start),
+        }
+    }
+
+    //A fix for the error which can be applied to the source, only given where the intent is clear
+    pub fn get_fix(&self, source: &str) -> Option<Fix> {
+        match self {
+            Self::InvalidExpression(error) => error.get_fix(source),
+            _ => None,
         }
     }
 }
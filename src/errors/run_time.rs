@@ -1,6 +1,40 @@
 #[derive(Debug, PartialEq, Clone)]
 pub enum LiteralOpError {
     InvalidTypeError,
+    //none was used where a value is needed, eg- in arithmetic or a comparison
+    NoneValueError,
     DivByZeroError,
+    //An integer result too large to be held by a number
+    OverflowError,
     UndefinedVariableError,
+    //A variable of a read-only scope was modified
+    ReadOnlyVariableError,
+    //A constant was reassigned, changed in place or declared again in its scope
+    ConstReassignmentError,
+    IndexOutOfBoundsError,
+    //A map was read with a key it does not have
+    UndefinedKeyError,
+    //Destructuring a tuple into a different number of identifiers
+    TupleLengthError,
+    UndefinedFunctionError,
+    //A function was called with the wrong number of arguments
+    ArgumentCountError,
+    //An argument has the right type but a value the function does not accept
+    InvalidArgumentError,
+    //Function calls were nested deeper than the executor allows, eg- by endless recursion
+    CallDepthError,
+    //A value could not be converted to another type
+    ConversionError,
+    //A record was made from a struct which is not declared
+    UndefinedStructError,
+    //A record was read or changed at a field its struct does not have
+    UndefinedFieldError,
+    //A record was made without giving every field of its struct a value
+    MissingFieldError,
+    //The store of store_set() could not be written
+    StoreError,
+    //Not a failure, exit ran inside a function so the expressions calling it are left unfinished
+    Exit,
+    //Not a failure, an error inside a try was caught in a function, so the expressions calling it are left unfinished
+    Thrown,
 }
@@ -0,0 +1,21 @@
+use std::io;
+
+//Errors found while reading a source file, before it can be lexed
+#[derive(Debug)]
+pub enum SourceError {
+    ReadError(io::Error),
+    //UnsupportedEncoding(name of the detected encoding)
+    UnsupportedEncoding(&'static str),
+}
+
+impl SourceError {
+    pub fn get_message(&self) -> String {
+        match self {
+            Self::ReadError(err) => format!("Failed to read file: {}", err),
+            Self::UnsupportedEncoding(encoding) => format!(
+                "Unsupported encoding {}, source files must be saved as UTF-8",
+                encoding
+            ),
+        }
+    }
+}
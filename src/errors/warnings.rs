@@ -0,0 +1,115 @@
+use super::stmt::Span;
+
+//Code that runs, but most likely does not do what was intended
+//found by the analyzer before the program runs
+#[derive(Debug, PartialEq)]
+pub enum Warning {
+    //An expression statement whose value is thrown away
+    UnusedExpression(Span),
+    //DeadStore(variable, store, next store)
+    //A value stored in a variable which is replaced before it is read
+    DeadStore(String, Span, Span),
+    //A division whose divisor is always zero
+    DivisionByZero(Span),
+    //Integer arithmetic whose result never fits in a number
+    Overflow(Span),
+    //EndlessLoop(variable, start, step, target, first step)
+    //A while loop comparing a variable with != to a value which its steps never reach
+    EndlessLoop(String, i32, i32, i32, Span),
+    //Deprecated(builtin, replacement, call)
+    //A call of a builtin which will be removed in a later version
+    Deprecated(String, &'static str, Span),
+    //The warnings below are only found by estel lint, they are about style and not mistakes
+    //NestedExpression(depth, limit, statement)
+    NestedExpression(usize, usize, Span),
+    //MagicNumber(number, statement), a number used without a name saying what it is
+    MagicNumber(String, Span),
+    //ShortName(name, declaration), a single letter name outside of a loop counter
+    ShortName(String, Span),
+    //LongStatement(nodes, limit, statement)
+    LongStatement(usize, usize, Span),
+}
+
+impl Warning {
+    pub fn get_message(&self) -> String {
+        match self {
+            Self::UnusedExpression(_) => String::from("Expression result is unused"),
+            Self::DeadStore(name, _, _) => format!("Value assigned to {} is never read", name),
+            Self::DivisionByZero(_) => String::from("Division by zero"),
+            Self::Overflow(_) => String::from("Integer overflow"),
+            Self::EndlessLoop(name, _, _, target, _) => {
+                format!("Loop condition {} != {} is never false", name, target)
+            }
+            Self::Deprecated(name, _, _) => format!("{} is deprecated", name),
+            Self::NestedExpression(depth, _, _) => format!("Expression is nested {} deep", depth),
+            Self::MagicNumber(number, _) => format!("Magic number {}", number),
+            Self::ShortName(name, _) => format!("Single letter name {}", name),
+            Self::LongStatement(nodes, _, _) => format!("Statement has {} expression nodes", nodes),
+        }
+    }
+
+    //A suggestion for fixing the code
+    pub fn get_hint(&self) -> String {
+        match self {
+            Self::UnusedExpression(_) => {
+                String::from("use print to show the result, or let to keep it in a variable")
+            }
+            Self::DeadStore(name, _, next) => format!(
+                "{} is assigned again at line {} before it is read, remove one of the assignments",
+                name, next.line
+            ),
+            Self::DivisionByZero(_) => String::from("the divisor of this division is always 0"),
+            Self::Overflow(_) => String::from(
+                "the result does not fit in a number, which is a 32 bit integer, use a float instead",
+            ),
+            //the target is either skipped over or behind the start
+            Self::EndlessLoop(name, start, step, target, _) => {
+                if (*target > *start) == (*step > 0) {
+                    format!(
+                        "{} starts at {} and changes by {}, so it skips over {}, compare with < or > instead",
+                        name, start, step, target
+                    )
+                } else {
+                    format!(
+                        "{} starts at {} and changes by {}, so it moves away from {}",
+                        name, start, step, target
+                    )
+                }
+            }
+            Self::Deprecated(name, replacement, _) => format!(
+                "use {} instead, {} will be removed in a later version",
+                replacement, name
+            ),
+            Self::NestedExpression(_, limit, _) => format!(
+                "keep parts of the expression in variables, expressions should be at most {} deep",
+                limit
+            ),
+            Self::MagicNumber(number, _) => format!(
+                "name the number with a constant, eg- const LIMIT = {}",
+                number
+            ),
+            Self::ShortName(name, _) => format!(
+                "use a name saying what {} holds, single letters are best kept for loop counters",
+                name
+            ),
+            Self::LongStatement(_, limit, _) => format!(
+                "split it into smaller statements, statements should have at most {} nodes",
+                limit
+            ),
+        }
+    }
+
+    pub fn get_position(&self) -> (u32, u32) {
+        match self {
+            Self::UnusedExpression(span) => (span.line, span.start),
+            Self::DeadStore(_, span, _) => (span.line, span.start),
+            Self::DivisionByZero(span) | Self::Overflow(span) => (span.line, span.start),
+            Self::EndlessLoop(.., span) => (span.line, span.start),
+            Self::Deprecated(_, _, span) => (span.line, span.start),
+            Self::NestedExpression(_, _, span) | Self::LongStatement(_, _, span) => {
+                (span.line, span.start)
+            }
+            Self::MagicNumber(_, span) | Self::ShortName(_, span) => (span.line, span.start),
+        }
+    }
+}
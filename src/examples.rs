@@ -0,0 +1,50 @@
+use colored::Colorize;
+use std::io::{self, Write};
+
+//An example program embedded in the binary, run with estel examples [name]
+pub struct Example {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub source: &'static str,
+}
+
+//The sources live in code/examples, next to a snapshot of their output checked by the tests
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        name: "fibonacci",
+        description: "The first fibonacci numbers, stepping a pair with tuple destructuring",
+        source: include_str!("../code/examples/fibonacci.est"),
+    },
+    Example {
+        name: "fizzbuzz",
+        description: "Fizz, buzz or the number for each count, picked with a for loop and %",
+        source: include_str!("../code/examples/fizzbuzz.est"),
+    },
+    Example {
+        name: "string_art",
+        description: "A wall of bricks drawn with string repetition",
+        source: include_str!("../code/examples/string_art.est"),
+    },
+    Example {
+        name: "tuples",
+        description: "Creating, destructuring, swapping and indexing tuples",
+        source: include_str!("../code/examples/tuples.est"),
+    },
+];
+
+pub fn find_example(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.name == name)
+}
+
+//List the examples, to stdout when they were asked for or to stderr after an error
+pub fn print_examples(out: &mut impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        "Run an example with estel examples [name], the sources are in code/examples".green()
+    )?;
+    for example in EXAMPLES {
+        writeln!(out, "  {:<12}{}", example.name, example.description)?;
+    }
+    Ok(())
+}
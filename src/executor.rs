@@ -0,0 +1,2269 @@
+use crate::errors::LiteralOpError;
+use crate::output::{format_table, progress_bar, Event, OutputMode, Phase, Progress};
+use crate::parser::analyzer::IdentFinder;
+use crate::parser::builtins::{call_builtin, take_args};
+use crate::parser::expr::Expr;
+use crate::parser::map::Map;
+use crate::parser::record::Record;
+use crate::parser::semantics::Semantics;
+use crate::parser::stmt::{Accessor, Block, Function, Span, Stmt, Struct};
+use crate::shared::SharedGlobals;
+use crate::store::{MemoryStore, Store};
+use crate::token::Literal;
+use std::cell::{RefCell, RefMut};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+//The deepest function calls can be nested, calls are run recursively so deeper calls would overflow the stack
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+//The most digits floats can be printed with after the point
+pub const MAX_PRECISION: usize = 32;
+
+//The stack size to run programs with, enough for MAX_CALL_DEPTH calls even in debug builds
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+//The iterations a while loop can run in education mode before it is warned about as possibly endless
+pub const EDUCATION_ITERATIONS: usize = 100_000;
+
+//A single level of variables
+#[derive(Debug, Default, Clone)]
+pub struct Scope {
+    //Each variable is kept in a cell of its own, shared with the function values made where it is seen
+    vars: HashMap<String, Rc<RefCell<Literal>>>,
+    functions: HashMap<String, Rc<Function>>,
+    structs: HashMap<String, Rc<Struct>>,
+    //Variables of a frozen scope can't be declared or modified
+    frozen: bool,
+    //The names of the variables declared with const
+    consts: HashSet<String>,
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        Self {
+            vars: HashMap::new(),
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            frozen: false,
+            consts: HashSet::new(),
+        }
+    }
+
+    //Copy the scope with cells of its own, changing the variables of the copy leaves the original as it was
+    fn detached(&self) -> Self {
+        let vars = self
+            .vars
+            .iter()
+            .map(|(name, cell)| {
+                (
+                    name.to_owned(),
+                    Rc::new(RefCell::new(cell.borrow().clone())),
+                )
+            })
+            .collect();
+        Self {
+            vars,
+            ..self.clone()
+        }
+    }
+
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn get_var(&self, name: &str) -> Option<Literal> {
+        self.vars.get(name).map(|cell| cell.borrow().clone())
+    }
+
+    //Declare a variable, in a new cell so function values made before keep the variable they saw
+    pub fn insert_var(&mut self, name: &str, value: Literal) -> Result<(), LiteralOpError> {
+        self.check_writable(name)?;
+        self.vars
+            .insert(name.to_owned(), Rc::new(RefCell::new(value)));
+        Ok(())
+    }
+
+    //Assign a variable, changing it for the function values sharing it too
+    pub fn set_var(&mut self, name: &str, value: Literal) -> Result<(), LiteralOpError> {
+        self.check_writable(name)?;
+        match self.vars.get(name) {
+            Some(cell) => *cell.borrow_mut() = value,
+            None => {
+                self.vars
+                    .insert(name.to_owned(), Rc::new(RefCell::new(value)));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_writable(&self, name: &str) -> Result<(), LiteralOpError> {
+        if self.frozen {
+            return Err(LiteralOpError::ReadOnlyVariableError);
+        }
+        if self.consts.contains(name) {
+            return Err(LiteralOpError::ConstReassignmentError);
+        }
+        Ok(())
+    }
+
+    //Declare a variable which can't be assigned again while the scope lasts
+    pub fn insert_const(&mut self, name: &str, value: Literal) -> Result<(), LiteralOpError> {
+        self.insert_var(name, value)?;
+        self.consts.insert(name.to_owned());
+        Ok(())
+    }
+
+    //Get a variable to change in place, eg- to set an item of a map
+    pub fn get_var_mut(&mut self, name: &str) -> Result<RefMut<'_, Literal>, LiteralOpError> {
+        self.check_writable(name)?;
+        self.vars
+            .get(name)
+            .map(|cell| cell.borrow_mut())
+            .ok_or(LiteralOpError::UndefinedVariableError)
+    }
+
+    pub fn contains_var(&self, name: &str) -> bool {
+        self.vars.contains_key(name)
+    }
+
+    pub fn get_fn(&self, name: &str) -> Option<&Rc<Function>> {
+        self.functions.get(name)
+    }
+
+    pub fn insert_fn(&mut self, function: Rc<Function>) -> Result<(), LiteralOpError> {
+        if self.frozen {
+            return Err(LiteralOpError::ReadOnlyVariableError);
+        }
+        self.functions.insert(function.name.to_owned(), function);
+        Ok(())
+    }
+
+    pub fn get_struct(&self, name: &str) -> Option<&Rc<Struct>> {
+        self.structs.get(name)
+    }
+
+    pub fn insert_struct(&mut self, declared: Rc<Struct>) -> Result<(), LiteralOpError> {
+        if self.frozen {
+            return Err(LiteralOpError::ReadOnlyVariableError);
+        }
+        self.structs.insert(declared.name.to_owned(), declared);
+        Ok(())
+    }
+}
+
+//A function value, made by a function expression, eg- let double = fn(x) { return x * 2 }
+//the local variables it sees are shared with the scopes they are declared in, so changes made
+//by the function or by the code around it are seen by both, the global ones are read when it is called
+#[derive(Clone)]
+pub struct Closure {
+    pub function: Rc<Function>,
+    captured: Rc<Scope>,
+}
+
+//a function value is only equal to the copies made of it
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function) && Rc::ptr_eq(&self.captured, &other.captured)
+    }
+}
+
+impl PartialOrd for Closure {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self == other).then_some(Ordering::Equal)
+    }
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Closure(fn({}))", self.function.params.join(", "))
+    }
+}
+
+//How execution continues after a statement
+#[derive(Debug, PartialEq)]
+pub enum Flow {
+    //Go on to the next statement, with the value of an expression statement
+    Next(Option<Literal>),
+    //Leave the running function with a value
+    Return(Literal),
+    //Leave the innermost loop
+    Break,
+    //Skip to the next iteration of the innermost loop
+    Continue,
+    //Stop the program with an exit code
+    Exit(i32),
+    //Leave the blocks and functions up to the innermost try, after a runtime error inside it
+    Throw,
+}
+
+pub struct Executor {
+    //Stack of scopes, the prelude scopes are at the bottom and the last scope is the innermost
+    scopes: Vec<Scope>,
+    //The number of prelude and global scopes, which are visible from every function
+    globals: usize,
+    //The local scopes of each caller, set aside while the function it called runs
+    frames: Vec<Vec<Scope>>,
+    //The digits printed after the point of floats, set with set_precision(), all digits are printed if None
+    pub float_precision: Option<usize>,
+    pub semantics: Semantics,
+    //Whether printed values and errors are written as text or as JSON events
+    pub output: OutputMode,
+    //Warn once when a single run of a while loop reaches this many iterations, eg- in education mode
+    //beginners can then spot a loop which never ends, without it being stopped
+    pub iteration_warning: Option<usize>,
+    //Stop the program like exit(1) once its loops have run this many times in all, eg- to check
+    //answers which may never end, None for no limit
+    pub loop_limit: Option<usize>,
+    //The runs of loop bodies in the program running, counted against the loop limit
+    iterations: usize,
+    //The span of the innermost statement being run
+    span: Span,
+    //Where input() reads lines from, stdin if None
+    input: Option<Box<dyn BufRead>>,
+    //Where the text output of programs is written, stdout if None
+    stdout: Option<Box<dyn Write>>,
+    //Read-only variables shared with other executors, below the prelude scopes
+    shared: Option<SharedGlobals>,
+    //The callbacks scheduled with after() and every(), run by run_events()
+    timers: Vec<Timer>,
+    //The id given to the next timer
+    next_timer: i32,
+    //Where store_get() and store_set() keep values between runs
+    store: Box<dyn Store>,
+    //When progress() last showed the progress of a loop
+    progress: Progress,
+    //The code of the exit statement which stopped the program, if one did
+    exit_code: Option<i32>,
+    //The number of try blocks being run, runtime errors are caught instead of reported inside them
+    tries: usize,
+    //The error caught inside a try block, bound to the variable of its catch block
+    thrown: Option<Literal>,
+    //Whether the running calls are being left after the call depth limit was reached outside of a try
+    //they are left like an exit, up to the statement which made the outermost call
+    unwinding: bool,
+}
+
+//An accessor of an index assignment, with its index solved
+enum Step<'a> {
+    Key(Literal),
+    Field(&'a str),
+}
+
+//A function value to call once its time comes, and again every interval if it repeats
+struct Timer {
+    id: i32,
+    due: Instant,
+    interval: Option<Duration>,
+    callback: Closure,
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Executor {
+    pub fn new() -> Self {
+        Self::with_prelude(Vec::new())
+    }
+
+    //Create an executor with prelude scopes below the global scope, the prelude scopes are frozen
+    //later prelude scopes shadow the earlier ones, and scripts can shadow them with let
+    pub fn with_prelude(prelude: Vec<Scope>) -> Self {
+        let mut scopes = prelude;
+        scopes.iter_mut().for_each(Scope::freeze);
+        scopes.push(Scope::new());
+        Self {
+            globals: scopes.len(),
+            scopes,
+            frames: Vec::new(),
+            float_precision: None,
+            semantics: Semantics::default(),
+            output: OutputMode::default(),
+            iteration_warning: None,
+            loop_limit: None,
+            iterations: 0,
+            span: Span::default(),
+            input: None,
+            stdout: None,
+            shared: None,
+            timers: Vec::new(),
+            next_timer: 1,
+            store: Box::new(MemoryStore::default()),
+            progress: Progress::default(),
+            exit_code: None,
+            tries: 0,
+            thrown: None,
+            unwinding: false,
+        }
+    }
+
+    //Keep the values of store_set() in another store, eg- a file so they last between runs
+    pub fn set_store(&mut self, store: impl Store + 'static) {
+        self.store = Box::new(store);
+    }
+
+    //Let the programs read the variables shared with other executors
+    pub fn set_shared_globals(&mut self, shared: SharedGlobals) {
+        self.shared = Some(shared);
+    }
+
+    //Copy a shared variable, for when no scope has the name
+    pub fn get_shared(&self, name: &str) -> Option<Literal> {
+        self.shared.as_ref().and_then(|shared| shared.get(name))
+    }
+
+    //The error for a variable which can't be changed because no scope has it
+    fn missing_var_error(&self, name: &str) -> LiteralOpError {
+        match &self.shared {
+            Some(shared) if shared.contains(name) => LiteralOpError::ReadOnlyVariableError,
+            _ => LiteralOpError::UndefinedVariableError,
+        }
+    }
+
+    //Read the lines of input() from another reader, eg- to give a program input in tests
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.input = Some(Box::new(input));
+    }
+
+    //Write the text output of programs to another writer, eg- to check what a program prints
+    pub fn set_stdout(&mut self, stdout: impl Write + 'static) {
+        self.stdout = Some(Box::new(stdout));
+    }
+
+    //Write text output of the program, flushed so prompts show before input is read
+    fn write_stdout(&mut self, text: &str) {
+        match &mut self.stdout {
+            Some(stdout) => {
+                let _ = stdout.write_all(text.as_bytes());
+                let _ = stdout.flush();
+            }
+            None => {
+                print!("{}", text);
+                let _ = io::stdout().flush();
+            }
+        }
+    }
+
+    //print_expr_result: whether to print the result of an an Expr statement (printed in prompt mode)
+    //returns the value of the final statement if it is an expression, so embedders can read a result
+    pub fn execute_code(&mut self, block: &Block, print_expr_result: bool) -> Option<Literal> {
+        self.exit_code = None;
+        self.iterations = 0;
+        match self.run_stmts(block, print_expr_result) {
+            Flow::Next(value) => value,
+            Flow::Return(value) => Some(value),
+            //the parser only allows break and continue inside loops
+            //errors are only thrown inside a try, which catches them
+            Flow::Break | Flow::Continue | Flow::Exit(_) | Flow::Throw => None,
+        }
+    }
+
+    //Drop the local scopes and call frames of a program stopped partway, eg- by a panic
+    pub fn reset_frames(&mut self) {
+        self.scopes.truncate(self.globals);
+        self.frames.clear();
+        self.tries = 0;
+        self.thrown = None;
+        self.unwinding = false;
+    }
+
+    //The exit code of the last program run, if it was stopped by an exit statement
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    //run the statements of a block in order, stopping early at a return, break, continue, exit or error in a try
+    fn run_stmts(&mut self, block: &Block, print_expr_result: bool) -> Flow {
+        let mut flow = Flow::Next(None);
+        for (stmt, span) in block.iter() {
+            self.span = *span;
+            flow = self.execute_statement(stmt, print_expr_result);
+            //an exit in a called function ends the statement calling it too
+            if let Some(code) = self.exit_code {
+                return Flow::Exit(code);
+            }
+            if self.thrown.is_some() {
+                return Flow::Throw;
+            }
+            if self.unwinding {
+                if !self.frames.is_empty() {
+                    return Flow::Throw;
+                }
+                self.unwinding = false;
+            }
+            if !matches!(flow, Flow::Next(_)) {
+                break;
+            }
+        }
+        flow
+    }
+
+    //run a nested block in its own scope, the value of its last statement is dropped
+    fn run_block(&mut self, block: &Block, print_expr_result: bool) -> Flow {
+        self.scopes.push(Scope::new());
+        let flow = self.run_stmts(block, print_expr_result);
+        self.scopes.pop();
+        match flow {
+            Flow::Next(_) => Flow::Next(None),
+            flow => flow,
+        }
+    }
+
+    //run a for loop inside the scope made for it, the init and step never print their values
+    fn run_for(
+        &mut self,
+        (init, condition, step): (Option<&Stmt>, Option<&Expr>, Option<&Stmt>),
+        body: &Block,
+        print_expr_result: bool,
+    ) -> Flow {
+        if let Some(init) = init {
+            self.execute_statement(init, false);
+        }
+        while condition.is_none_or(|condition| self.check_condition(condition)) {
+            if !self.count_iteration() {
+                return Flow::Exit(1);
+            }
+            match self.run_block(body, print_expr_result) {
+                Flow::Break => break,
+                flow @ (Flow::Return(_) | Flow::Exit(_) | Flow::Throw) => return flow,
+                _ => {}
+            }
+            if let Some(step) = step {
+                self.execute_statement(step, false);
+            }
+        }
+        Flow::Next(None)
+    }
+
+    //solve the condition of an if or while, a failing condition is reported and counts as false
+    fn check_condition(&mut self, condition: &Expr) -> bool {
+        //the step of a for loop may have called exit, failed inside a try or reached the call depth limit
+        if self.exit_code.is_some() || self.thrown.is_some() || self.unwinding {
+            return false;
+        }
+        match condition
+            .solve(self)
+            .and_then(|value| value.condition(&self.semantics))
+        {
+            Ok(result) => result,
+            Err(err) => {
+                self.report_error(err);
+                false
+            }
+        }
+    }
+
+    //the flow carries the value of an Expr statement, other statements have no value
+    pub fn execute_statement(&mut self, stmt: &Stmt, print_expr_result: bool) -> Flow {
+        match stmt {
+            Stmt::Print(expr) => {
+                let res = expr.solve(self);
+                match res {
+                    Ok(literal) => self.print_value(&literal, Event::Print),
+                    Err(err) => {
+                        self.report_error(err);
+                    }
+                }
+            }
+            Stmt::Assign(name, expr) => {
+                let res = expr.solve(self);
+                match res {
+                    Ok(value) => {
+                        if let Err(err) = self.insert_var(name, value) {
+                            self.report_assign_error(name, err);
+                        }
+                    }
+                    Err(err) => {
+                        self.report_error(err);
+                    }
+                }
+            }
+            Stmt::Const(name, expr) => match expr.solve(self) {
+                Ok(value) => {
+                    if let Err(err) = self.insert_const(name, value) {
+                        self.report_assign_error(name, err);
+                    }
+                }
+                Err(err) => self.report_error(err),
+            },
+            Stmt::Destructure(names, expr) => {
+                let res = expr.solve(self);
+                match res {
+                    Ok(Literal::Tuple(items)) if items.len() == names.len() => {
+                        for (name, value) in names.iter().zip(items) {
+                            if let Err(err) = self.insert_var(name, value) {
+                                self.report_assign_error(name, err);
+                            }
+                        }
+                    }
+                    Ok(Literal::Tuple(_)) => self.report_error(LiteralOpError::TupleLengthError),
+                    Ok(_) => self.report_error(LiteralOpError::InvalidTypeError),
+                    Err(err) => {
+                        self.report_error(err);
+                    }
+                }
+            }
+            //Reassign only if the current variable exists in scope
+            Stmt::Reassign(name, expr) => {
+                let res = expr.solve(self);
+                match res {
+                    Ok(value) => {
+                        if let Err(err) = self.insert_if_exists(name, value) {
+                            self.report_assign_error(name, err);
+                        }
+                    }
+                    Err(err) => {
+                        self.report_error(err);
+                    }
+                }
+            }
+            Stmt::IndexAssign(name, accessors, expr) => {
+                match self.assign_index(name, accessors, expr) {
+                    Ok(()) => {}
+                    //an undefined variable error may also come from a map in the variable
+                    Err(LiteralOpError::UndefinedVariableError) if self.get_var(name).is_some() => {
+                        self.report_error(LiteralOpError::UndefinedVariableError);
+                    }
+                    Err(err) => self.report_assign_error(name, err),
+                }
+            }
+            Stmt::Block(block) => return self.run_block(block, print_expr_result),
+            Stmt::If(condition, then, otherwise) => {
+                if self.check_condition(condition) {
+                    return self.run_block(then, print_expr_result);
+                }
+                if let Some(otherwise) = otherwise {
+                    return self.run_block(otherwise, print_expr_result);
+                }
+            }
+            Stmt::While(condition, body) => {
+                let span = self.span;
+                let mut iterations = 0;
+                while self.check_condition(condition) {
+                    iterations += 1;
+                    self.check_iterations(iterations, condition, span);
+                    if !self.count_iteration() {
+                        return Flow::Exit(1);
+                    }
+                    match self.run_block(body, print_expr_result) {
+                        Flow::Break => break,
+                        flow @ (Flow::Return(_) | Flow::Exit(_) | Flow::Throw) => return flow,
+                        _ => {}
+                    }
+                }
+            }
+            Stmt::DoWhile(condition, body) => {
+                let span = self.span;
+                let mut iterations = 0;
+                loop {
+                    iterations += 1;
+                    self.check_iterations(iterations, condition, span);
+                    if !self.count_iteration() {
+                        return Flow::Exit(1);
+                    }
+                    match self.run_block(body, print_expr_result) {
+                        Flow::Break => break,
+                        flow @ (Flow::Return(_) | Flow::Exit(_) | Flow::Throw) => return flow,
+                        _ => {}
+                    }
+                    if !self.check_condition(condition) {
+                        break;
+                    }
+                }
+            }
+            //the loop gets a scope of its own, so a variable declared by the init ends with the loop
+            Stmt::For(init, condition, step, body) => {
+                self.scopes.push(Scope::new());
+                let header = (init.as_deref(), condition.as_ref(), step.as_deref());
+                let flow = self.run_for(header, body, print_expr_result);
+                self.scopes.pop();
+                return flow;
+            }
+            //the catch block runs in a scope holding the error, after the scopes of the body are left
+            Stmt::Try(body, name, catch) => {
+                self.tries += 1;
+                let flow = self.run_block(body, print_expr_result);
+                self.tries -= 1;
+                let Some(error) = self.thrown.take() else {
+                    return flow;
+                };
+                self.scopes.push(Scope::new());
+                //the scope is new, so the name can always be declared
+                let _ = self.insert_var(name, error);
+                let flow = self.run_block(catch, print_expr_result);
+                self.scopes.pop();
+                return flow;
+            }
+            //an error thrown outside of any try stops the program, like a failed assertion
+            Stmt::Throw(expr) => {
+                let value = match expr.solve(self) {
+                    Ok(value) => value,
+                    //the error solving the value is raised instead
+                    Err(err) => {
+                        self.report_error(err);
+                        return Flow::Next(None);
+                    }
+                };
+                let message = self.format_value(&value);
+                if self.tries == 0 {
+                    self.report_located_error(message);
+                    self.exit_code = Some(1);
+                    return Flow::Exit(1);
+                }
+                let mut error = self.error_value(String::from("UserError"), message);
+                error.insert(Literal::String(String::from("value")), value);
+                self.thrown = Some(Literal::Map(error));
+                return Flow::Throw;
+            }
+            Stmt::Break => return Flow::Break,
+            Stmt::Continue => return Flow::Continue,
+            Stmt::FnDecl(function) => {
+                if self
+                    .scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert_fn(function.clone())
+                    .is_err()
+                {
+                    let message = format!("Error: Function {} is read-only", function.name);
+                    self.report_run_error(LiteralOpError::ReadOnlyVariableError, message);
+                }
+            }
+            Stmt::Struct(declared) => {
+                if self
+                    .scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert_struct(declared.clone())
+                    .is_err()
+                {
+                    let message = format!("Error: Struct {} is read-only", declared.name);
+                    self.report_run_error(LiteralOpError::ReadOnlyVariableError, message);
+                }
+            }
+            Stmt::Return(expr) => {
+                //a bare return, or one whose value failed, returns none
+                let mut value = Literal::None;
+                if let Some(expr) = expr {
+                    match expr.solve(self) {
+                        Ok(literal) => value = literal,
+                        Err(err) => self.report_error(err),
+                    }
+                }
+                return Flow::Return(value);
+            }
+            //a false condition stops the program like exit(1), after reporting where it failed
+            Stmt::Assert(condition, message) => {
+                let holds = condition
+                    .solve(self)
+                    .and_then(|value| value.condition(&self.semantics));
+                let error = match holds {
+                    Ok(true) => None,
+                    Ok(false) => match message.as_ref().map(|message| message.solve(self)) {
+                        None => Some(String::from("Assertion failed")),
+                        Some(Ok(message)) => {
+                            Some(format!("Assertion failed: {}", self.format_value(&message)))
+                        }
+                        Some(Err(err)) => Some(format!("Assertion failed: {:?}", err)),
+                    },
+                    Err(err) => Some(format!("{:?}", err)),
+                };
+                if let Some(error) = error {
+                    self.report_located_error(error);
+                    self.exit_code = Some(1);
+                    return Flow::Exit(1);
+                }
+            }
+            Stmt::Exit(expr) => {
+                let code = match expr.as_ref().map(|expr| expr.solve(self)) {
+                    None => 0,
+                    Some(Ok(Literal::Number(code))) => code,
+                    //a failing exit still stops the program, as a failure
+                    Some(Ok(_)) => {
+                        self.report_error(LiteralOpError::InvalidTypeError);
+                        1
+                    }
+                    Some(Err(err)) => {
+                        self.report_error(err);
+                        1
+                    }
+                };
+                self.exit_code = Some(code);
+                return Flow::Exit(code);
+            }
+            Stmt::Expr(expr) => {
+                let res = expr.solve(self);
+                match res {
+                    Ok(literal) => {
+                        if print_expr_result {
+                            self.print_value(&literal, Event::Result);
+                        }
+                        return Flow::Next(Some(literal));
+                    }
+                    Err(err) => {
+                        self.report_error(err);
+                    }
+                }
+            }
+        }
+        Flow::Next(None)
+    }
+
+    //Set an item of a map or a field of a record held by a variable, the value is solved before the indexes
+    //each accessor before the last must lead to a map already holding the key or a record with the field
+    fn assign_index(
+        &mut self,
+        name: &str,
+        accessors: &[Accessor],
+        expr: &Expr,
+    ) -> Result<(), LiteralOpError> {
+        let value = expr.solve(self)?;
+        let mut steps = Vec::new();
+        for accessor in accessors {
+            steps.push(match accessor {
+                Accessor::Index(index) => Step::Key(index.solve(self)?),
+                Accessor::Field(field) => Step::Field(field),
+            });
+        }
+        //the parser never makes an index assignment without an accessor
+        let last = steps.pop().unwrap();
+        let mut variable = self.get_var_mut(name)?;
+        let mut target = &mut *variable;
+        for step in steps.iter() {
+            target = match step {
+                Step::Key(key) => target.index_mut(key)?,
+                Step::Field(field) => target.field_mut(field)?,
+            };
+        }
+        match last {
+            Step::Key(key) => target.set_index(key, value),
+            Step::Field(field) => {
+                *target.field_mut(field)? = value;
+                Ok(())
+            }
+        }
+    }
+
+    //Warn when a loop reaches the iteration warning, showing the variables its condition reads
+    //span: the span of the loop statement
+    //Count a run of a loop body, returns false once the loop limit is passed and the program stopped
+    fn count_iteration(&mut self) -> bool {
+        self.iterations += 1;
+        match self.loop_limit {
+            Some(limit) if self.iterations > limit => {
+                self.report_located_error(format!(
+                    "Loops ran more than {} times, the program was stopped",
+                    limit
+                ));
+                self.exit_code = Some(1);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    fn check_iterations(&self, iterations: usize, condition: &Expr, span: Span) {
+        if self.iteration_warning != Some(iterations) {
+            return;
+        }
+        let mut names = IdentFinder::idents(condition);
+        names.dedup();
+        let values: Vec<String> = names
+            .iter()
+            .map(|name| match self.get_var(name) {
+                Some(value) => format!("{} = {}", name, self.format_value(&value)),
+                None => format!("{} is not defined", name),
+            })
+            .collect();
+        let message = format!("Loop has run {} times", iterations);
+        let hint = if values.is_empty() {
+            String::from("the condition reads no variables, check that it can become false")
+        } else {
+            format!(
+                "check that the condition can become false, it reads {}",
+                values.join(", ")
+            )
+        };
+        match self.output {
+            OutputMode::Text => {
+                eprintln!(
+                    "Warning: {} at line {} position {}",
+                    message, span.line, span.start
+                );
+                eprintln!("help: {}", hint);
+            }
+            OutputMode::Json => Event::Warning(message, hint, (span.line, span.start)).emit(),
+        }
+    }
+
+    //Report why a variable could not be declared or assigned
+    fn report_assign_error(&mut self, name: &str, err: LiteralOpError) {
+        let message = match &err {
+            LiteralOpError::UndefinedVariableError => {
+                format!("Error: Variable {} does not exist in scope", name)
+            }
+            LiteralOpError::ReadOnlyVariableError => {
+                format!("Error: Variable {} is read-only", name)
+            }
+            LiteralOpError::ConstReassignmentError => {
+                format!("Error: Variable {} is a constant", name)
+            }
+            err => format!("{:?}", err),
+        };
+        self.report_run_error(err, message);
+    }
+
+    //Print a value with the float precision of the program
+    //event: the kind of event the value is reported as in the Json output mode
+    fn print_value(&mut self, value: &Literal, event: fn(String) -> Event) {
+        let text = self.format_value(value);
+        match self.output {
+            OutputMode::Text => self.write_stdout(&format!("{}\n", text)),
+            OutputMode::Json => event(text).emit(),
+        }
+    }
+
+    //Format a value like print shows it, with the precision set for floats
+    pub fn format_value(&self, value: &Literal) -> String {
+        match self.float_precision {
+            Some(precision) => format!("{:.*}", precision, value),
+            None => value.to_string(),
+        }
+    }
+
+    //Report an error with the position of the statement running, eg- a failed assertion
+    fn report_located_error(&self, message: String) {
+        if self.exit_code.is_some() {
+            return;
+        }
+        let span = self.span;
+        match self.output {
+            OutputMode::Text => eprintln!(
+                "Error: {} at line {} position {}",
+                message, span.line, span.start
+            ),
+            OutputMode::Json => {
+                Event::Error(Phase::Run, message, Some((span.line, span.start)), None).emit()
+            }
+        }
+    }
+
+    //Report an error found while running
+    fn report_error(&mut self, err: LiteralOpError) {
+        self.report_run_error(err.clone(), format!("{:?}", err));
+    }
+
+    //Report an error found while running, errors go to stderr unless they are JSON events
+    //inside a try the first error is kept for its catch block instead
+    //nothing is reported while an exit or the call depth limit unwinds the calls it was reached in
+    fn report_run_error(&mut self, kind: LiteralOpError, message: String) {
+        if self.exit_code.is_some() || self.unwinding {
+            return;
+        }
+        if self.tries > 0 {
+            if self.thrown.is_none() {
+                let error = self.error_value(format!("{:?}", kind), message);
+                self.thrown = Some(Literal::Map(error));
+            }
+            return;
+        }
+        match self.output {
+            OutputMode::Text => eprintln!("{}", message),
+            OutputMode::Json => Event::Error(Phase::Run, message, None, None).emit(),
+        }
+    }
+
+    //The value a catch block gets for an error, a map of its kind, message and where it happened
+    fn error_value(&self, kind: String, message: String) -> Map {
+        let entry = |key: &str, value| (Literal::String(key.to_owned()), value);
+        Map::from_iter([
+            entry("kind", Literal::String(kind)),
+            entry("message", Literal::String(message)),
+            entry("line", Literal::Number(self.span.line as i32)),
+            entry("position", Literal::Number(self.span.start as i32)),
+        ])
+    }
+
+    //Call a function by its name, functions declared by the program shadow the builtins
+    //a variable holding a function value can be called like a declared function
+    pub fn call(&mut self, name: &str, args: Vec<Literal>) -> Result<Literal, LiteralOpError> {
+        if let Some(Literal::Function(closure)) = self.get_var(name) {
+            return self.run_function(&closure.function, Some(&closure.captured), args);
+        }
+        if let Some(function) = self.get_fn(name) {
+            return self.call_function(&function, args);
+        }
+        //calling a struct makes a record, with the arguments given to the fields in order
+        if let Some(declared) = self.get_struct(name) {
+            if args.len() != declared.fields.len() {
+                return Err(LiteralOpError::ArgumentCountError);
+            }
+            let fields = declared.fields.iter().cloned().zip(args).collect();
+            return Ok(Literal::Record(Record::new(name, fields)));
+        }
+        match name {
+            //set the digits printed after the point of floats, a negative precision prints all digits
+            "set_precision" => {
+                let [precision] = take_args(args)?;
+                self.float_precision = match precision {
+                    Literal::Number(precision) if precision < 0 => None,
+                    Literal::Number(precision) if precision as usize <= MAX_PRECISION => {
+                        Some(precision as usize)
+                    }
+                    Literal::Number(_) => return Err(LiteralOpError::InvalidArgumentError),
+                    _ => return Err(LiteralOpError::InvalidTypeError),
+                };
+                Ok(Literal::None)
+            }
+            //print a prompt and read a line of input, none at the end of input
+            "input" => {
+                if args.len() > 1 {
+                    return Err(LiteralOpError::ArgumentCountError);
+                }
+                if let Some(prompt) = args.first() {
+                    let prompt = self.format_value(prompt);
+                    match self.output {
+                        OutputMode::Text => self.write_stdout(&prompt),
+                        OutputMode::Json => Event::Print(prompt).emit(),
+                    }
+                }
+                Ok(self.read_line().map_or(Literal::None, Literal::String))
+            }
+            //print a tuple of rows in aligned columns, under a tuple of headers if given
+            //numbers are aligned to the right of their column, other values to the left
+            "print_table" => {
+                if args.is_empty() || args.len() > 2 {
+                    return Err(LiteralOpError::ArgumentCountError);
+                }
+                let mut args = args.into_iter();
+                let Some(Literal::Tuple(rows)) = args.next() else {
+                    return Err(LiteralOpError::InvalidTypeError);
+                };
+                let headers = match args.next() {
+                    Some(Literal::Tuple(headers)) => Some(
+                        headers
+                            .iter()
+                            .map(|header| self.format_value(header))
+                            .collect::<Vec<_>>(),
+                    ),
+                    Some(_) => return Err(LiteralOpError::InvalidTypeError),
+                    None => None,
+                };
+                let mut cells = Vec::new();
+                for row in &rows {
+                    let Literal::Tuple(row) = row else {
+                        return Err(LiteralOpError::InvalidTypeError);
+                    };
+                    cells.push(
+                        row.iter()
+                            .map(|value| {
+                                let right = matches!(value, Literal::Number(_) | Literal::Float(_));
+                                (self.format_value(value), right)
+                            })
+                            .collect(),
+                    );
+                }
+                let table = format_table(headers.as_deref(), &cells);
+                match self.output {
+                    OutputMode::Text => self.write_stdout(&format!("{}\n", table)),
+                    OutputMode::Json => Event::Print(table).emit(),
+                }
+                Ok(Literal::None)
+            }
+            //show how much of a loop is done on stderr, eg- progress(i + 1, total)
+            "progress" => {
+                let [done, total] = take_args(args)?;
+                let (Literal::Number(done), Literal::Number(total)) = (done, total) else {
+                    return Err(LiteralOpError::InvalidTypeError);
+                };
+                if total <= 0 {
+                    return Err(LiteralOpError::InvalidArgumentError);
+                }
+                let done = done.clamp(0, total);
+                let interactive = self.output == OutputMode::Text && io::stderr().is_terminal();
+                if self
+                    .progress
+                    .update(done, total, interactive, Instant::now())
+                {
+                    match self.output {
+                        //the bar is drawn over itself until the loop is done
+                        OutputMode::Text if interactive => {
+                            eprint!("\r{}", progress_bar(done, total));
+                            if done == total {
+                                eprintln!();
+                            }
+                        }
+                        OutputMode::Text => eprintln!("{}", progress_bar(done, total)),
+                        OutputMode::Json => Event::Progress(done, total).emit(),
+                    }
+                }
+                Ok(Literal::None)
+            }
+            //call a function once after some milliseconds, or every time they pass
+            //returns the id of the timer, the callbacks only run while run_events() does
+            "after" | "every" => {
+                let [delay, callback] = take_args(args)?;
+                let (Literal::Number(delay), Literal::Function(callback)) = (delay, callback)
+                else {
+                    return Err(LiteralOpError::InvalidTypeError);
+                };
+                let delay = u64::try_from(delay)
+                    .map(Duration::from_millis)
+                    .map_err(|_| LiteralOpError::InvalidArgumentError)?;
+                let id = self.next_timer;
+                self.next_timer += 1;
+                self.timers.push(Timer {
+                    id,
+                    due: Instant::now() + delay,
+                    interval: (name == "every").then_some(delay),
+                    callback,
+                });
+                Ok(Literal::Number(id))
+            }
+            //stop a timer, returns whether it was still scheduled
+            "cancel" => {
+                let [id] = take_args(args)?;
+                let Literal::Number(id) = id else {
+                    return Err(LiteralOpError::InvalidTypeError);
+                };
+                let scheduled = self.timers.len();
+                self.timers.retain(|timer| timer.id != id);
+                Ok(Literal::Bool(self.timers.len() < scheduled))
+            }
+            //read a value kept with store_set(), none if the key was never set
+            "store_get" => {
+                let [key] = take_args(args)?;
+                let Literal::String(key) = key else {
+                    return Err(LiteralOpError::InvalidTypeError);
+                };
+                Ok(self.store.get(&key).unwrap_or(Literal::None))
+            }
+            "store_set" => {
+                let [key, value] = take_args(args)?;
+                let Literal::String(key) = key else {
+                    return Err(LiteralOpError::InvalidTypeError);
+                };
+                self.store.set(&key, value)?;
+                Ok(Literal::None)
+            }
+            "run_events" => {
+                let [] = take_args(args)?;
+                self.run_events()?;
+                Ok(Literal::None)
+            }
+            _ => call_builtin(name, args),
+        }
+    }
+
+    //Run the scheduled callbacks in the order they are due, waiting for each one
+    //returns once no timers are left, or when a callback fails
+    fn run_events(&mut self) -> Result<(), LiteralOpError> {
+        while let Some(next) = (0..self.timers.len()).min_by_key(|&i| self.timers[i].due) {
+            let due = self.timers[next].due;
+            thread::sleep(due.saturating_duration_since(Instant::now()));
+            //a repeating timer is scheduled again first, so its callback can cancel it
+            let callback = match self.timers[next].interval {
+                Some(interval) => {
+                    self.timers[next].due = due + interval;
+                    self.timers[next].callback.clone()
+                }
+                None => self.timers.remove(next).callback,
+            };
+            self.run_function(&callback.function, Some(&callback.captured), Vec::new())?;
+        }
+        Ok(())
+    }
+
+    //Read a line of input without its line ending, None at the end of input or if reading fails
+    fn read_line(&mut self) -> Option<String> {
+        let mut line = String::new();
+        let read = match &mut self.input {
+            Some(input) => input.read_line(&mut line),
+            None => io::stdin().read_line(&mut line),
+        };
+        match read {
+            Ok(0) | Err(_) => None,
+            Ok(_) => {
+                let end = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(end);
+                Some(line)
+            }
+        }
+    }
+
+    //Run a function in a new call frame with its parameters bound to the arguments
+    //the function sees the prelude and global scopes, but not the local variables of its caller
+    //a function ending without a return statement returns none
+    pub fn call_function(
+        &mut self,
+        function: &Rc<Function>,
+        args: Vec<Literal>,
+    ) -> Result<Literal, LiteralOpError> {
+        self.run_function(function, None, args)
+    }
+
+    //captured: the variables shared with a function value, seen by its body below its parameters
+    fn run_function(
+        &mut self,
+        function: &Rc<Function>,
+        captured: Option<&Scope>,
+        args: Vec<Literal>,
+    ) -> Result<Literal, LiteralOpError> {
+        if args.len() != function.params.len() {
+            return Err(LiteralOpError::ArgumentCountError);
+        }
+        //outside of a try every running call is left, so the error is only reported once
+        //instead of each caller going on with a missing value and failing on it
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            if self.tries == 0 {
+                self.report_error(LiteralOpError::CallDepthError);
+                self.unwinding = true;
+            }
+            return Err(LiteralOpError::CallDepthError);
+        }
+        let mut scope = Scope::new();
+        //the function can call itself even if it was declared in a local scope of the caller
+        scope.insert_fn(function.clone())?;
+        for (param, value) in function.params.iter().zip(args) {
+            scope.insert_var(param, value)?;
+        }
+        let caller = self.scopes.split_off(self.globals);
+        self.frames.push(caller);
+        self.scopes.extend(captured.cloned());
+        self.scopes.push(scope);
+        let flow = self.run_stmts(&function.body, false);
+        self.scopes.truncate(self.globals);
+        let mut caller = self.frames.pop().unwrap();
+        self.scopes.append(&mut caller);
+        match flow {
+            Flow::Return(value) => Ok(value),
+            Flow::Exit(_) => Err(LiteralOpError::Exit),
+            Flow::Throw => Err(LiteralOpError::Thrown),
+            _ => Ok(Literal::None),
+        }
+    }
+
+    //Make a function value, sharing the local variables and functions seen where it is made
+    pub fn make_closure(&self, function: &Rc<Function>) -> Closure {
+        let mut captured = Scope::new();
+        for scope in &self.scopes[self.globals..] {
+            for (name, value) in &scope.vars {
+                captured.vars.insert(name.to_owned(), value.clone());
+                //a constant stays one, unless a variable of an inner scope shadows it
+                if scope.consts.contains(name) {
+                    captured.consts.insert(name.to_owned());
+                } else {
+                    captured.consts.remove(name);
+                }
+            }
+            for (name, function) in &scope.functions {
+                captured.functions.insert(name.to_owned(), function.clone());
+            }
+            for (name, declared) in &scope.structs {
+                captured.structs.insert(name.to_owned(), declared.clone());
+            }
+        }
+        Closure {
+            function: function.clone(),
+            captured: Rc::new(captured),
+        }
+    }
+
+    //Search the scopes from the innermost to the outermost for a function
+    pub fn get_fn(&self, name: &str) -> Option<Rc<Function>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get_fn(name))
+            .cloned()
+    }
+
+    //Search the scopes from the innermost to the outermost for a struct
+    pub fn get_struct(&self, name: &str) -> Option<Rc<Struct>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get_struct(name))
+            .cloned()
+    }
+
+    //Solve a record expression into a record of its struct, which must give each of its fields once
+    //the values are solved in the order they are given, but kept in the order the struct declares them
+    pub fn make_record(
+        &mut self,
+        name: &str,
+        fields: &[(String, Expr)],
+    ) -> Result<Literal, LiteralOpError> {
+        let mut values = Vec::new();
+        for (field, value) in fields {
+            values.push((field.to_owned(), value.solve(self)?));
+        }
+        let declared = self
+            .get_struct(name)
+            .ok_or(LiteralOpError::UndefinedStructError)?;
+        if values
+            .iter()
+            .any(|(field, _)| !declared.fields.contains(field))
+        {
+            return Err(LiteralOpError::UndefinedFieldError);
+        }
+        let mut fields = Vec::new();
+        for field in &declared.fields {
+            let index = values
+                .iter()
+                .position(|(name, _)| name == field)
+                .ok_or(LiteralOpError::MissingFieldError)?;
+            fields.push(values.swap_remove(index));
+        }
+        Ok(Literal::Record(Record::new(name, fields)))
+    }
+
+    //Search the scopes from the innermost to the outermost for a variable
+    pub fn get_var(&self, name: &str) -> Option<Literal> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get_var(name))
+    }
+
+    //Declare a variable in the innermost scope
+    pub fn insert_var(&mut self, name: &str, value: Literal) -> Result<(), LiteralOpError> {
+        //the global scope is never removed, so there is always a scope
+        self.scopes.last_mut().unwrap().insert_var(name, value)
+    }
+
+    //Declare a constant in the innermost scope
+    pub fn insert_const(&mut self, name: &str, value: Literal) -> Result<(), LiteralOpError> {
+        self.scopes.last_mut().unwrap().insert_const(name, value)
+    }
+
+    //Modify a variable in the innermost scope it exists in
+    pub fn insert_if_exists(&mut self, name: &str, value: Literal) -> Result<(), LiteralOpError> {
+        match self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.contains_var(name))
+        {
+            Some(scope) => scope.set_var(name, value),
+            None => Err(self.missing_var_error(name)),
+        }
+    }
+
+    //Get a variable to change in place, from the innermost scope it exists in
+    pub fn get_var_mut(&mut self, name: &str) -> Result<RefMut<'_, Literal>, LiteralOpError> {
+        let error = self.missing_var_error(name);
+        match self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.contains_var(name))
+        {
+            Some(scope) => scope.get_var_mut(name),
+            None => Err(error),
+        }
+    }
+
+    //The names of the shared variables and the variables in the prelude and global scopes
+    pub fn global_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.scopes[..self.globals]
+            .iter()
+            .flat_map(|scope| scope.vars.keys().cloned())
+            .collect();
+        if let Some(shared) = &self.shared {
+            names.extend(shared.names());
+        }
+        names
+    }
+
+    //Freeze the innermost scope, its variables can no longer be declared or modified
+    pub fn freeze_scope(&mut self) {
+        self.scopes.last_mut().unwrap().freeze();
+    }
+
+    //Copy the variables and functions of every scope, to be restored later
+    pub fn snapshot(&self) -> StateBlob {
+        StateBlob {
+            scopes: self.scopes.iter().map(Scope::detached).collect(),
+            globals: self.globals,
+        }
+    }
+
+    //Put back the scopes of a snapshot, dropping everything declared or changed since it was taken
+    pub fn restore(&mut self, blob: StateBlob) {
+        self.scopes = blob.scopes;
+        self.globals = blob.globals;
+        self.frames.clear();
+    }
+}
+
+//The state of an executor's scopes at one point, so long running sessions can roll back to it
+//function values keep sharing the parsed functions they were made from, so a snapshot only lives in memory
+#[derive(Debug)]
+pub struct StateBlob {
+    scopes: Vec<Scope>,
+    globals: usize,
+}
+
+//a copy has variables of its own, so restoring one copy and changing it leaves the others as they were
+impl Clone for StateBlob {
+    fn clone(&self) -> Self {
+        Self {
+            scopes: self.scopes.iter().map(Scope::detached).collect(),
+            globals: self.globals,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::features::{Feature, Features};
+    use crate::parser::parser::Parser;
+    use crate::shared::Channel;
+
+    fn scope_with(name: &str, value: Literal) -> Scope {
+        let mut scope = Scope::new();
+        scope.insert_var(name, value).unwrap();
+        scope
+    }
+
+    #[test]
+    fn prelude_scopes() {
+        let mut executor = Executor::with_prelude(vec![
+            scope_with("a", Literal::Number(1)),
+            scope_with("a", Literal::Number(2)),
+        ]);
+        //later prelude scopes shadow earlier ones
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(2)));
+
+        //prelude variables can't be modified
+        assert_eq!(
+            executor.insert_if_exists("a", Literal::Number(3)),
+            Err(LiteralOpError::ReadOnlyVariableError)
+        );
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(2)));
+
+        //but they can be shadowed by globals, which can be modified
+        executor.insert_var("a", Literal::Number(4)).unwrap();
+        assert_eq!(executor.insert_if_exists("a", Literal::Number(5)), Ok(()));
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(5)));
+
+        assert_eq!(
+            executor.insert_if_exists("b", Literal::Number(1)),
+            Err(LiteralOpError::UndefinedVariableError)
+        );
+    }
+
+    fn run(executor: &mut Executor, source: &str) -> Option<Literal> {
+        let tokens = Lexer::new(source).lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        executor.execute_code(&block, false)
+    }
+
+    #[test]
+    fn trailing_expression_value() {
+        let mut executor = Executor::new();
+        assert_eq!(
+            run(&mut executor, "let a = 2\na * 3"),
+            Some(Literal::Number(6))
+        );
+        //only the final statement's value is returned
+        assert_eq!(run(&mut executor, "a\nprint a"), None);
+        assert_eq!(run(&mut executor, "a + 1\nlet b = a"), None);
+        //a failing expression has no value
+        assert_eq!(run(&mut executor, "a - \"text\""), None);
+    }
+
+    #[test]
+    fn block_scopes() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let a = 1\n{\n  let b = 2\n  a = a + b\n  let a = 5\n}",
+        );
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(3)));
+        //variables declared in a block are dropped at its end
+        assert_eq!(executor.get_var("b"), None);
+    }
+
+    #[test]
+    fn frozen_scopes() {
+        let mut scope = scope_with("a", Literal::Number(1));
+        scope.freeze();
+        assert!(scope.is_frozen());
+        assert_eq!(
+            scope.insert_var("b", Literal::Number(2)),
+            Err(LiteralOpError::ReadOnlyVariableError)
+        );
+        assert!(!scope.contains_var("b"));
+
+        let mut executor = Executor::new();
+        executor.insert_var("a", Literal::Number(1)).unwrap();
+        executor.freeze_scope();
+        assert_eq!(
+            executor.insert_if_exists("a", Literal::Number(2)),
+            Err(LiteralOpError::ReadOnlyVariableError)
+        );
+        assert_eq!(
+            executor.insert_var("b", Literal::Number(2)),
+            Err(LiteralOpError::ReadOnlyVariableError)
+        );
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(1)));
+    }
+
+    #[test]
+    fn constants() {
+        let mut executor = Executor::new();
+        run(&mut executor, "const limit = 3\nconst m = {\"a\": 1}");
+        assert_eq!(
+            executor.insert_if_exists("limit", Literal::Number(4)),
+            Err(LiteralOpError::ConstReassignmentError)
+        );
+        //a constant can't be changed in place or declared again in its scope
+        run(
+            &mut executor,
+            "limit += 1\nlet limit = 5\nconst limit = 6\nm[\"a\"] = 2",
+        );
+        assert_eq!(executor.get_var("limit"), Some(Literal::Number(3)));
+        assert_eq!(run(&mut executor, "m[\"a\"]"), Some(Literal::Number(1)));
+
+        //inner scopes can shadow it
+        assert_eq!(
+            run(
+                &mut executor,
+                "fn f() { let limit = 10; return limit }\nf()"
+            ),
+            Some(Literal::Number(10))
+        );
+        run(&mut executor, "{\n  const inner = 1\n}\nlet inner = 2");
+        assert_eq!(executor.get_var("inner"), Some(Literal::Number(2)));
+    }
+
+    #[test]
+    fn function_calls() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let g = 10\nfn scale(a, b) {\n  let c = a * b\n  return c * g\n}\nfn nothing() { let c = 1 }",
+        );
+        assert_eq!(run(&mut executor, "scale(2, 3)"), Some(Literal::Number(60)));
+        //a function without a return gives none
+        assert_eq!(run(&mut executor, "nothing()"), Some(Literal::None));
+        //locals of a function are dropped when it returns
+        assert_eq!(executor.get_var("c"), None);
+        assert_eq!(run(&mut executor, "scale(1)"), None);
+
+        //return leaves every block of the function
+        run(
+            &mut executor,
+            "fn early(a) {\n  {\n    return a\n  }\n  return 0\n}",
+        );
+        assert_eq!(run(&mut executor, "early(5)"), Some(Literal::Number(5)));
+
+        //a function can't see the local variables of its caller
+        run(&mut executor, "let b = 0\nfn read_b() { return b }");
+        run(&mut executor, "{\n  let b = 1\n  g = read_b()\n}");
+        assert_eq!(executor.get_var("g"), Some(Literal::Number(0)));
+
+        //declared functions shadow builtins
+        run(&mut executor, "fn type(a) { return 1 }");
+        assert_eq!(run(&mut executor, "type(\"a\")"), Some(Literal::Number(1)));
+    }
+
+    #[test]
+    fn function_values() {
+        let mut executor = Executor::new();
+        run(&mut executor, "let double = fn(x) { return x * 2 }");
+        assert_eq!(run(&mut executor, "double(4)"), Some(Literal::Number(8)));
+        assert_eq!(
+            run(
+                &mut executor,
+                "let apply = fn(f, v) { return f(v) }\napply(double, 5)"
+            ),
+            Some(Literal::Number(10))
+        );
+
+        //the local variables around a function value are kept after the function making it returns
+        run(
+            &mut executor,
+            "fn adder(n) {\n  return fn(x) { return x + n }\n}\nlet add5 = adder(5)",
+        );
+        assert_eq!(run(&mut executor, "add5(1)"), Some(Literal::Number(6)));
+        //and shared with the block they are declared in, so changes are seen by both
+        run(
+            &mut executor,
+            "let seen = none\nlet after = none\n{\n  let count = 0\n  let next = fn() { count += 1; return count }\n  seen = (next(), next(), count)\n  count = 10\n  after = next()\n}",
+        );
+        assert_eq!(
+            executor.get_var("seen"),
+            Some(Literal::Tuple(vec![
+                Literal::Number(1),
+                Literal::Number(2),
+                Literal::Number(2)
+            ]))
+        );
+        assert_eq!(executor.get_var("after"), Some(Literal::Number(11)));
+        //each call of the making function declares new variables
+        run(
+            &mut executor,
+            "fn counter() {\n  let count = 0\n  return fn() { count += 1; return count }\n}\nlet a = counter()\nlet b = counter()",
+        );
+        assert_eq!(
+            run(&mut executor, "(a(), a(), b(), a())"),
+            Some(Literal::Tuple(vec![
+                Literal::Number(1),
+                Literal::Number(2),
+                Literal::Number(1),
+                Literal::Number(3)
+            ]))
+        );
+        //while the global ones are read when it is called
+        run(
+            &mut executor,
+            "let g = 1\nlet read_g = fn() { return g }\ng = 2",
+        );
+        assert_eq!(run(&mut executor, "read_g()"), Some(Literal::Number(2)));
+
+        //a variable holding a function shadows declared functions and builtins
+        run(&mut executor, "let len = fn(v) { return -1 }");
+        assert_eq!(
+            run(&mut executor, "len(\"abc\")"),
+            Some(Literal::Number(-1))
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "(type(double), double == double, double == add5)"
+            ),
+            Some(Literal::Tuple(vec![
+                Literal::String(String::from("function")),
+                Literal::Bool(true),
+                Literal::Bool(false)
+            ]))
+        );
+        assert_eq!(
+            run(&mut executor, "\"\" + adder(1)"),
+            Some(Literal::String(String::from("fn(x)")))
+        );
+    }
+
+    #[test]
+    fn branches_and_loops() {
+        let mut executor = Executor::new();
+        let sign = "fn sign(n) {\n  if n < 0 { return -1 } else if n == 0 { return 0 } else { return 1 }\n}";
+        run(&mut executor, sign);
+        assert_eq!(
+            run(&mut executor, "(sign(-5), sign(0), sign(5))"),
+            Some(Literal::Tuple(vec![
+                Literal::Number(-1),
+                Literal::Number(0),
+                Literal::Number(1)
+            ]))
+        );
+
+        //break and continue only affect the innermost loop
+        run(
+            &mut executor,
+            "let i = 0; let pairs = 0\nwhile i < 5 {\n  i = i + 1\n  if i == 2 { continue }\n  let j = 0\n  while true {\n    j = j + 1\n    if j > i { break }\n    pairs = pairs + 1\n  }\n}",
+        );
+        assert_eq!(executor.get_var("i"), Some(Literal::Number(5)));
+        assert_eq!(executor.get_var("pairs"), Some(Literal::Number(13)));
+        assert_eq!(executor.get_var("j"), None);
+
+        //return leaves every loop of the function
+        run(
+            &mut executor,
+            "fn find(target) {\n  let n = 0\n  while true {\n    while true {\n      if n == target { return n }\n      n = n + 1\n    }\n  }\n}",
+        );
+        assert_eq!(run(&mut executor, "find(4)"), Some(Literal::Number(4)));
+
+        //a failing condition is reported and ends the loop
+        assert_eq!(
+            run(&mut executor, "while i - \"a\" { i = 0 }\ni"),
+            Some(Literal::Number(5))
+        );
+    }
+
+    #[test]
+    fn chained_comparisons() {
+        let mut executor = Executor::new();
+        run(&mut executor, "let x = 5");
+        assert_eq!(
+            run(&mut executor, "(1 < x < 10, 1 < x < 3, 10 > x >= 5 > 4)"),
+            Some(Literal::Tuple(vec![
+                Literal::Bool(true),
+                Literal::Bool(false),
+                Literal::Bool(true)
+            ]))
+        );
+
+        //each operand is solved once, and the ones after a false comparison are not solved
+        run(
+            &mut executor,
+            "let calls = 0\nfn f() {\n  calls += 1\n  return calls * 5\n}",
+        );
+        assert_eq!(
+            run(&mut executor, "1 < f() < 10"),
+            Some(Literal::Bool(true))
+        );
+        assert_eq!(executor.get_var("calls"), Some(Literal::Number(1)));
+        assert_eq!(
+            run(&mut executor, "1 > x < f() < 100"),
+            Some(Literal::Bool(false))
+        );
+        assert_eq!(executor.get_var("calls"), Some(Literal::Number(1)));
+        assert_eq!(
+            run(&mut executor, "1 < f() < f()"),
+            Some(Literal::Bool(true))
+        );
+        assert_eq!(executor.get_var("calls"), Some(Literal::Number(3)));
+    }
+
+    #[test]
+    fn increments() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let total = 0\nfor (let i = 0; i < 4; i++) { total++ }\nlet f = 1.5\nf--",
+        );
+        assert_eq!(executor.get_var("total"), Some(Literal::Number(4)));
+        assert_eq!(executor.get_var("f"), Some(Literal::Float(0.5)));
+        //5--3 is still a subtraction
+        assert_eq!(run(&mut executor, "5--3"), Some(Literal::Number(8)));
+    }
+
+    #[test]
+    fn compound_assign() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let n = 10\nn += 5\nn -= 1\nn *= 2 + 1\nn %= 5\nlet s = \"ab\"\ns *= 2",
+        );
+        assert_eq!(executor.get_var("n"), Some(Literal::Number(2)));
+        assert_eq!(
+            executor.get_var("s"),
+            Some(Literal::String(String::from("abab")))
+        );
+        assert_eq!(run(&mut executor, "n /= 4\nn"), Some(Literal::Float(0.5)));
+        assert_eq!(run(&mut executor, "-7 % 3"), Some(Literal::Number(-1)));
+
+        //a failing operation leaves the variable unchanged
+        run(&mut executor, "s %= 0\ns -= 1");
+        assert_eq!(
+            executor.get_var("s"),
+            Some(Literal::String(String::from("abab")))
+        );
+        run(&mut executor, "let m = 3\nm %= 0");
+        assert_eq!(executor.get_var("m"), Some(Literal::Number(3)));
+    }
+
+    #[test]
+    fn for_loops() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let total = 0\nfor (let i = 0; i < 10; i = i + 1) {\n  if i == 2 { continue }\n  if i == 6 { break }\n  total = total + i\n}",
+        );
+        assert_eq!(executor.get_var("total"), Some(Literal::Number(13)));
+        //the loop variable is scoped to the loop
+        assert_eq!(executor.get_var("i"), None);
+
+        //an existing variable can be used instead
+        run(&mut executor, "let j = 5\nfor (j = 0; j < 3; j = j + 1) {}");
+        assert_eq!(executor.get_var("j"), Some(Literal::Number(3)));
+        run(&mut executor, "fn first() { for (;;) { return 1 } }");
+        assert_eq!(run(&mut executor, "first()"), Some(Literal::Number(1)));
+    }
+
+    #[test]
+    fn do_while_loops() {
+        let mut executor = Executor::new();
+        //the body runs once before the condition is checked
+        run(
+            &mut executor,
+            "let n = 10
+do { n += 1 } while n < 5",
+        );
+        assert_eq!(executor.get_var("n"), Some(Literal::Number(11)));
+
+        //continue skips to the condition
+        run(
+            &mut executor,
+            "let i = 0; let total = 0\ndo {\n  i += 1\n  if i == 2 { continue }\n  total += i\n} while (i < 4);",
+        );
+        assert_eq!(executor.get_var("total"), Some(Literal::Number(8)));
+        run(&mut executor, "fn once() { do { return 1 } while true }");
+        assert_eq!(run(&mut executor, "once()"), Some(Literal::Number(1)));
+        run(&mut executor, "do { n = 0; break } while true");
+        assert_eq!(executor.get_var("n"), Some(Literal::Number(0)));
+    }
+
+    #[test]
+    fn iteration_warning() {
+        let mut executor = Executor::new();
+        executor.iteration_warning = Some(3);
+        //the warning does not stop the loop
+        run(&mut executor, "let i = 0\nwhile i < 10 { i += 1 }");
+        assert_eq!(executor.get_var("i"), Some(Literal::Number(10)));
+        run(&mut executor, "do { i -= 1 } while i > 0");
+        assert_eq!(executor.get_var("i"), Some(Literal::Number(0)));
+    }
+
+    #[test]
+    fn loop_limit() {
+        let mut executor = Executor::new();
+        executor.loop_limit = Some(10);
+        //the runs of every loop count against the limit
+        run(
+            &mut executor,
+            "let n = 0\nwhile n < 5 { n += 1 }\nfor (let i = 0; i < 5; i++) { n += 1 }",
+        );
+        assert_eq!(executor.exit_code(), None);
+        assert_eq!(executor.get_var("n"), Some(Literal::Number(10)));
+        run(&mut executor, "while true { n += 1 }\nn = 0");
+        assert_eq!(executor.exit_code(), Some(1));
+        assert_eq!(executor.get_var("n"), Some(Literal::Number(20)));
+        //the count starts again for each program
+        run(&mut executor, "do { n -= 1 } while n > 10");
+        assert_eq!(executor.exit_code(), None);
+        assert_eq!(executor.get_var("n"), Some(Literal::Number(10)));
+    }
+
+    #[test]
+    fn ranges() {
+        let mut executor = Executor::new();
+        run(&mut executor, "let n = 4\nlet r = 1..n + 1\nlet i = 1..=n");
+        assert_eq!(executor.get_var("r"), Some(Literal::Range(1, 5)));
+        assert_eq!(run(&mut executor, "r == i"), Some(Literal::Bool(true)));
+        assert_eq!(run(&mut executor, "len(r)"), Some(Literal::Number(4)));
+        assert_eq!(run(&mut executor, "len(5..1)"), Some(Literal::Number(0)));
+        assert_eq!(
+            run(&mut executor, "(contains(r, 4), contains(r, 5), r[1])"),
+            Some(Literal::Tuple(vec![
+                Literal::Bool(true),
+                Literal::Bool(false),
+                Literal::Number(2)
+            ]))
+        );
+        assert_eq!(
+            run(&mut executor, "(-1..1) as tuple"),
+            Some(Literal::Tuple(vec![
+                Literal::Number(-1),
+                Literal::Number(0)
+            ]))
+        );
+        assert_eq!(
+            run(&mut executor, "(r is range, \"\" + r)"),
+            Some(Literal::Tuple(vec![
+                Literal::Bool(true),
+                Literal::String(String::from("1..5"))
+            ]))
+        );
+        //only numbers make ranges
+        assert_eq!(run(&mut executor, "1.5..3"), None);
+        assert_eq!(run(&mut executor, "1..=2147483647"), None);
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let mut executor = Executor::new();
+        run(&mut executor, "let a = 1\nfn f() { return a }");
+        let blob = executor.snapshot();
+        run(
+            &mut executor,
+            "a = 2\nlet b = 3\nfn f() { return 0 }\nfn g() {}",
+        );
+        executor.restore(blob.clone());
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(1)));
+        assert_eq!(executor.get_var("b"), None);
+        assert!(executor.get_fn("g").is_none());
+        assert_eq!(run(&mut executor, "f()"), Some(Literal::Number(1)));
+
+        //a snapshot can be restored more than once
+        run(&mut executor, "a = 5");
+        executor.restore(blob);
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(1)));
+    }
+
+    #[test]
+    fn input_builtin() {
+        let mut executor = Executor::new();
+        executor.set_input(io::Cursor::new("bob\r\n42\n"));
+        run(
+            &mut executor,
+            "let name = input()\nlet age = input(\"age: \") as number\nlet rest = input()",
+        );
+        assert_eq!(
+            executor.get_var("name"),
+            Some(Literal::String(String::from("bob")))
+        );
+        assert_eq!(executor.get_var("age"), Some(Literal::Number(42)));
+        assert_eq!(executor.get_var("rest"), Some(Literal::None));
+        assert_eq!(run(&mut executor, "input(1, 2)"), None);
+    }
+
+    #[test]
+    fn shared_globals() {
+        let shared = SharedGlobals::new();
+        shared.set("port", &Literal::Number(80));
+        let mut executor = Executor::new();
+        executor.set_shared_globals(shared.clone());
+        assert_eq!(run(&mut executor, "port + 1"), Some(Literal::Number(81)));
+
+        //changes by the host are seen by the next read
+        shared.set("port", &Literal::Number(8080));
+        assert_eq!(run(&mut executor, "port"), Some(Literal::Number(8080)));
+
+        //programs can shadow a shared variable but not change it
+        assert!(matches!(
+            executor.insert_if_exists("port", Literal::Number(1)),
+            Err(LiteralOpError::ReadOnlyVariableError)
+        ));
+        run(&mut executor, "let port = 1");
+        assert_eq!(run(&mut executor, "port"), Some(Literal::Number(1)));
+        assert_eq!(shared.get("port"), Some(Literal::Number(8080)));
+    }
+
+    #[test]
+    fn exit_statement() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let log = \"a\"
+fn stop(code) {
+  log += \"b\"
+  exit(code)
+  log += \"x\"
+}
+for (let i = 0; i < 3; i++) {
+  print log + stop(3) + \"x\"
+  log += \"x\"
+}
+log += \"x\"",
+        );
+        assert_eq!(executor.exit_code(), Some(3));
+        assert_eq!(
+            executor.get_var("log"),
+            Some(Literal::String(String::from("ab")))
+        );
+
+        //the executor can run programs after an exit
+        assert_eq!(run(&mut executor, "1 + 1"), Some(Literal::Number(2)));
+        assert_eq!(executor.exit_code(), None);
+        run(&mut executor, "while true { exit }");
+        assert_eq!(executor.exit_code(), Some(0));
+        run(&mut executor, "exit(\"a\")");
+        assert_eq!(executor.exit_code(), Some(1));
+    }
+
+    #[test]
+    fn assert_statement() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let t = (1, 2)\nassert len(t) == 2, \"a pair\"\nassert t\nt = 0",
+        );
+        assert_eq!(executor.exit_code(), None);
+        assert_eq!(executor.get_var("t"), Some(Literal::Number(0)));
+
+        //a failed assertion stops the program as a failure
+        run(&mut executor, "assert t > 1, \"t is \" + t\nt = 1");
+        assert_eq!(executor.exit_code(), Some(1));
+        assert_eq!(executor.get_var("t"), Some(Literal::Number(0)));
+        run(&mut executor, "assert none + 1\nt = 1");
+        assert_eq!(executor.exit_code(), Some(1));
+        assert_eq!(executor.get_var("t"), Some(Literal::Number(0)));
+    }
+
+    #[test]
+    fn try_catch() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let a = 0\nlet kind = none\ntry {\n  a = 1\n  a = a / 0\n  a = 2\n} catch (e) {\n  kind = e[\"kind\"]\n}",
+        );
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(1)));
+        assert_eq!(
+            executor.get_var("kind"),
+            Some(Literal::String(String::from("DivByZeroError")))
+        );
+
+        //errors in called functions and loops leave them, up to the try
+        run(
+            &mut executor,
+            "fn f(n) {\n  while true { n = n - 1\n  a = 1 / n }\n}\ntry { f(3)\n  a = 5 } catch (err) { kind = err[\"message\"] }",
+        );
+        assert_eq!(executor.get_var("a"), Some(Literal::Float(1.0)));
+        assert_eq!(
+            executor.get_var("kind"),
+            Some(Literal::String(String::from("DivByZeroError")))
+        );
+
+        //a nested try catches its own errors, and a catch block can fail into the outer try
+        run(
+            &mut executor,
+            "try {\n  try { none + 1 } catch (e) { a = e[\"kind\"]\n  b = 1 }\n} catch (e) { kind = e[\"message\"] }",
+        );
+        assert_eq!(
+            executor.get_var("a"),
+            Some(Literal::String(String::from("NoneValueError")))
+        );
+        assert_eq!(
+            executor.get_var("kind"),
+            Some(Literal::String(String::from(
+                "Error: Variable b does not exist in scope"
+            )))
+        );
+
+        //without an error the catch block is skipped
+        run(
+            &mut executor,
+            "try { a = 3 } catch (e) { a = 4 }\na = a + 1",
+        );
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(4)));
+        assert_eq!(executor.get_var("e"), None);
+    }
+
+    #[test]
+    fn throw_statement() {
+        let mut executor = Executor::new();
+        let map = |entries: &[(&str, Literal)]| {
+            Literal::Map(Map::from_iter(entries.iter().map(|(key, value)| {
+                (Literal::String(key.to_string()), value.clone())
+            })))
+        };
+        run(
+            &mut executor,
+            "fn check(n) {\n  for (let i = 0; i < n; i++) {\n    if i == 2 { throw (\"two\", i) }\n  }\n  return n\n}\nlet a = 0\ntry {\n  a = check(1)\n  a = check(5)\n} catch (e) { a = e }",
+        );
+        let value = Literal::Tuple(vec![
+            Literal::String(String::from("two")),
+            Literal::Number(2),
+        ]);
+        assert_eq!(
+            executor.get_var("a"),
+            Some(map(&[
+                ("kind", Literal::String(String::from("UserError"))),
+                ("message", Literal::String(String::from("(\"two\", 2)"))),
+                ("line", Literal::Number(3)),
+                ("position", Literal::Number(16)),
+                ("value", value),
+            ]))
+        );
+
+        //a caught error can be thrown on to an outer try
+        run(
+            &mut executor,
+            "try {\n  try { 1 / 0 } catch (e) { throw e[\"kind\"] }\n} catch (e) { a = e[\"value\"] }",
+        );
+        assert_eq!(
+            executor.get_var("a"),
+            Some(Literal::String(String::from("DivByZeroError")))
+        );
+
+        //an error thrown outside of a try stops the program
+        run(&mut executor, "a = 1\nwhile true { throw \"stop\" }\na = 2");
+        assert_eq!(executor.exit_code(), Some(1));
+        assert_eq!(executor.get_var("a"), Some(Literal::Number(1)));
+    }
+
+    #[test]
+    fn short_circuit() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let calls = 0\nfn touch(value) {\n  calls += 1\n  return value\n}\nlet x = 0\nlet safe = x != 0 and 10 / x > 1\nlet a = false and touch(true)\nlet b = true or touch(false)\nlet c = 1 and touch(\"\")\nlet d = 0 or touch(2)",
+        );
+        assert_eq!(executor.get_var("safe"), Some(Literal::Bool(false)));
+        assert_eq!(executor.get_var("a"), Some(Literal::Bool(false)));
+        assert_eq!(executor.get_var("b"), Some(Literal::Bool(true)));
+        assert_eq!(executor.get_var("c"), Some(Literal::Bool(false)));
+        assert_eq!(executor.get_var("d"), Some(Literal::Bool(true)));
+        //only the calls of the operands which were needed ran
+        assert_eq!(executor.get_var("calls"), Some(Literal::Number(2)));
+
+        //strict conditions only check the operands which are solved
+        executor.semantics.strict_conditions = true;
+        assert_eq!(
+            run(&mut executor, "false and 1"),
+            Some(Literal::Bool(false))
+        );
+        assert_eq!(run(&mut executor, "true and 1"), None);
+    }
+
+    #[test]
+    fn records() {
+        //structs are an experimental feature
+        let run = |executor: &mut Executor, source: &str| {
+            let tokens = Lexer::new(source).lex();
+            let features = Features::default().with(Feature::Structs);
+            let block = Parser::new(&tokens)
+                .with_features(features)
+                .parse()
+                .unwrap();
+            executor.execute_code(&block, false)
+        };
+        let mut executor = Executor::new();
+        let point = |x: i32, y: i32| {
+            Literal::Record(Record::new(
+                "Point",
+                vec![
+                    (String::from("x"), Literal::Number(x)),
+                    (String::from("y"), Literal::Number(y)),
+                ],
+            ))
+        };
+        run(
+            &mut executor,
+            "struct Point { x, y }\nstruct Line { start, end }\nlet p = Point(1, 2)\nlet q = Point { y: 4, x: 3 }\nlet sum = p.x + q.y\np.x = 5\nlet l = Line { start: p, end: q }\nl.start.y = 6\nlet m = {\"a\": Point(0, 0)}\nm[\"a\"].x = 7",
+        );
+        assert_eq!(executor.get_var("sum"), Some(Literal::Number(5)));
+        assert_eq!(executor.get_var("p"), Some(point(5, 2)));
+        assert_eq!(executor.get_var("q"), Some(point(3, 4)));
+        match executor.get_var("l") {
+            Some(Literal::Record(line)) => {
+                assert_eq!(line.get("start"), Ok(&point(5, 6)));
+                assert_eq!(line.get("end"), Ok(&point(3, 4)));
+            }
+            value => panic!("Expected a record, got {:?}", value),
+        }
+        assert_eq!(run(&mut executor, "m[\"a\"]"), Some(point(7, 0)));
+        assert_eq!(
+            run(&mut executor, "p is record and p == Point(5, 2)"),
+            Some(Literal::Bool(true))
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "fn origin() { return Point(0, 0) }\norigin().y"
+            ),
+            Some(Literal::Number(0))
+        );
+
+        //a record must give each field of its struct, and only those
+        let kind = |executor: &mut Executor, expr: &str| {
+            let source = format!(
+                "let e = none\ntry {{ {} }} catch (err) {{ e = err[\"kind\"] }}\ne",
+                expr
+            );
+            run(executor, &source)
+        };
+        for (expr, error) in [
+            ("Point { x: 1 }", "MissingFieldError"),
+            ("Point { x: 1, y: 2, z: 3 }", "UndefinedFieldError"),
+            ("p.z", "UndefinedFieldError"),
+            ("p.z = 1", "UndefinedFieldError"),
+            ("Size { w: 1 }", "UndefinedStructError"),
+            ("Point(1)", "ArgumentCountError"),
+            ("sum.x", "InvalidTypeError"),
+        ] {
+            assert_eq!(
+                kind(&mut executor, expr),
+                Some(Literal::String(String::from(error))),
+                "{}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn timers() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let log = \"\"
+after(6, fn() { log += \"c\" })
+after(1, fn() { log += \"a\" })
+let ticks = 0
+let id = every(2, fn() {
+  ticks += 1
+  if ticks == 2 { cancel(id) }
+})
+cancel(after(3, fn() { log += \"x\" }))
+run_events()",
+        );
+        assert_eq!(executor.get_var("ticks"), Some(Literal::Number(2)));
+        assert_eq!(
+            executor.get_var("log"),
+            Some(Literal::String(String::from("ac")))
+        );
+        assert_eq!(run(&mut executor, "(cancel(id), after(-1, fn() {}))"), None);
+    }
+
+    #[test]
+    fn channels_between_threads() {
+        let (requests, replies) = (Channel::new(), Channel::new());
+        let worker = {
+            let (requests, replies) = (requests.clone(), replies.clone());
+            thread::spawn(move || {
+                let mut executor = Executor::new();
+                executor
+                    .insert_var("requests", Literal::Channel(requests))
+                    .unwrap();
+                executor
+                    .insert_var("replies", Literal::Channel(replies))
+                    .unwrap();
+                run(&mut executor, "send(replies, recv(requests) * 2)");
+            })
+        };
+        let mut executor = Executor::new();
+        executor
+            .insert_var("requests", Literal::Channel(requests))
+            .unwrap();
+        executor
+            .insert_var("replies", Literal::Channel(replies))
+            .unwrap();
+        run(&mut executor, "send(requests, 21)");
+        assert_eq!(
+            run(&mut executor, "recv(replies, 5000)"),
+            Some(Literal::Number(42))
+        );
+        worker.join().unwrap();
+        assert_eq!(
+            run(&mut executor, "(type(replies), send(replies, fn() {}))"),
+            None
+        );
+    }
+
+    #[test]
+    fn store_builtins() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let runs = store_get(\"runs\")\nif runs == none { runs = 0 }\nstore_set(\"runs\", runs + 1)",
+        );
+        run(
+            &mut executor,
+            "store_set(\"runs\", store_get(\"runs\") + 1)",
+        );
+        assert_eq!(
+            run(&mut executor, "store_get(\"runs\")"),
+            Some(Literal::Number(2))
+        );
+        assert_eq!(run(&mut executor, "store_get(1)"), None);
+    }
+
+    #[test]
+    fn maps() {
+        let mut executor = Executor::new();
+        run(
+            &mut executor,
+            "let m = {\"a\": {\"b\": 1}}\nm[\"a\"][\"b\"] = 2\nm[\"c\"] = m[\"a\"][\"b\"] + 1",
+        );
+        assert_eq!(
+            run(&mut executor, "m == {\"c\": 3, \"a\": {\"b\": 2}}"),
+            Some(Literal::Bool(true))
+        );
+        //a missing key can't be read, or lead to a nested map
+        assert_eq!(run(&mut executor, "m[\"d\"]"), None);
+        run(&mut executor, "m[\"d\"][\"e\"] = 1");
+        assert_eq!(run(&mut executor, "len(m)"), Some(Literal::Number(2)));
+        //maps are values, changing a copy leaves the original as it was
+        run(&mut executor, "let n = m\nn[\"c\"] = 0");
+        assert_eq!(run(&mut executor, "m[\"c\"]"), Some(Literal::Number(3)));
+
+        //the keys are looped over by indexing the tuple of keys
+        run(
+            &mut executor,
+            "let total = 0\nlet k = keys(m)\nfor (let i = 0; i < len(k); i = i + 1) { total = total + len(k[i]) }",
+        );
+        assert_eq!(executor.get_var("total"), Some(Literal::Number(2)));
+
+        //only maps can be changed through an index
+        run(&mut executor, "let t = (1, 2)\nt[0] = 5");
+        assert_eq!(run(&mut executor, "t[0]"), Some(Literal::Number(1)));
+        let mut frozen =
+            Executor::with_prelude(vec![scope_with("p", Literal::Map(Default::default()))]);
+        run(&mut frozen, "p[1] = 1");
+        assert_eq!(run(&mut frozen, "len(p)"), Some(Literal::Number(0)));
+    }
+
+    #[test]
+    fn set_precision() {
+        let mut executor = Executor::new();
+        run(&mut executor, "set_precision(2)");
+        assert_eq!(executor.float_precision, Some(2));
+        //values joined to a string are formatted like print shows them
+        assert_eq!(
+            run(&mut executor, "\"x = \" + 1 / 3 + \", \" + (0.5, 2)"),
+            Some(Literal::String(String::from("x = 0.33, (0.50, 2)")))
+        );
+        assert_eq!(
+            run(&mut executor, "1.0 / 4 + \"\""),
+            Some(Literal::String(String::from("0.25")))
+        );
+        run(&mut executor, "set_precision(-1)");
+        assert_eq!(executor.float_precision, None);
+        assert_eq!(
+            run(&mut executor, "\"\" + 1 / 4"),
+            Some(Literal::String(String::from("0.25")))
+        );
+
+        let mut set = |precision| executor.call("set_precision", vec![precision]);
+        assert_eq!(
+            set(Literal::Number(MAX_PRECISION as i32 + 1)),
+            Err(LiteralOpError::InvalidArgumentError)
+        );
+        assert_eq!(
+            set(Literal::Float(2.0)),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+    }
+
+    #[test]
+    fn string_interpolation() {
+        let mut executor = Executor::new();
+        let string = |text: &str| Some(Literal::String(text.to_owned()));
+        run(
+            &mut executor,
+            "let n = 2; let s = \"b\"; let t = (1, \"c\")",
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "\"${n} + ${n} is ${n + n}, ${s}${t} ${none}\""
+            ),
+            string("2 + 2 is 4, b(1, \"c\") none")
+        );
+        //floats are shown with the precision print uses
+        run(&mut executor, "set_precision(2)");
+        assert_eq!(run(&mut executor, "\"${1 / 3}\""), string("0.33"));
+        //a failing part fails the whole string
+        assert_eq!(run(&mut executor, "\"${s - 1}\""), None);
+    }
+
+    #[test]
+    fn call_depth_limit() {
+        //test threads have a small stack, so the calls run on a thread like the one main uses
+        let runner = std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let mut executor = Executor::new();
+                run(
+                    &mut executor,
+                    "let a = 1\nfn down(n) { return (n, down(n + 1)).1 }",
+                );
+                //the call past the limit ends every call, so the statement making the first one has no value
+                assert_eq!(run(&mut executor, "down(0)"), None);
+                assert!(!executor.unwinding);
+                //the global scope is left as it was once the calls unwind
+                assert_eq!(run(&mut executor, "a"), Some(Literal::Number(1)));
+                assert!(executor.frames.is_empty());
+            });
+        runner.unwrap().join().unwrap();
+    }
+}
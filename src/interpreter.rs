@@ -1,96 +1,427 @@
-use crate::errors::ErrorHandler;
+use crate::errors::{ErrorHandler, LiteralOpError, StmtErrors};
+use crate::executor::{Executor, StateBlob};
 use crate::lexer::Lexer;
+use crate::output::{Event, OutputMode, Phase};
+use crate::parser::analyzer::Analyzer;
+use crate::parser::features::Features;
+use crate::parser::optimizer::Optimizer;
 use crate::parser::parser::Parser;
+use crate::parser::semantics::Semantics;
 use crate::parser::stmt::Block;
-use crate::token::Token;
+use crate::shared::SharedGlobals;
+use crate::source::normalize_source;
+use crate::store::Store;
+use crate::token::{is_identifier, KeywordAliases, Literal, Token};
 use colored::Colorize;
-use std::io::{self, Write};
+use std::cell::Cell;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+//A function told the message of a panic caught by the interpreter
+pub type PanicHook = dyn Fn(&str);
+
+thread_local! {
+    //Whether the thread is running code whose panics are caught and reported as internal errors
+    static CATCHING_PANICS: Cell<bool> = const { Cell::new(false) };
+}
+
+//Keep the panic hook from printing the panics the interpreter catches, other panics are still printed
+//the hook is shared by every thread, so it is replaced once and checks the thread it is called on
+fn silence_caught_panics() {
+    static SILENCED: Once = Once::new();
+    SILENCED.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING_PANICS.with(Cell::get) {
+                hook(info);
+            }
+        }));
+    });
+}
 
 pub struct Interpreter {
     source: String,
     tokens: Vec<Token>,
+    //Holds the variables, which are kept between runs
+    executor: Executor,
+    //Whether interpret prints the analyzer's warnings, off for embedders by default
+    warnings: bool,
+    //Other spellings of keywords accepted in the programs run
+    aliases: KeywordAliases,
+    //The experimental features the programs run may use
+    features: Features,
+    //Told the message of a panic of the interpreter caught while running a program
+    panic_hook: Option<Box<PanicHook>>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Interpreter {
+        Self::with_executor(Executor::new())
+    }
+
+    //Create an interpreter running code on a prepared executor, eg- one with prelude scopes
+    pub fn with_executor(executor: Executor) -> Interpreter {
         let source = String::from("");
         Self {
             source,
             tokens: Vec::new(),
+            executor,
+            warnings: false,
+            aliases: KeywordAliases::new(),
+            features: Features::default(),
+            panic_hook: None,
         }
     }
 
+    //Let the programs run use experimental features, eg- Features::parse("structs")
+    pub fn set_features(&mut self, features: Features) {
+        self.features = features;
+    }
+
+    //Be told about bugs of the interpreter, eg- to log them, the program is stopped but the interpreter can go on
+    pub fn set_panic_hook(&mut self, hook: impl Fn(&str) + 'static) {
+        self.panic_hook = Some(Box::new(hook));
+    }
+
+    //Change how the programs run by this interpreter are evaluated
+    pub fn set_semantics(&mut self, semantics: Semantics) {
+        self.executor.semantics = semantics;
+    }
+
+    //Write printed values and errors as text, or as JSON events on stdout
+    pub fn set_output(&mut self, output: OutputMode) {
+        self.executor.output = output;
+    }
+
+    //Print warnings about likely mistakes before running a program with interpret
+    //warnings are never shown in prompt mode, where expression results are printed
+    pub fn set_warnings(&mut self, enabled: bool) {
+        self.warnings = enabled;
+    }
+
+    //Warn once when a single run of a while loop reaches this many iterations, None turns it off
+    pub fn set_iteration_warning(&mut self, iterations: Option<usize>) {
+        self.executor.iteration_warning = iterations;
+    }
+
+    //Stop programs like exit(1) once their loops have run this many times in all, None for no limit
+    pub fn set_loop_limit(&mut self, iterations: Option<usize>) {
+        self.executor.loop_limit = iterations;
+    }
+
+    //Accept other spellings of keywords, eg- localized ones, in the programs run after this
+    pub fn set_keyword_aliases(&mut self, aliases: KeywordAliases) {
+        self.aliases = aliases;
+    }
+
+    //Set a global variable before running a program, eg- one given with --define
+    pub fn define(&mut self, name: &str, value: Literal) -> Result<(), LiteralOpError> {
+        self.executor.insert_var(name, value)
+    }
+
+    //Bind the arguments given to a program as globals, eg- estel main.estel -- 10 --name=estel
+    //ARGS holds the other arguments as a tuple of strings and --key=value pairs become variables
+    //returns the first key which is not a valid variable name
+    pub fn set_args(&mut self, args: &[String]) -> Result<(), String> {
+        let mut positional = Vec::new();
+        for arg in args {
+            match arg.strip_prefix("--").and_then(|pair| pair.split_once('=')) {
+                Some((key, value)) => {
+                    if !is_identifier(key) || key == "ARGS" {
+                        return Err(key.to_owned());
+                    }
+                    self.executor
+                        .insert_var(key, Literal::String(value.to_owned()))
+                        .map_err(|_| key.to_owned())?;
+                }
+                None => positional.push(Literal::String(arg.to_owned())),
+            }
+        }
+        self.executor
+            .insert_var("ARGS", Literal::Tuple(positional))
+            .map_err(|_| String::from("ARGS"))
+    }
+
+    //Read the lines of input() from another reader instead of stdin
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.executor.set_input(input);
+    }
+
+    //Write what the programs run print to another writer instead of stdout
+    pub fn set_stdout(&mut self, stdout: impl Write + 'static) {
+        self.executor.set_stdout(stdout);
+    }
+
+    //Let the programs run read variables shared with other interpreters, eg- common configuration
+    pub fn set_shared_globals(&mut self, shared: SharedGlobals) {
+        self.executor.set_shared_globals(shared);
+    }
+
+    //The exit code of the last program run, if it was stopped by an exit statement
+    pub fn exit_code(&self) -> Option<i32> {
+        self.executor.exit_code()
+    }
+
+    //Keep the values of store_set() in another store, eg- a JSON file next to the script
+    pub fn set_store(&mut self, store: impl Store + 'static) {
+        self.executor.set_store(store);
+    }
+
+    //Save the variables and functions of the programs run so far, eg- as a checkpoint of a session
+    pub fn snapshot(&self) -> StateBlob {
+        self.executor.snapshot()
+    }
+
+    //Roll the variables and functions back to a snapshot
+    pub fn restore(&mut self, blob: StateBlob) {
+        self.executor.restore(blob);
+    }
+
+    //Only program output and the results of expressions go to stdout
+    //the banner, prompts and errors go to stderr, so the output can be piped on its own
     pub fn run_prompt(&mut self) {
-        //create a single block for a prompt session
-        let mut prompt_block: Block = Block::new(Vec::new(), None);
-        println!(
+        eprintln!(
             "{}",
-            "Entering prompt mode, use !q or !quit to exit. To run a file, use estel [filename]"
+            "Entering prompt mode, use !q or !quit to exit and :paste to enter several lines at once. To run a file, use estel [filename]"
                 .green()
         );
+        //stdin is only locked while reading, so the programs run can read it with input()
+        let stdin = io::stdin();
         loop {
-            self.source.clear();
+            eprint!(">>>>");
+            io::stderr().flush().unwrap();
+            let line = match Self::read_prompt_line(&mut stdin.lock()) {
+                Some(line) => line,
+                //stop at the end of input
+                None => {
+                    eprintln!();
+                    break;
+                }
+            };
 
-            print!(">>>>");
-            io::stdout().flush().unwrap();
-            io::stdin()
-                .read_line(&mut self.source)
-                .unwrap_or_else(|_| panic!("{}", "Failed to read input!".red()));
+            match line.trim() {
+                "!q" | "!quit" => break,
+                ":paste" => {
+                    eprintln!(
+                        "{}",
+                        "Paste mode, end the input with :end on its own line or Ctrl-D".green()
+                    );
+                    self.source = Self::read_paste(&mut stdin.lock());
+                }
+                _ => self.source = line,
+            }
+            self.catch_panic(Self::run_prompt_source);
+            if self.exit_code().is_some() {
+                break;
+            }
+        }
+    }
 
-            if self.source == "!q\r\n" || self.source == "!quit\r\n" {
+    //Read a line of input, None at the end of input
+    fn read_prompt_line(input: &mut impl BufRead) -> Option<String> {
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(line),
+            Err(_) => panic!("{}", "Failed to read input!".red()),
+        }
+    }
+
+    //Read lines until a line with :end or the end of input, the lines are run as one program
+    fn read_paste(input: &mut impl BufRead) -> String {
+        let mut source = String::new();
+        while let Some(line) = Self::read_prompt_line(input) {
+            if line.trim() == ":end" {
                 break;
             }
+            source.push_str(&line);
+        }
+        source
+    }
 
-            let mut error_handler = ErrorHandler::new(&self.source);
+    //Run the source entered in prompt mode, printing the results of expressions
+    fn run_prompt_source(&mut self) {
+        self.source = normalize_source(&self.source);
+        let mut error_handler = ErrorHandler::new(&self.source, self.executor.output);
 
-            self.tokens = Lexer::new(&self.source).lex();
+        self.tokens = Lexer::with_aliases(&self.source, &self.aliases).lex();
 
-            //Print lexical errors
-            if error_handler.find_lexical_errors(&self.tokens) {
-                error_handler.print_lexical_errors();
-                continue;
-            }
+        //Print lexical errors
+        if error_handler.find_lexical_errors(&self.tokens) {
+            error_handler.print_lexical_errors();
+            return;
+        }
 
-            //add new variables to the block
-            let block = Parser::new(&self.tokens).parse(None);
-            match block {
-                Err(errors) => {
-                    //handle errors using error handler
-                    error_handler.print_stmt_errors(&errors);
-                }
-                Ok(block) => {
-                    //copy the statements from the new block to the prompt block
-                    prompt_block.stmts = block.stmts;
-                    //show Expr result in prompt
-                    prompt_block.execute(true);
-                }
+        match self.parse() {
+            Err(errors) => {
+                //handle errors using error handler
+                error_handler.print_stmt_errors(&errors);
+            }
+            Ok(mut block) => {
+                Optimizer::optimize(&mut block);
+                //show Expr result in prompt
+                self.executor.execute_code(&block, true);
             }
         }
     }
 
-    pub fn interpret(&mut self, source: String) {
-        self.source = source;
+    //Parse the tokens of the source, checking the declarations of variables if the semantics ask for it
+    fn parse(&self) -> Result<Block, StmtErrors> {
+        let semantics = self.executor.semantics;
+        let mut parser = if semantics.declare_before_use {
+            Parser::with_declared(&self.tokens, self.executor.global_names())
+        } else {
+            Parser::new(&self.tokens)
+        };
+        if semantics.no_shadowing {
+            parser = parser.without_shadowing(self.executor.global_names());
+        }
+        parser.with_features(self.features).parse()
+    }
+
+    //Run a program, returning the value of its final expression statement
+    //nothing is returned if the program had errors or did not end with an expression
+    pub fn interpret(&mut self, source: String) -> Option<Literal> {
+        self.catch_panic(|interpreter| interpreter.run_source(source))
+            .flatten()
+    }
+
+    //Run part of the interpreter, turning a panic into an internal error instead of unwinding into the embedder
+    //the program stopped by the panic loses its local variables, the globals are kept
+    fn catch_panic<T>(&mut self, run: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        silence_caught_panics();
+        //a panic is reported below instead of being printed with a backtrace
+        let outer = CATCHING_PANICS.with(|catching| catching.replace(true));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| run(self)));
+        CATCHING_PANICS.with(|catching| catching.set(outer));
+        let payload = match result {
+            Ok(value) => return Some(value),
+            Err(payload) => payload,
+        };
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => payload
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| String::from("unknown panic")),
+        };
+        self.executor.reset_frames();
+        ErrorHandler::new(&self.source, self.executor.output).report(&Event::Error(
+            Phase::Run,
+            format!(
+                "Internal error: {}, this is a bug in estel, please report it with the program run",
+                message
+            ),
+            None,
+            None,
+        ));
+        if let Some(hook) = &self.panic_hook {
+            hook(&message);
+        }
+        None
+    }
+
+    fn run_source(&mut self, source: String) -> Option<Literal> {
+        self.source = normalize_source(&source);
 
-        let mut error_handler = ErrorHandler::new(&self.source);
-        let mut lexer = Lexer::new(&self.source);
+        let mut error_handler = ErrorHandler::new(&self.source, self.executor.output);
+        let mut lexer = Lexer::with_aliases(&self.source, &self.aliases);
         self.tokens = lexer.lex();
 
         //Stop interpreting if a lexical error occured
         if error_handler.find_lexical_errors(&self.tokens) {
             error_handler.print_lexical_errors();
-            return;
+            return None;
         }
 
-        //Parser
-        let mut parser = Parser::new(&self.tokens);
-        let block = parser.parse(None);
-        match block {
+        match self.parse() {
             Err(errors) => {
                 error_handler.print_stmt_errors(&errors);
+                None
             }
             Ok(mut block) => {
-                block.execute(false);
+                if self.warnings {
+                    error_handler.print_warnings(&Analyzer::analyze(&block));
+                }
+                //the analyzer reports on the code as written, so it runs first
+                Optimizer::optimize(&mut block);
+                self.executor.execute_code(&block, false)
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn paste_mode_input() {
+        let mut input = io::Cursor::new("let a = 1\n\nprint a\n  :end  \na\n");
+        assert_eq!(
+            Interpreter::read_paste(&mut input),
+            "let a = 1\n\nprint a\n"
+        );
+        assert_eq!(
+            Interpreter::read_prompt_line(&mut input),
+            Some(String::from("a\n"))
+        );
+
+        //the end of input also ends the paste
+        let mut input = io::Cursor::new("print 1\r\nprint 2");
+        assert_eq!(Interpreter::read_paste(&mut input), "print 1\r\nprint 2");
+        assert_eq!(Interpreter::read_prompt_line(&mut input), None);
+    }
+
+    #[test]
+    fn panic_hook() {
+        let panics = Rc::new(RefCell::new(Vec::new()));
+        let mut interpreter = Interpreter::new();
+        let hook_panics = panics.clone();
+        interpreter
+            .set_panic_hook(move |message| hook_panics.borrow_mut().push(message.to_owned()));
+        interpreter.interpret(String::from("let a = 1"));
+        let caught = interpreter.catch_panic::<()>(|interpreter| {
+            interpreter.interpret(String::from("let b = 2"));
+            panic!("deliberate panic")
+        });
+        assert_eq!(caught, None);
+        assert_eq!(*panics.borrow(), ["deliberate panic"]);
+        //the panic is only hidden from the hook while it is caught
+        assert!(!CATCHING_PANICS.with(Cell::get));
+
+        //the interpreter keeps working after the panic
+        assert_eq!(
+            interpreter.interpret(String::from("a + b")),
+            Some(Literal::Number(3))
+        );
+    }
+
+    #[test]
+    fn script_args() {
+        let mut interpreter = Interpreter::new();
+        let args = ["10", "--name=estel", "--flag", "--empty="].map(String::from);
+        assert_eq!(interpreter.set_args(&args), Ok(()));
+        let string = |value: &str| Literal::String(value.to_owned());
+        assert_eq!(
+            interpreter.executor.get_var("ARGS"),
+            Some(Literal::Tuple(vec![string("10"), string("--flag")]))
+        );
+        assert_eq!(interpreter.executor.get_var("name"), Some(string("estel")));
+        assert_eq!(interpreter.executor.get_var("empty"), Some(string("")));
+
+        for key in ["1st", "while", "ARGS", ""] {
+            let args = [format!("--{}=1", key)];
+            assert_eq!(interpreter.set_args(&args), Err(key.to_owned()));
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::interpreter::Interpreter;
+use crate::token::Literal;
+use colored::Colorize;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+//How the answer to a lesson is checked
+pub enum Check {
+    //the answer evaluates to the value
+    Value(Literal),
+    //the answer leaves the variable holding the value
+    Variable(&'static str, Literal),
+    //the answer prints exactly this text, line breaks included
+    Prints(&'static str),
+}
+
+pub struct Lesson {
+    pub title: &'static str,
+    pub explanation: &'static str,
+    pub example: &'static str,
+    pub task: &'static str,
+    //Code run before the answer, eg- to declare the variables used by the task
+    pub setup: &'static str,
+    pub check: Check,
+    //Shown when the lesson is skipped
+    pub solution: &'static str,
+}
+
+//The runs of loop bodies an answer may take, so an answer which never ends is stopped and fails
+const ANSWER_LOOP_LIMIT: usize = 100_000;
+
+impl Lesson {
+    //Run the answer on a fresh interpreter and check it solves the task
+    pub fn check_answer(&self, answer: &str) -> bool {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_loop_limit(Some(ANSWER_LOOP_LIMIT));
+        interpreter.interpret(self.setup.to_owned());
+        let printed = Printed::default();
+        if let Check::Prints(_) = self.check {
+            interpreter.set_stdout(printed.clone());
+        }
+        let value = interpreter.interpret(answer.to_owned());
+        //a stopped answer fails, whatever it did before
+        if interpreter.exit_code().is_some_and(|code| code != 0) {
+            return false;
+        }
+        match &self.check {
+            Check::Value(expected) => value.as_ref() == Some(expected),
+            Check::Variable(name, expected) => {
+                interpreter.interpret(name.to_string()).as_ref() == Some(expected)
+            }
+            Check::Prints(expected) => {
+                let printed = printed.0.borrow();
+                printed.as_slice() == expected.as_bytes()
+            }
+        }
+    }
+}
+
+//Collects what an answer prints, shared with the interpreter running it
+#[derive(Default, Clone)]
+struct Printed(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Printed {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub fn lessons() -> Vec<Lesson> {
+    vec![
+        Lesson {
+            title: "Printing",
+            explanation: "print shows the value of an expression.",
+            example: "print \"Hello, world!\"",
+            task: "Print the number 42.",
+            setup: "",
+            check: Check::Prints("42\n"),
+            solution: "print 42",
+        },
+        Lesson {
+            title: "Arithmetic",
+            explanation: "Numbers can be combined with + - * /, and * and / are done before + and -.",
+            example: "(2 + 3) * 4",
+            task: "Write an expression multiplying 6 by 7.",
+            setup: "",
+            check: Check::Value(Literal::Number(42)),
+            solution: "6 * 7",
+        },
+        Lesson {
+            title: "Variables",
+            explanation: "let declares a variable, and a variable can be changed with =.",
+            example: "let name = \"estel\"\nname = \"ESTEL\"",
+            task: "Declare a variable called age holding 30.",
+            setup: "",
+            check: Check::Variable("age", Literal::Number(30)),
+            solution: "let age = 30",
+        },
+        Lesson {
+            title: "Loops",
+            explanation: "while runs a block again and again while its condition is true, for also sets up a counter and steps it after each run.",
+            example: "let i = 1\nwhile i <= 3 {\n    print i\n    i += 1\n}\nfor (let j = 1; j <= 3; j += 1) { print j }",
+            task: "The variable total holds 0. Use a loop to add the numbers from 1 to 10 to it.",
+            setup: "let total = 0",
+            check: Check::Variable("total", Literal::Number(55)),
+            solution: "for (let i = 1; i <= 10; i += 1) { total += i }",
+        },
+        Lesson {
+            title: "Tuples",
+            explanation: "A tuple groups values in parenthesis, its items are read with a dot and their position, starting at 0.",
+            example: "let pair = (\"a\", 1)\npair.0",
+            task: "The variable point holds (3, 4). Get its second item.",
+            setup: "let point = (3, 4)",
+            check: Check::Value(Literal::Number(4)),
+            solution: "point.1",
+        },
+        Lesson {
+            title: "Types",
+            explanation: "type() names the type of a value, is checks a type and as converts a value to another type.",
+            example: "type(1.5)\n5 is number\n\"12\" as number",
+            task: "The variable text holds \"12\". Convert it to a number.",
+            setup: "let text = \"12\"",
+            check: Check::Value(Literal::Number(12)),
+            solution: "text as number",
+        },
+    ]
+}
+
+//Walk the user through every lesson, checking their answers
+//typing !skip shows the solution and moves on, !q or !quit stops the tutorial
+pub fn run_tutorial() {
+    println!(
+        "{}",
+        "Welcome to estel! Answer each task to move on, !skip to skip a lesson or !q to quit."
+            .green()
+    );
+    let lessons = lessons();
+    for (number, lesson) in (1..).zip(&lessons) {
+        println!();
+        println!(
+            "{}",
+            format!("Lesson {}/{}: {}", number, lessons.len(), lesson.title).green()
+        );
+        println!("{}", lesson.explanation);
+        println!("Example:");
+        for line in lesson.example.lines() {
+            println!("    {}", line.cyan());
+        }
+        println!("{}", lesson.task.bold());
+        loop {
+            print!(">>>>");
+            io::stdout().flush().unwrap();
+            let mut answer = String::new();
+            //stop at the end of input
+            if io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+                return;
+            }
+            match answer.trim() {
+                "!q" | "!quit" => return,
+                "!skip" => {
+                    println!("One solution is: {}", lesson.solution.cyan());
+                    break;
+                }
+                answer => {
+                    if lesson.check_answer(answer) {
+                        println!("{}", "Correct!".green());
+                        break;
+                    }
+                    println!("{}", "Not quite, try again.".yellow());
+                }
+            }
+        }
+    }
+    println!();
+    println!("{}", "You finished every lesson!".green());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lesson_solutions() {
+        for lesson in lessons() {
+            assert!(lesson.check_answer(lesson.solution), "{}", lesson.title);
+            assert!(!lesson.check_answer("0"), "{}", lesson.title);
+        }
+        //printing needs a print statement, not just the value
+        assert!(!lessons()[0].check_answer("42"));
+        //the whole output is compared, extra or wrong lines fail
+        assert!(!lessons()[0].check_answer("print 1\nprint 42"));
+        assert!(!lessons()[0].check_answer("print 42\nprint 42"));
+        assert!(!lessons()[0].check_answer("print 420"));
+        assert!(lessons()[0].check_answer("print 40 + 2"));
+        assert!(lessons()[1].check_answer("let a = 6\na * 7"));
+        let loops = &lessons()[3];
+        assert!(loops.check_answer("let i = 1\nwhile i <= 10 {\n    total += i\n    i += 1\n}"));
+        //answers which never end are stopped and fail
+        assert!(!loops.check_answer("while true {}"));
+        assert!(!loops.check_answer("while true { total = 55 }"));
+        assert!(!loops.check_answer("do { total = 55 } while true"));
+        assert!(!lessons()[0].check_answer("print 42\nfor (;;) {}"));
+    }
+}
@@ -1,4 +1,17 @@
 use parser::{lexer, token};
+pub mod ast_diff;
+pub mod config;
 pub mod errors;
+pub mod examples;
+pub mod executor;
 pub mod interpreter;
-mod parser;
+pub mod learn;
+pub mod output;
+pub mod parser;
+pub mod pipeline;
+pub mod shared;
+pub mod source;
+pub mod store;
+
+pub use parser::semantics::Semantics;
+pub use parser::token::Literal;
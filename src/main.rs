@@ -1,16 +1,352 @@
+use colored::Colorize;
+use estel::ast_diff::{diff, format_diff, parse_tree};
+use estel::config::{parse_define, parse_env, ENV_FILE};
+use estel::errors::ErrorHandler;
+use estel::examples::{find_example, print_examples};
+use estel::executor::{EDUCATION_ITERATIONS, STACK_SIZE};
 use estel::interpreter::Interpreter;
-use std::{env, fs};
+use estel::learn::run_tutorial;
+use estel::output::OutputMode;
+use estel::parser::features::{Feature, Features};
+use estel::parser::linter::{LintConfig, Rule};
+use estel::pipeline::{self, PhaseMask};
+use estel::source::{normalize_source, read_source};
+use estel::store::JsonFileStore;
+use estel::{Literal, Semantics};
+use std::io::{self, Write};
+use std::path::Path;
+use std::{env, fs, process, thread};
+
+//The unchanged nodes shown around each change of diff-ast
+const AST_DIFF_CONTEXT: usize = 3;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    //programs are run recursively, so they get a thread with a stack large enough for deep calls
+    let runner = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .expect("Failed to start the interpreter thread");
+    if runner.join().is_err() {
+        process::exit(1);
+    }
+}
+
+fn run() {
+    let mut args: Vec<String> = env::args().collect();
+    //the arguments after -- are given to the program run
+    let script_args = match args.iter().skip(2).position(|arg| arg == "--") {
+        Some(split) => args.split_off(split + 2)[1..].to_vec(),
+        None => Vec::new(),
+    };
+    let defines = take_defines(&mut args);
+    let features = take_features(&mut args);
     let mut interpreter = Interpreter::new();
-    if args.len() == 1 {
-        interpreter.run_prompt();
+    interpreter.set_features(features);
+    match args.get(1).map(String::as_str) {
+        None => interpreter.run_prompt(),
+        Some("learn") => run_tutorial(),
+        Some("diff-ast") => diff_ast(&args, features),
+        Some("lint") => lint_files(args[2..].to_vec()),
+        Some("fix") => fix_files(&args[2..]),
+        Some("examples") => match args.get(2) {
+            None => {
+                //the output may be piped to a reader which stops early
+                let _ = print_examples(&mut io::stdout());
+            }
+            Some(name) => match find_example(name) {
+                Some(example) => {
+                    interpreter.interpret(example.source.to_owned());
+                }
+                None => {
+                    eprintln!(
+                        "{}",
+                        format!("Error: No example named {}", name).bright_red()
+                    );
+                    let _ = print_examples(&mut io::stderr());
+                    process::exit(1);
+                }
+            },
+        },
+        //print output, warnings and errors are written as JSON events, one per line of stdout
+        Some(option @ "--json-output") => {
+            let file = file_arg(&args, option);
+            interpreter.set_warnings(true);
+            interpreter.set_output(OutputMode::Json);
+            run_file(&mut interpreter, file, &defines, &script_args);
+        }
+        //run a file reporting how long each phase took, for finding slow parts of the interpreter
+        Some(option @ "-v") => run_verbose(file_arg(&args, option)),
+        //booleans only in conditions, no string repetition or conversion, variables declared before use and not shadowed
+        Some(option @ "--strict") => {
+            let file = file_arg(&args, option);
+            interpreter.set_warnings(true);
+            interpreter.set_semantics(Semantics::strict());
+            run_file(&mut interpreter, file, &defines, &script_args);
+        }
+        //warn about loops which may never end, without stopping them
+        Some(option @ "--education") => {
+            let file = file_arg(&args, option);
+            interpreter.set_warnings(true);
+            interpreter.set_iteration_warning(Some(EDUCATION_ITERATIONS));
+            run_file(&mut interpreter, file, &defines, &script_args);
+        }
+        Some(file) => {
+            interpreter.set_warnings(true);
+            run_file(&mut interpreter, file, &defines, &script_args);
+        }
+    }
+    //the program stopped with exit, its code is the code of the process
+    if let Some(code) = interpreter.exit_code() {
+        let _ = io::stdout().flush();
+        process::exit(code);
+    }
+}
+
+//The globals are set in order, so --define overrides estel.env and named arguments override both
+fn run_file(
+    interpreter: &mut Interpreter,
+    file: &str,
+    defines: &[(String, Literal)],
+    script_args: &[String],
+) {
+    let source = open_file(file);
+    let env = if Path::new(ENV_FILE).exists() {
+        match parse_env(&open_file(ENV_FILE)) {
+            Ok(env) => env,
+            Err((line, err)) => exit_with_error(&format!(
+                "{} in {} at line {}",
+                err.get_message(),
+                ENV_FILE,
+                line
+            )),
+        }
     } else {
-        interpreter.interpret(open_file(&args[1]));
+        Vec::new()
+    };
+    for (name, value) in env.iter().chain(defines) {
+        if let Err(err) = interpreter.define(name, value.clone()) {
+            exit_with_error(&format!("Can't define {}, {:?}", name, err));
+        }
+    }
+    if let Err(key) = interpreter.set_args(script_args) {
+        exit_with_error(&format!(
+            "{} is not a valid variable name, named arguments are given as --name=value",
+            key
+        ));
+    }
+    //values kept with store_set() are saved next to the script, eg- game.estel.store.json
+    match JsonFileStore::open(format!("{}.store.json", file)) {
+        Ok(store) => interpreter.set_store(store),
+        Err(err) => exit_with_error(&err),
+    }
+    //the internal error is reported before the hook runs, a script stopped by one fails
+    interpreter.set_panic_hook(|_| process::exit(1));
+    interpreter.interpret(source);
+}
+
+//the problems found are printed after the program runs, followed by the phase summary
+fn run_verbose(file: &str) {
+    let source = normalize_source(&open_file(file));
+    let report = pipeline::run(&source, PhaseMask::ALL);
+    let error_handler = ErrorHandler::new(&source, OutputMode::Text);
+    for diagnostic in &report.diagnostics {
+        error_handler.report(diagnostic);
+    }
+    eprintln!("{}", report.summary().bright_cyan());
+    if let Some(code) = report.exit_code {
+        let _ = io::stdout().flush();
+        process::exit(code);
+    }
+}
+
+//Print the changes between the syntax trees of two versions of a script, eg- to check a refactor kept its meaning
+//exits with 1 if the trees differ, like diff, and with 2 if a file can't be parsed
+fn diff_ast(args: &[String], features: Features) {
+    let (Some(old), Some(new)) = (args.get(2), args.get(3)) else {
+        exit_with_error(
+            "diff-ast needs two files to compare, eg- estel diff-ast old.estel new.estel",
+        );
+    };
+    let parse = |file: &str| {
+        let source = open_file(file);
+        parse_tree(&source, features).unwrap_or_else(|errors| {
+            let error_handler = ErrorHandler::new(&source, OutputMode::Text);
+            for error in &errors {
+                error_handler.report(error);
+            }
+            eprintln!("{}", format!("Error: Can't parse {}", file).bright_red());
+            process::exit(2);
+        })
+    };
+    let (old, new) = (parse(old), parse(new));
+    let changes = format_diff(&diff(&old, &new), AST_DIFF_CONTEXT);
+    if !changes.is_empty() {
+        print!("{}", changes);
+        let _ = io::stdout().flush();
+        process::exit(1);
+    }
+}
+
+//Check the style of scripts, eg- estel lint --rules=magic-number,short-name main.estel
+//exits with 1 if any file has a warning or can't be parsed
+fn lint_files(mut args: Vec<String>) {
+    let config = take_lint_config(&mut args);
+    if args.is_empty() {
+        exit_with_error("lint needs a file to check, eg- estel lint main.estel");
+    }
+    let mut clean = true;
+    for file in &args {
+        let source = normalize_source(&open_file(file));
+        let report = pipeline::lint(&source, &config);
+        let error_handler = ErrorHandler::new(&source, OutputMode::Text);
+        if !report.diagnostics.is_empty() {
+            clean = false;
+            eprintln!("{}", file.bright_cyan());
+        }
+        for diagnostic in &report.diagnostics {
+            error_handler.report(diagnostic);
+        }
+    }
+    if !clean {
+        process::exit(1);
+    }
+}
+
+//Apply the fixes of the errors found in scripts, eg- a missing ; or = in place of ==, rewriting the files
+//exits with 1 if errors without a fix are left in any file
+fn fix_files(files: &[String]) {
+    if files.is_empty() {
+        exit_with_error("fix needs a file to fix, eg- estel fix main.estel");
+    }
+    let mut clean = true;
+    for file in files {
+        let (source, fixes) = pipeline::fix(&open_file(file));
+        if !fixes.is_empty() {
+            if let Err(err) = fs::write(file, &source) {
+                exit_with_error(&format!("Failed to write {}: {}", file, err));
+            }
+            for fix in &fixes {
+                println!("{} line {}: {}", file, fix.line, fix.message);
+            }
+        }
+        let report = pipeline::run(&source, PhaseMask::LEX | PhaseMask::PARSE);
+        if report.had_error() {
+            clean = false;
+            eprintln!("{}", file.bright_cyan());
+            let error_handler = ErrorHandler::new(&source, OutputMode::Text);
+            for diagnostic in &report.diagnostics {
+                error_handler.report(diagnostic);
+            }
+        }
+    }
+    if !clean {
+        process::exit(1);
+    }
+}
+
+//Remove the options of estel lint from its arguments, exits if one is invalid
+//--rules=a,b checks only the rules named, --max-depth=n and --max-nodes=n set the limits
+fn take_lint_config(args: &mut Vec<String>) -> LintConfig {
+    let mut config = LintConfig::default();
+    let limit = |value: &str, option: &str| match value.parse::<usize>() {
+        Ok(limit) if limit > 0 => limit,
+        _ => exit_with_error(&format!("{} needs a number above 0, got {}", option, value)),
+    };
+    args.retain(|arg| {
+        let Some((option, value)) = arg.split_once('=') else {
+            return true;
+        };
+        match option {
+            "--rules" => {
+                config.rules = value
+                    .split(',')
+                    .map(|name| {
+                        Rule::from_name(name.trim()).unwrap_or_else(|| {
+                            let names: Vec<&str> = Rule::ALL.iter().map(Rule::name).collect();
+                            exit_with_error(&format!(
+                                "{} is not a lint rule, the rules are {}",
+                                name,
+                                names.join(", ")
+                            ))
+                        })
+                    })
+                    .collect()
+            }
+            "--max-depth" => config.max_depth = limit(value, option),
+            "--max-nodes" => config.max_nodes = limit(value, option),
+            _ => return true,
+        }
+        false
+    });
+    config
+}
+
+//Remove the --define name=value options from the arguments, exits if one is invalid
+fn take_defines(args: &mut Vec<String>) -> Vec<(String, Literal)> {
+    let mut defines = Vec::new();
+    while let Some(index) = args.iter().position(|arg| arg == "--define") {
+        let definition = match args.get(index + 1) {
+            Some(definition) => definition.clone(),
+            None => exit_with_error("--define needs a variable to set, eg- --define count=5"),
+        };
+        match parse_define(&definition) {
+            Ok(define) => defines.push(define),
+            Err(err) => exit_with_error(&err.get_message()),
+        }
+        args.drain(index..=index + 1);
+    }
+    defines
+}
+
+//Remove the --features=a,b options from the arguments, exits if a feature is not known
+fn take_features(args: &mut Vec<String>) -> Features {
+    let mut features = Features::default();
+    while let Some(index) = args.iter().position(|arg| arg.starts_with("--features=")) {
+        let list = args.remove(index);
+        match Features::parse(&list["--features=".len()..]) {
+            Ok(more) => features = features | more,
+            Err(name) => {
+                let names: Vec<&str> = Feature::ALL.iter().map(Feature::name).collect();
+                exit_with_error(&format!(
+                    "{} is not a feature, the experimental features are {}",
+                    name,
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+    features
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", format!("Error: {}", message).bright_red());
+    process::exit(1);
+}
+
+//The file following an option, exits if it is missing
+fn file_arg<'a>(args: &'a [String], option: &str) -> &'a str {
+    match args.get(2) {
+        Some(file) => file,
+        None => {
+            eprintln!(
+                "{}",
+                format!(
+                    "Error: {} needs a file to run, eg- estel {} main.estel",
+                    option, option
+                )
+                .bright_red()
+            );
+            process::exit(1);
+        }
     }
 }
 
 fn open_file(file: &str) -> String {
-    fs::read_to_string(file).expect("Failed to read file")
+    match read_source(file) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{}", format!("Error: {}", err.get_message()).bright_red());
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,333 @@
+use crate::errors::Fix;
+use colored::{Color, Colorize};
+use std::time::{Duration, Instant};
+
+//How the output of programs and the problems found in them are reported
+//Json writes every event as a JSON object on its own line of stdout, for graders and editors to read
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputMode {
+    #[default]
+    Text,
+    Json,
+}
+
+//The step of running a program an error was found in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Lex,
+    Parse,
+    Analyze,
+    Run,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lex => "lex",
+            Self::Parse => "parse",
+            Self::Analyze => "analyze",
+            Self::Run => "run",
+        }
+    }
+}
+
+//Something reported while running a program in the Json output mode
+//positions are (line, position) pairs, like in the errors printed as text
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    //A value printed by a print statement
+    Print(String),
+    //The value of an expression statement, only reported in prompt mode
+    Result(String),
+    //Error(Phase, Message, Position, Fix), the position is not known for errors found while running
+    //the fix is a change to the source resolving the error, given for some errors found before running
+    Error(Phase, String, Option<(u32, u32)>, Option<Fix>),
+    //Warning(Message, Hint, Position)
+    Warning(String, String, (u32, u32)),
+    //Progress(Done, Total), reported by progress() at every tenth of the total
+    Progress(i32, i32),
+}
+
+impl Event {
+    pub fn to_json(&self) -> String {
+        let mut fields = Vec::new();
+        let mut position = None;
+        match self {
+            Self::Print(text) => {
+                fields.push(("event", json_string("print")));
+                fields.push(("text", json_string(text)));
+            }
+            Self::Result(text) => {
+                fields.push(("event", json_string("result")));
+                fields.push(("text", json_string(text)));
+            }
+            Self::Error(phase, message, at, fix) => {
+                fields.push(("event", json_string("error")));
+                fields.push(("phase", json_string(phase.name())));
+                fields.push(("message", json_string(message)));
+                if let Some(fix) = fix {
+                    fields.push(("fix", fix_json(fix)));
+                }
+                position = *at;
+            }
+            Self::Warning(message, hint, at) => {
+                fields.push(("event", json_string("warning")));
+                fields.push(("message", json_string(message)));
+                fields.push(("hint", json_string(hint)));
+                position = Some(*at);
+            }
+            Self::Progress(done, total) => {
+                fields.push(("event", json_string("progress")));
+                fields.push(("done", done.to_string()));
+                fields.push(("total", total.to_string()));
+            }
+        }
+        if let Some((line, start)) = position {
+            fields.push(("line", line.to_string()));
+            fields.push(("position", start.to_string()));
+        }
+        let fields: Vec<String> = fields
+            .into_iter()
+            .map(|(name, value)| format!("\"{}\":{}", name, value))
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    }
+
+    //Write the event as a line of stdout
+    pub fn emit(&self) {
+        println!("{}", self.to_json());
+    }
+}
+
+//How often a progress bar on a terminal is redrawn at most
+const PROGRESS_REDRAW: Duration = Duration::from_millis(100);
+
+//Decides when progress() shows the progress of a loop, so calling it on every step stays cheap
+//on a terminal the bar is redrawn in place at most every PROGRESS_REDRAW
+//otherwise a line is written at every tenth of the total, as the lines can't be redrawn
+#[derive(Debug, Default)]
+pub struct Progress {
+    last_draw: Option<Instant>,
+    last_tenth: Option<i64>,
+}
+
+impl Progress {
+    //Whether the progress should be shown now, the last step of a loop is always shown
+    pub fn update(&mut self, done: i32, total: i32, interactive: bool, now: Instant) -> bool {
+        let finished = done >= total;
+        let show = if interactive {
+            finished
+                || self
+                    .last_draw
+                    .is_none_or(|last| now.duration_since(last) >= PROGRESS_REDRAW)
+        } else {
+            let tenth = done as i64 * 10 / total as i64;
+            self.last_tenth != Some(tenth)
+        };
+        if finished {
+            //the next call starts another loop
+            *self = Self::default();
+        } else if show {
+            self.last_draw = Some(now);
+            self.last_tenth = Some(done as i64 * 10 / total as i64);
+        }
+        show
+    }
+}
+
+//Draw the progress of a loop, eg- [##########          ]  50% (5/10)
+pub fn progress_bar(done: i32, total: i32) -> String {
+    const WIDTH: i64 = 20;
+    let filled = (done as i64 * WIDTH / total as i64) as usize;
+    let percent = done as i64 * 100 / total as i64;
+    format!(
+        "[{}{}] {:>3}% ({}/{})",
+        "#".repeat(filled),
+        " ".repeat(WIDTH as usize - filled),
+        percent,
+        done,
+        total
+    )
+}
+
+//Style text for the terminal, eg- style_text("done", Some("green"), true)
+//colors are names like "red" or "bright blue", None if the color is not known
+//the text is left plain when colors are turned off, eg- by NO_COLOR or when stdout is not a terminal
+pub fn style_text(text: &str, color: Option<&str>, bold: bool) -> Option<String> {
+    let mut styled = text.normal();
+    if let Some(color) = color {
+        styled = styled.color(color.parse::<Color>().ok()?);
+    }
+    if bold {
+        styled = styled.bold();
+    }
+    Some(styled.to_string())
+}
+
+//Lay out rows of cells in aligned columns, each cell is (text, right aligned)
+//the header row is underlined with dashes, rows shorter than others are padded with empty cells
+pub fn format_table(headers: Option<&[String]>, rows: &[Vec<(String, bool)>]) -> String {
+    let columns = rows
+        .iter()
+        .map(Vec::len)
+        .chain(headers.map(<[String]>::len))
+        .max()
+        .unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for (i, header) in headers.into_iter().flatten().enumerate() {
+        widths[i] = header.chars().count();
+    }
+    for row in rows {
+        for (i, (text, _)) in row.iter().enumerate() {
+            widths[i] = widths[i].max(text.chars().count());
+        }
+    }
+
+    let mut lines = Vec::new();
+    let format_row = |cells: Vec<(&str, bool)>| {
+        let cells: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(i, &width)| match cells.get(i) {
+                Some((text, true)) => format!("{:>width$}", text),
+                Some((text, false)) => format!("{:<width$}", text),
+                None => " ".repeat(width),
+            })
+            .collect();
+        cells.join("  ").trim_end().to_owned()
+    };
+    if let Some(headers) = headers {
+        lines.push(format_row(
+            headers.iter().map(|h| (h.as_str(), false)).collect(),
+        ));
+        let rules: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+        lines.push(rules.join("  "));
+    }
+    for row in rows {
+        lines.push(format_row(
+            row.iter()
+                .map(|(text, right)| (text.as_str(), *right))
+                .collect(),
+        ));
+    }
+    lines.join("\n")
+}
+
+//Write a fix as a JSON object, for editors to offer it as an action on the error
+fn fix_json(fix: &Fix) -> String {
+    format!(
+        "{{\"message\":{},\"line\":{},\"start\":{},\"end\":{},\"replacement\":{}}}",
+        json_string(&fix.message),
+        fix.line,
+        fix.start,
+        fix.end,
+        json_string(&fix.replacement)
+    )
+}
+
+//Quote text as a JSON string, escaping the characters JSON does not allow in strings
+pub(crate) fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_json() {
+        assert_eq!(
+            Event::Print(String::from("say \"hi\"\n\\ é")).to_json(),
+            r#"{"event":"print","text":"say \"hi\"\n\\ é"}"#
+        );
+        assert_eq!(
+            Event::Error(Phase::Run, String::from("InvalidTypeError"), None, None).to_json(),
+            r#"{"event":"error","phase":"run","message":"InvalidTypeError"}"#
+        );
+        assert_eq!(
+            Event::Error(
+                Phase::Parse,
+                String::from("Unterminated map"),
+                Some((2, 8)),
+                None
+            )
+            .to_json(),
+            r#"{"event":"error","phase":"parse","message":"Unterminated map","line":2,"position":8}"#
+        );
+        let fix = Fix::new("Replace = with ==", (1, 5), 6, "==");
+        assert_eq!(
+            Event::Error(
+                Phase::Parse,
+                String::from("Expected an operator"),
+                Some((1, 5)),
+                Some(fix)
+            )
+            .to_json(),
+            r#"{"event":"error","phase":"parse","message":"Expected an operator","fix":{"message":"Replace = with ==","line":1,"start":5,"end":6,"replacement":"=="},"line":1,"position":5}"#
+        );
+        assert_eq!(
+            Event::Progress(5, 10).to_json(),
+            r#"{"event":"progress","done":5,"total":10}"#
+        );
+        assert_eq!(json_string("\u{1b}[0m\t"), r#""\u001b[0m\t""#);
+    }
+
+    #[test]
+    fn progress() {
+        assert_eq!(progress_bar(5, 10), "[##########          ]  50% (5/10)");
+        assert_eq!(progress_bar(3, 3), "[####################] 100% (3/3)");
+
+        //a terminal is redrawn when enough time passed, and always at the end
+        let start = Instant::now();
+        let mut progress = Progress::default();
+        assert!(progress.update(0, 100, true, start));
+        assert!(!progress.update(1, 100, true, start + Duration::from_millis(50)));
+        assert!(progress.update(2, 100, true, start + PROGRESS_REDRAW));
+        assert!(progress.update(100, 100, true, start + PROGRESS_REDRAW));
+
+        //otherwise a line is written at every tenth of the total
+        let shown: Vec<i32> = (0..=100)
+            .filter(|&done| progress.update(done, 100, false, start))
+            .collect();
+        assert_eq!(shown, [0, 10, 20, 30, 40, 50, 60, 70, 80, 90, 100]);
+    }
+
+    #[test]
+    fn styled_text() {
+        colored::control::set_override(false);
+        assert_eq!(
+            style_text("hi", Some("bright red"), true),
+            Some(String::from("hi"))
+        );
+        assert_eq!(style_text("hi", Some("reddish"), false), None);
+    }
+
+    #[test]
+    fn table() {
+        let cell = |text: &str, right| (text.to_owned(), right);
+        let rows = [
+            vec![cell("apple", false), cell("3", true), cell("1.5", true)],
+            vec![cell("fig", false), cell("12", true)],
+        ];
+        assert_eq!(format_table(None, &rows), "apple   3  1.5\nfig    12");
+        let headers = [String::from("fruit"), String::from("count")];
+        assert_eq!(
+            format_table(Some(&headers), &rows),
+            "fruit  count\n-----  -----  ---\napple      3  1.5\nfig       12"
+        );
+        assert_eq!(format_table(None, &[]), "");
+    }
+}
@@ -0,0 +1,1001 @@
+use super::errors::Warning;
+use super::expr::{Expr, Position};
+use super::features::DEPRECATED_BUILTINS;
+use super::stmt::{Accessor, Block, Span, Stmt};
+use super::token::{Literal, Operator};
+use super::visitor::{walk_expr, walk_stmt, ExprVisitor, StmtVisitor};
+use std::collections::{HashMap, HashSet};
+
+//Finds code that is valid but most likely a mistake, before the program runs
+pub struct Analyzer {
+    warnings: Vec<Warning>,
+}
+
+impl Analyzer {
+    pub fn analyze(block: &Block) -> Vec<Warning> {
+        let mut analyzer = Self {
+            warnings: Vec::new(),
+        };
+        analyzer.check_unused_exprs(block);
+        analyzer.check_dead_stores(block, false);
+        analyzer.check_constants(block, HashMap::new());
+        analyzer.check_deprecated(block, DEPRECATED_BUILTINS);
+        analyzer.warnings
+    }
+
+    //Find the calls of deprecated builtins, deprecated: (builtin, replacement) pairs
+    fn check_deprecated(
+        &mut self,
+        block: &Block,
+        deprecated: &'static [(&'static str, &'static str)],
+    ) {
+        let mut finder = DeprecationFinder {
+            deprecated,
+            span: Span::default(),
+            warnings: &mut self.warnings,
+        };
+        finder.visit_block(block);
+    }
+
+    //An expression statement is useless if its value is dropped and evaluating it does nothing else
+    fn check_unused_exprs(&mut self, block: &Block) {
+        for (stmt, span) in block.iter() {
+            match stmt {
+                Stmt::Expr(expr) if !CallFinder::has_call(expr) => {
+                    self.warnings.push(Warning::UnusedExpression(*span));
+                }
+                Stmt::Block(block) => self.check_unused_exprs(block),
+                Stmt::FnDecl(function) => self.check_unused_exprs(&function.body),
+                Stmt::If(_, then, otherwise) => {
+                    self.check_unused_exprs(then);
+                    if let Some(otherwise) = otherwise {
+                        self.check_unused_exprs(otherwise);
+                    }
+                }
+                Stmt::While(_, body) | Stmt::DoWhile(_, body) | Stmt::For(.., body) => {
+                    self.check_unused_exprs(body)
+                }
+                Stmt::Try(body, _, catch) => {
+                    self.check_unused_exprs(body);
+                    self.check_unused_exprs(catch);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    //Find values stored in a variable and stored over before any read
+    //statements in a block always run in order, so the stores reaching each statement are known exactly
+    //caught: the block is in the body of a try, so any statement can jump to the catch block
+    fn check_dead_stores(&mut self, block: &Block, caught: bool) {
+        //the store of each variable which has not been read yet
+        let mut unread: HashMap<&str, Span> = HashMap::new();
+        for (stmt, span) in block.iter() {
+            //a called function or a catch block can read any variable it sees
+            if caught || has_call(stmt) {
+                unread.clear();
+            }
+            //the expression is evaluated before its value is stored
+            let (names, expr): (Vec<&String>, &Expr) = match stmt {
+                Stmt::Expr(expr)
+                | Stmt::Print(expr)
+                | Stmt::Return(Some(expr))
+                | Stmt::Exit(Some(expr))
+                | Stmt::Throw(expr) => (Vec::new(), expr),
+                Stmt::Assign(name, expr) | Stmt::Const(name, expr) | Stmt::Reassign(name, expr) => {
+                    (vec![name], expr)
+                }
+                Stmt::Destructure(names, expr) => (names.iter().collect(), expr),
+                //changing an item keeps the rest of the stored value, so it counts as a read
+                Stmt::IndexAssign(name, accessors, expr) => {
+                    unread.remove(name.as_str());
+                    for expr in accessors.iter().filter_map(Accessor::index).chain([expr]) {
+                        for name in IdentFinder::idents(expr) {
+                            unread.remove(name.as_str());
+                        }
+                    }
+                    continue;
+                }
+                Stmt::Assert(condition, message) => {
+                    for expr in [Some(condition), message.as_ref()].into_iter().flatten() {
+                        for name in IdentFinder::idents(expr) {
+                            unread.remove(name.as_str());
+                        }
+                    }
+                    continue;
+                }
+                //a nested block can read any variable, it is checked on its own
+                Stmt::Block(block) => {
+                    unread.clear();
+                    self.check_dead_stores(block, caught);
+                    continue;
+                }
+                Stmt::If(_, then, otherwise) => {
+                    unread.clear();
+                    self.check_dead_stores(then, caught);
+                    if let Some(otherwise) = otherwise {
+                        self.check_dead_stores(otherwise, caught);
+                    }
+                    continue;
+                }
+                Stmt::While(_, body) | Stmt::DoWhile(_, body) | Stmt::For(.., body) => {
+                    unread.clear();
+                    self.check_dead_stores(body, caught);
+                    continue;
+                }
+                Stmt::Try(body, _, catch) => {
+                    unread.clear();
+                    self.check_dead_stores(body, true);
+                    self.check_dead_stores(catch, caught);
+                    continue;
+                }
+                //the body runs when the function is called, not where it is declared
+                Stmt::FnDecl(function) => {
+                    self.check_dead_stores(&function.body, false);
+                    continue;
+                }
+                Stmt::Struct(_) => continue,
+                Stmt::Return(None) | Stmt::Exit(None) | Stmt::Break | Stmt::Continue => {
+                    unread.clear();
+                    continue;
+                }
+            };
+            for name in IdentFinder::idents(expr) {
+                unread.remove(name.as_str());
+            }
+            //the block is left here, so a later store is not reached from the stores before it
+            //and the code the statement jumps to may read them
+            if matches!(stmt, Stmt::Return(_) | Stmt::Exit(_) | Stmt::Throw(_)) {
+                unread.clear();
+            }
+            for name in names {
+                if let Some(store) = unread.insert(name, *span) {
+                    self.warnings
+                        .push(Warning::DeadStore(name.to_owned(), store, *span));
+                }
+            }
+        }
+    }
+
+    //Track the values of variables holding constants through the block
+    //and report arithmetic on constants which is guaranteed to fail
+    //constants: the constant variables when the block starts
+    fn check_constants(&mut self, block: &Block, mut constants: HashMap<String, Literal>) {
+        for (stmt, span) in block.iter() {
+            //a called function can change any variable it sees
+            if has_call(stmt) {
+                constants.clear();
+            }
+            let mut checker = ConstantChecker::new(&constants, *span, &mut self.warnings);
+            match stmt {
+                Stmt::Expr(expr)
+                | Stmt::Print(expr)
+                | Stmt::Return(Some(expr))
+                | Stmt::Exit(Some(expr))
+                | Stmt::Throw(expr) => checker.visit_expr(expr),
+                Stmt::Struct(_)
+                | Stmt::Return(None)
+                | Stmt::Exit(None)
+                | Stmt::Break
+                | Stmt::Continue => {}
+                Stmt::Assert(condition, message) => {
+                    checker.visit_expr(condition);
+                    if let Some(message) = message {
+                        checker.visit_expr(message);
+                    }
+                }
+                Stmt::Assign(name, expr) | Stmt::Const(name, expr) | Stmt::Reassign(name, expr) => {
+                    checker.visit_expr(expr);
+                    match fold(expr, &constants) {
+                        Some(value) => constants.insert(name.to_owned(), value),
+                        None => constants.remove(name),
+                    };
+                }
+                Stmt::IndexAssign(name, accessors, expr) => {
+                    for expr in accessors.iter().filter_map(Accessor::index).chain([expr]) {
+                        checker.visit_expr(expr);
+                    }
+                    constants.remove(name);
+                }
+                Stmt::Destructure(names, expr) => {
+                    checker.visit_expr(expr);
+                    match fold(expr, &constants) {
+                        Some(Literal::Tuple(items)) if items.len() == names.len() => {
+                            constants.extend(names.iter().cloned().zip(items));
+                        }
+                        _ => names.iter().for_each(|name| {
+                            constants.remove(name);
+                        }),
+                    }
+                }
+                //the block may change any variable, so none are known to be constant after it
+                Stmt::Block(block) => {
+                    self.check_constants(block, constants.clone());
+                    constants.clear();
+                }
+                //the parameters and the variables the body sees are only known when it is called
+                Stmt::FnDecl(function) => self.check_constants(&function.body, HashMap::new()),
+                Stmt::If(condition, then, otherwise) => {
+                    checker.visit_expr(condition);
+                    self.check_constants(then, constants.clone());
+                    if let Some(otherwise) = otherwise {
+                        self.check_constants(otherwise, constants.clone());
+                    }
+                    constants.clear();
+                }
+                //the condition is solved again after each run of the body
+                //so the variables the body changes are not constant in it
+                Stmt::While(condition, body) => {
+                    let mut unchanged = constants.clone();
+                    match AssignFinder::assigned(body) {
+                        Some(names) => unchanged.retain(|name, _| !names.contains(name)),
+                        None => unchanged.clear(),
+                    }
+                    ConstantChecker::new(&unchanged, *span, &mut self.warnings)
+                        .visit_expr(condition);
+                    if let Some(warning) = endless_loop(condition, body, &constants, &unchanged) {
+                        self.warnings.push(warning);
+                    }
+                    self.check_constants(body, HashMap::new());
+                    constants.clear();
+                }
+                //the condition is only solved after the body has run
+                Stmt::DoWhile(_, body) | Stmt::For(.., body) => {
+                    self.check_constants(body, HashMap::new());
+                    constants.clear();
+                }
+                //the catch block may run after any part of the body has changed variables
+                Stmt::Try(body, _, catch) => {
+                    self.check_constants(body, constants.clone());
+                    self.check_constants(catch, HashMap::new());
+                    constants.clear();
+                }
+            }
+        }
+    }
+}
+
+//Check if the expression of a statement calls a function, nested blocks are not searched
+fn has_call(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Expr(expr)
+        | Stmt::Print(expr)
+        | Stmt::Assign(_, expr)
+        | Stmt::Const(_, expr)
+        | Stmt::Destructure(_, expr)
+        | Stmt::Reassign(_, expr)
+        | Stmt::Return(Some(expr))
+        | Stmt::Exit(Some(expr))
+        | Stmt::Throw(expr)
+        | Stmt::If(expr, ..)
+        | Stmt::While(expr, _) => CallFinder::has_call(expr),
+        Stmt::IndexAssign(_, accessors, expr) => accessors
+            .iter()
+            .filter_map(Accessor::index)
+            .chain([expr])
+            .any(CallFinder::has_call),
+        Stmt::Assert(condition, message) => {
+            CallFinder::has_call(condition) || message.as_ref().is_some_and(CallFinder::has_call)
+        }
+        //the parts of a for loop and the condition of a do while are not checked
+        //the constants are cleared for their bodies
+        Stmt::Block(_)
+        | Stmt::Try(..)
+        | Stmt::For(..)
+        | Stmt::DoWhile(..)
+        | Stmt::FnDecl(_)
+        | Stmt::Struct(_)
+        | Stmt::Return(None)
+        | Stmt::Exit(None)
+        | Stmt::Break
+        | Stmt::Continue => false,
+    }
+}
+
+//Reports the failing operations of one statement's expression
+//the expression is folded from its leaves up, so each node is only folded once
+struct ConstantChecker<'a> {
+    constants: &'a HashMap<String, Literal>,
+    span: Span,
+    warnings: &'a mut Vec<Warning>,
+    //The folded values of the nodes visited whose parent has not been folded yet
+    values: Vec<Option<Literal>>,
+}
+
+impl<'a> ConstantChecker<'a> {
+    fn new(
+        constants: &'a HashMap<String, Literal>,
+        span: Span,
+        warnings: &'a mut Vec<Warning>,
+    ) -> Self {
+        Self {
+            constants,
+            span,
+            warnings,
+            values: Vec::new(),
+        }
+    }
+}
+
+impl ExprVisitor for ConstantChecker<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        let at = self.values.len();
+        match expr {
+            //the right operand is only solved when the left one does not decide the result
+            //eg- x != 0 and 10 / x > 1 never divides by zero
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                let decided_by = matches!(expr, Expr::Or(..));
+                self.visit_expr(left);
+                let left = self.values[at].as_ref();
+                if left.map(Literal::is_truthy) == Some(decided_by) {
+                    self.values.push(None);
+                } else {
+                    self.visit_expr(right);
+                }
+            }
+            //the operands after a false comparison are not solved
+            Expr::Compare(first, comparisons) => {
+                self.visit_expr(first);
+                let mut decided = false;
+                for (operator, operand) in comparisons {
+                    if decided {
+                        self.values.push(None);
+                        continue;
+                    }
+                    self.visit_expr(operand);
+                    let compared = &self.values[self.values.len() - 2..];
+                    decided =
+                        compare(operator, compared[0].clone(), compared[1].clone()) == Some(false);
+                }
+            }
+            expr => walk_expr(self, expr),
+        }
+        let operands = self.values.split_off(at);
+        //-0.0 is matched too, float patterns compare with ==
+        let divides_by_zero = matches!(
+            (expr, &operands[..]),
+            (
+                Expr::Div(..) | Expr::Mod(..) | Expr::FloorDiv(..),
+                [_, Some(Literal::Number(0) | Literal::Float(0.0))],
+            )
+        );
+        //an operand which overflows on its own is already reported
+        let integer_operands = matches!(
+            (expr, &operands[..]),
+            (
+                Expr::Add(..) | Expr::Sub(..) | Expr::Mul(..) | Expr::Pow(..),
+                [Some(Literal::Number(_)), Some(Literal::Number(_))],
+            ) | (Expr::Negate(..), [Some(Literal::Number(_))])
+        );
+        let value = fold_node(expr, operands, self.constants);
+        //the warnings point at the operator
+        let span = operator_span(expr, self.span);
+        if divides_by_zero {
+            self.warnings.push(Warning::DivisionByZero(span));
+        }
+        if integer_operands && value.is_none() {
+            self.warnings.push(Warning::Overflow(span));
+        }
+        self.values.push(value);
+    }
+}
+
+//The span of the operator of an expression in a statement, or of the statement if it is not known
+fn operator_span(expr: &Expr, span: Span) -> Span {
+    match expr.position() {
+        Position(Some((line, start))) => Span {
+            line,
+            start,
+            ..span
+        },
+        Position(None) => span,
+    }
+}
+
+//Evaluate an expression made of constants, returns None if the value is not known before running
+//only numbers, booleans and tuples of them are folded, so folding never builds large strings
+pub(super) fn fold(expr: &Expr, constants: &HashMap<String, Literal>) -> Option<Literal> {
+    let mut folder = Folder {
+        constants,
+        values: Vec::new(),
+    };
+    folder.visit_expr(expr);
+    folder.values.pop().flatten()
+}
+
+//Folds an expression from its leaves up, each node is folded once its operands are
+struct Folder<'a> {
+    constants: &'a HashMap<String, Literal>,
+    values: Vec<Option<Literal>>,
+}
+
+impl ExprVisitor for Folder<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        let at = self.values.len();
+        walk_expr(self, expr);
+        let operands = self.values.split_off(at);
+        let value = fold_node(expr, operands, self.constants);
+        self.values.push(value);
+    }
+}
+
+//Fold a single node from the folded values of its operands, in the order walk_expr visits them
+pub(super) fn fold_node(
+    expr: &Expr,
+    operands: Vec<Option<Literal>>,
+    constants: &HashMap<String, Literal>,
+) -> Option<Literal> {
+    let mut operands = operands.into_iter();
+    let mut next = || operands.next().flatten();
+    match expr {
+        Expr::Literal(Literal::String(_)) => None,
+        Expr::Literal(literal) => Some(literal.clone()),
+        Expr::Ident(name) => constants.get(name).cloned(),
+        Expr::Add(..) => match numbers(next(), next())? {
+            (Literal::Number(left), Literal::Number(right)) => {
+                left.checked_add(right).map(Literal::Number)
+            }
+            (left, right) => left.add(right).ok(),
+        },
+        Expr::Sub(..) => match numbers(next(), next())? {
+            (Literal::Number(left), Literal::Number(right)) => {
+                left.checked_sub(right).map(Literal::Number)
+            }
+            (left, right) => left.sub(right).ok(),
+        },
+        Expr::Mul(..) => match numbers(next(), next())? {
+            (Literal::Number(left), Literal::Number(right)) => {
+                left.checked_mul(right).map(Literal::Number)
+            }
+            (left, right) => left.mul(right).ok(),
+        },
+        Expr::Div(..) => {
+            let (left, right) = numbers(next(), next())?;
+            left.div(right).ok()
+        }
+        Expr::Mod(..) => {
+            let (left, right) = numbers(next(), next())?;
+            left.modulo(right).ok()
+        }
+        Expr::FloorDiv(..) => {
+            let (left, right) = numbers(next(), next())?;
+            left.floor_div(right).ok()
+        }
+        Expr::Pow(..) => {
+            let (left, right) = numbers(next(), next())?;
+            left.pow(right).ok()
+        }
+        Expr::Negate(..) => match next()? {
+            Literal::Number(num) => num.checked_neg().map(Literal::Number),
+            Literal::Float(num) => Some(Literal::Float(-num)),
+            _ => None,
+        },
+        Expr::Greater(..) => {
+            let (left, right) = numbers(next(), next())?;
+            left.greater(right).ok()
+        }
+        Expr::Less(..) => {
+            let (left, right) = numbers(next(), next())?;
+            left.less(right).ok()
+        }
+        Expr::GreaterEqual(..) => {
+            let (left, right) = numbers(next(), next())?;
+            left.greater_equal(right).ok()
+        }
+        Expr::LessEqual(..) => {
+            let (left, right) = numbers(next(), next())?;
+            left.less_equal(right).ok()
+        }
+        //a false comparison decides the chain, like the left operand of an and
+        Expr::Compare(_, comparisons) => {
+            let mut left = next();
+            for (operator, _) in comparisons {
+                let right = next();
+                if !compare(operator, left, right.clone())? {
+                    return Some(Literal::Bool(false));
+                }
+                left = right;
+            }
+            Some(Literal::Bool(true))
+        }
+        Expr::Equal(..) => {
+            let (left, right) = (next()?, next()?);
+            Some(left.equal(right))
+        }
+        Expr::NotEqual(..) => {
+            let (left, right) = (next()?, next()?);
+            Some(left.not_equal(right))
+        }
+        //only booleans are folded, other values are errors with strict conditions
+        //a left operand deciding the result leaves the right one unsolved, so it does not need a value
+        Expr::And(..) => match (next()?, next()) {
+            (Literal::Bool(false), _) => Some(Literal::Bool(false)),
+            (Literal::Bool(true), Some(Literal::Bool(right))) => Some(Literal::Bool(right)),
+            _ => None,
+        },
+        Expr::Or(..) => match (next()?, next()) {
+            (Literal::Bool(true), _) => Some(Literal::Bool(true)),
+            (Literal::Bool(false), Some(Literal::Bool(right))) => Some(Literal::Bool(right)),
+            _ => None,
+        },
+        Expr::Not(_) => match next()? {
+            Literal::Bool(value) => Some(Literal::Bool(!value)),
+            _ => None,
+        },
+        Expr::Tuple(_) => operands
+            .by_ref()
+            .collect::<Option<Vec<Literal>>>()
+            .map(Literal::Tuple),
+        Expr::TupleIndex(_, index) => match next()? {
+            Literal::Tuple(mut items) if *index < items.len() => Some(items.swap_remove(*index)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+//The folded operands of arithmetic or a comparison, both must be numbers or floats
+fn numbers(left: Option<Literal>, right: Option<Literal>) -> Option<(Literal, Literal)> {
+    match (left?, right?) {
+        (
+            left @ (Literal::Number(_) | Literal::Float(_)),
+            right @ (Literal::Number(_) | Literal::Float(_)),
+        ) => Some((left, right)),
+        _ => None,
+    }
+}
+
+//Compare the folded operands of a comparison in a chain, None if they are not both numbers
+fn compare(operator: &Operator, left: Option<Literal>, right: Option<Literal>) -> Option<bool> {
+    let (left, right) = numbers(left, right)?;
+    match operator.compare(left, right).ok()? {
+        Literal::Bool(result) => Some(result),
+        _ => None,
+    }
+}
+
+//Collects the variables read by an expression
+pub(crate) struct IdentFinder {
+    idents: Vec<String>,
+}
+
+impl IdentFinder {
+    pub(crate) fn idents(expr: &Expr) -> Vec<String> {
+        let mut finder = Self { idents: Vec::new() };
+        finder.visit_expr(expr);
+        finder.idents
+    }
+}
+
+impl ExprVisitor for IdentFinder {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Ident(name) = expr {
+            self.idents.push(name.to_owned());
+        }
+        walk_expr(self, expr);
+    }
+}
+
+//Reports the calls of deprecated builtins, at the statement they are in
+struct DeprecationFinder<'a> {
+    deprecated: &'static [(&'static str, &'static str)],
+    span: Span,
+    warnings: &'a mut Vec<Warning>,
+}
+
+impl ExprVisitor for DeprecationFinder<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Call(name, _) => {
+                if let Some((_, replacement)) = self.deprecated.iter().find(|(old, _)| old == name)
+                {
+                    self.warnings.push(Warning::Deprecated(
+                        name.to_owned(),
+                        replacement,
+                        self.span,
+                    ));
+                }
+            }
+            Expr::Function(function) => self.visit_block(&function.body),
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+impl StmtVisitor for DeprecationFinder<'_> {
+    fn visit_block(&mut self, block: &Block) {
+        for (stmt, span) in block.iter() {
+            self.span = *span;
+            walk_stmt(self, stmt);
+        }
+    }
+}
+
+//Find a while loop whose condition compares a variable with != to a value it never reaches
+//eg- i starting at 1 and changed by i += 2 is never 0, so while i != 0 never ends
+//constants: the constants before the loop, unchanged: the ones the loop does not change
+fn endless_loop(
+    condition: &Expr,
+    body: &Block,
+    constants: &HashMap<String, Literal>,
+    unchanged: &HashMap<String, Literal>,
+) -> Option<Warning> {
+    let Expr::NotEqual(left, right) = condition else {
+        return None;
+    };
+    let (name, target) = match (&**left, &**right) {
+        (Expr::Ident(name), target) | (target, Expr::Ident(name)) => (name, target),
+        _ => return None,
+    };
+    let (Some(Literal::Number(start)), Some(Literal::Number(target))) =
+        (constants.get(name), fold(target, unchanged))
+    else {
+        return None;
+    };
+    let (step, span) = StepFinder::step(name, body, unchanged)?;
+    //the variable only takes the values start + n * step, for n from 0 up
+    let distance = i64::from(target) - i64::from(*start);
+    let step_size = i64::from(step);
+    if distance % step_size == 0 && distance / step_size >= 0 {
+        return None;
+    }
+    Some(Warning::EndlessLoop(
+        name.to_owned(),
+        *start,
+        step,
+        target,
+        span,
+    ))
+}
+
+//Finds the constant step a loop body changes a variable by, eg- i += 2 or i = i - 1
+struct StepFinder<'a> {
+    name: &'a str,
+    //the constants which the steps can use
+    constants: &'a HashMap<String, Literal>,
+    //The step and where it is made, for each change of the variable
+    steps: Vec<(i32, Span)>,
+    //The span of the statement being visited, for steps without a position
+    span: Span,
+    //Whether the variable may change in another way, or the loop may end without its condition
+    unknown: bool,
+}
+
+impl<'a> StepFinder<'a> {
+    //The step of every change of the variable and the span of the first one
+    //None if the changes differ, the body calls a function, or it can leave the loop
+    fn step(
+        name: &'a str,
+        body: &Block,
+        constants: &'a HashMap<String, Literal>,
+    ) -> Option<(i32, Span)> {
+        let mut finder = Self {
+            name,
+            constants,
+            steps: Vec::new(),
+            span: Span::default(),
+            unknown: false,
+        };
+        finder.visit_block(body);
+        let (step, span) = *finder.steps.first()?;
+        let same = finder.steps.iter().all(|(other, _)| *other == step);
+        (!finder.unknown && same && step != 0).then_some((step, span))
+    }
+
+    //The step of a reassignment of the variable, eg- i + 2, 2 + i or i - 2
+    fn step_of(&self, expr: &Expr) -> Option<i32> {
+        let is_variable = |expr: &Expr| matches!(expr, Expr::Ident(name) if name == self.name);
+        let step = |expr: &Expr| match fold(expr, self.constants)? {
+            Literal::Number(step) => Some(step),
+            _ => None,
+        };
+        match expr {
+            Expr::Add(left, right, _) if is_variable(left) => step(right),
+            Expr::Add(left, right, _) if is_variable(right) => step(left),
+            Expr::Sub(left, right, _) if is_variable(left) => step(right)?.checked_neg(),
+            _ => None,
+        }
+    }
+}
+
+impl ExprVisitor for StepFinder<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        //a called function can change any variable it sees
+        if let Expr::Call(..) | Expr::Function(_) = expr {
+            self.unknown = true;
+        }
+        walk_expr(self, expr);
+    }
+}
+
+impl StmtVisitor for StepFinder<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Reassign(name, expr) if name == self.name => match self.step_of(expr) {
+                Some(step) => self.steps.push((step, operator_span(expr, self.span))),
+                None => self.unknown = true,
+            },
+            Stmt::Assign(name, _)
+            | Stmt::Const(name, _)
+            | Stmt::Reassign(name, _)
+            | Stmt::IndexAssign(name, ..)
+                if name == self.name =>
+            {
+                self.unknown = true
+            }
+            Stmt::Destructure(names, _) if names.iter().any(|name| name == self.name) => {
+                self.unknown = true
+            }
+            //the loop ends without its condition being false
+            Stmt::Break | Stmt::Return(_) | Stmt::Exit(_) | Stmt::Throw(_) | Stmt::Assert(..) => {
+                self.unknown = true
+            }
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        for (stmt, span) in block.iter() {
+            self.span = *span;
+            self.visit_stmt(stmt);
+        }
+    }
+}
+
+//Collects the variables a block assigns, including in its nested blocks
+struct AssignFinder {
+    names: HashSet<String>,
+    calls: bool,
+}
+
+impl AssignFinder {
+    //None if the block calls a function, which can change any variable it sees
+    fn assigned(block: &Block) -> Option<HashSet<String>> {
+        let mut finder = Self {
+            names: HashSet::new(),
+            calls: false,
+        };
+        finder.visit_block(block);
+        (!finder.calls).then_some(finder.names)
+    }
+}
+
+impl ExprVisitor for AssignFinder {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Call(..) | Expr::Function(_) = expr {
+            self.calls = true;
+        }
+        walk_expr(self, expr);
+    }
+}
+
+impl StmtVisitor for AssignFinder {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Assign(name, _)
+            | Stmt::Const(name, _)
+            | Stmt::Reassign(name, _)
+            | Stmt::IndexAssign(name, ..) => {
+                self.names.insert(name.to_owned());
+            }
+            Stmt::Destructure(names, _) => self.names.extend(names.iter().cloned()),
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+}
+
+//Calls can have effects, so expressions with calls are never reported as unused
+//function values are found too, since their bodies can read any variable they see
+struct CallFinder {
+    found: bool,
+}
+
+impl CallFinder {
+    fn has_call(expr: &Expr) -> bool {
+        let mut finder = Self { found: false };
+        finder.visit_expr(expr);
+        finder.found
+    }
+}
+
+impl ExprVisitor for CallFinder {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Call(..) | Expr::Function(_) = expr {
+            self.found = true;
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{lexer::Lexer, parser::Parser};
+    use super::*;
+
+    fn analyze(source: &str) -> Vec<Warning> {
+        let tokens = Lexer::new(source).lex();
+        Analyzer::analyze(&Parser::new(&tokens).parse().unwrap())
+    }
+
+    #[test]
+    fn unused_expressions() {
+        let warnings = analyze("let a = 1\n1 + 2; a\nprint a\na * 2 == 2");
+        let positions: Vec<(u32, u32)> = warnings.iter().map(Warning::get_position).collect();
+        assert_eq!(positions, vec![(2, 0), (2, 7), (4, 0)]);
+        assert_eq!(warnings[0].get_message(), "Expression result is unused");
+
+        //calls are kept for their effects
+        assert!(analyze("type(1)\n(1, bool(0))").is_empty());
+    }
+
+    #[test]
+    fn deprecated_builtins() {
+        let tokens = Lexer::new("print old(1)\nif true {\n  let f = fn() { old() }\n}").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        let mut analyzer = Analyzer {
+            warnings: Vec::new(),
+        };
+        analyzer.check_deprecated(&block, &[("old", "new()")]);
+        let positions: Vec<(u32, u32)> = analyzer
+            .warnings
+            .iter()
+            .map(Warning::get_position)
+            .collect();
+        assert_eq!(positions, [(1, 0), (3, 17)]);
+        assert_eq!(analyzer.warnings[0].get_message(), "old is deprecated");
+        assert_eq!(
+            analyzer.warnings[0].get_hint(),
+            "use new() instead, old will be removed in a later version"
+        );
+    }
+
+    #[test]
+    fn dead_stores() {
+        let warnings =
+            analyze("let a = type(1)\nlet b = 2\na = 0\nprint a + b\nb = 3; let a, b = (b, 4)");
+        match &warnings[..] {
+            [Warning::DeadStore(name, first, next)] => {
+                assert_eq!(name, "a");
+                assert_eq!((first.line, first.start), (1, 0));
+                assert_eq!((next.line, next.start), (3, 0));
+            }
+            _ => panic!("Expected one dead store, got {:?}", warnings),
+        }
+        assert_eq!(warnings[0].get_position(), (1, 0));
+
+        //reading a variable in its own assignment uses the old value
+        assert!(analyze("let a = 1\na = a + 1\nprint a").is_empty());
+        //the last store of a variable is kept for later runs
+        assert!(analyze("let a = 1").is_empty());
+        //a branch may read the variable, but stores inside a loop body are checked
+        assert!(analyze("let a = 1\nif a { a = 2 }\na = 3\nprint a").is_empty());
+        assert_eq!(
+            analyze("while true {\n  let a = 1\n  a = 2\n  print a\n}").len(),
+            1
+        );
+        //a called function may read the variable
+        assert!(analyze("let a = 1\nfn f() { return a }\nf()\na = 2\nprint a").is_empty());
+        assert_eq!(
+            analyze("fn f() {\n  let a = 1\n  a = 2\n  return a\n}").len(),
+            1
+        );
+        //a failing statement in a try body jumps to the catch, which may read the variable
+        assert!(analyze(
+            "let m = {1: 2}\nlet a = 0\ntry {\n  a = 1\n  print m[\"missing\"]\n  a = 2\n  print a\n} catch (e) {\n  print a\n}"
+        )
+        .is_empty());
+        assert_eq!(
+            analyze("try {} catch (e) {\n  let a = 1\n  a = 2\n  print a\n}").len(),
+            1
+        );
+        //nothing after a throw runs, so the store before it is not overwritten
+        assert!(
+            analyze("let a = 1\nif true {\n  a = 2\n  throw \"failed\"\n  a = 3\n}").is_empty()
+        );
+        assert!(analyze("while true {\n  let a = 1\n  break\n  a = 2\n}").is_empty());
+    }
+
+    #[test]
+    fn endless_loops() {
+        let warnings = analyze("let i = 1\nwhile i != 0 {\n  i += 2\n}");
+        match &warnings[..] {
+            [warning @ Warning::EndlessLoop(name, 1, 2, 0, _)] if name == "i" => {
+                assert_eq!(
+                    warning.get_message(),
+                    "Loop condition i != 0 is never false"
+                );
+                assert_eq!(
+                    warning.get_hint(),
+                    "i starts at 1 and changes by 2, so it moves away from 0"
+                );
+                //the warning points at the step
+                assert_eq!(warning.get_position(), (3, 4));
+            }
+            _ => panic!("Expected an endless loop, got {:?}", warnings),
+        }
+        let warnings = analyze("let i = 0\nconst STEP = 2\nwhile 5 != i {\n  i = i + STEP\n}");
+        match &warnings[..] {
+            [warning @ Warning::EndlessLoop(_, 0, 2, 5, _)] => {
+                assert_eq!(
+                    warning.get_hint(),
+                    "i starts at 0 and changes by 2, so it skips over 5, compare with < or > instead"
+                );
+                assert_eq!(warning.get_position(), (4, 8));
+            }
+            _ => panic!("Expected an endless loop, got {:?}", warnings),
+        }
+
+        //the steps reach the value
+        assert!(analyze("let i = 10\nwhile i != 0 {\n  i -= 2\n}").is_empty());
+        assert!(analyze("let i = 0\nwhile i != 3 {\n  if i > 1 { print i }\n  i++\n}").is_empty());
+        //the loop can end in another way, or the variable may change in another way
+        assert!(analyze("let i = 1\nwhile i != 0 {\n  i += 2\n  if i > 9 { break }\n}").is_empty());
+        assert!(analyze("let i = 1\nwhile i != 0 {\n  i += 2\n  print type(i)\n}").is_empty());
+        assert!(analyze("let i = 1\nwhile i != 0 {\n  i += 2\n  i -= 1\n}").is_empty());
+        assert!(analyze("let i = 1\nwhile i != 0 {\n  i *= 2\n}").is_empty());
+        assert!(analyze("let i = input()\nwhile i != 0 {\n  i += 2\n}").is_empty());
+    }
+
+    #[test]
+    fn constant_errors() {
+        let positions = |source| -> Vec<(String, (u32, u32))> {
+            analyze(source)
+                .iter()
+                .map(|warning| (warning.get_message(), warning.get_position()))
+                .collect()
+        };
+        //the warnings point at the operator which fails
+        let div_zero = |line, start| (String::from("Division by zero"), (line, start));
+        let overflow = |line, start| (String::from("Integer overflow"), (line, start));
+
+        assert_eq!(
+            positions(
+                "let x = 5\nprint x / 0\nlet a, b = (x - 5, 1)\nprint x / a\nprint x / b\nprint x / -0.0"
+            ),
+            vec![div_zero(2, 8), div_zero(4, 8), div_zero(6, 8)]
+        );
+        assert_eq!(
+            positions(
+                "let big = 2147483647\nprint big + 1\nprint (big * 2) * 2\nprint -(-big - 1)"
+            ),
+            vec![overflow(2, 10), overflow(3, 11), overflow(4, 6)]
+        );
+        //compound assignments and steps point at their operator
+        assert_eq!(
+            positions("let x = 0\nlet big = 2147483647\nx %= 0\nbig++"),
+            vec![div_zero(3, 2), overflow(4, 3)]
+        );
+
+        //variables stop being constant once they get an unknown value
+        assert!(positions("let x = 0\nx = type(x)\nprint 1 / x").is_empty());
+        //loop bodies may run after the variable changed
+        assert!(positions("let x = 0\nwhile true {\n  print 1 / x\n  x = x + 1\n}").is_empty());
+        assert_eq!(positions("let x = 0\nif x / 0 { print 1 / x }").len(), 2);
+        //a called function may change the variable
+        assert!(positions("let x = 0\nfn f() { x = 1 }\nf()\nprint 1 / x").is_empty());
+        //function bodies are checked without knowing the parameters
+        assert_eq!(
+            positions("let x = 0\nfn f(x) {\n  print 1 / x\n  return x / 0\n}"),
+            vec![div_zero(4, 11)]
+        );
+        //floats don't overflow
+        assert!(positions("print 2147483647.0 * 2").is_empty());
+        //the right operand of and/or is not solved when the left one decides the result
+        assert!(positions("let x = 0\nif x != 0 and 10 / x > 1 { print x }").is_empty());
+        assert!(positions("let x = 0\nprint x == 0 or 10 / x > 1").is_empty());
+        assert_eq!(positions("let x = 0\nprint x == 0 and 10 / x > 1").len(), 1);
+        //nor are the operands after a false comparison in a chain
+        assert!(positions("let x = 0\nprint 1 < x < 10 / x").is_empty());
+        assert_eq!(positions("let x = 0\nprint -1 < x < 10 / x").len(), 1);
+        //the condition of a while loop reads the values left by the body
+        assert!(positions("let x = 0\nwhile x == 0 or 10 / x > 1 {\n  x = x + 1\n}").is_empty());
+        assert!(positions("let x = 0\nwhile 10 / x > 1 {\n  x = x + 1\n}").is_empty());
+        assert_eq!(
+            positions("let x = 0\nwhile 10 / x > 1 {\n  print x\n}").len(),
+            1
+        );
+    }
+}
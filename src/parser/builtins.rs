@@ -0,0 +1,389 @@
+use super::errors::LiteralOpError;
+use super::token::{Literal, ValueType};
+use crate::output::style_text;
+use std::time::Duration;
+
+//Call a function available in every program by its name
+//args: The arguments of the call, already solved
+pub fn call_builtin(name: &str, args: Vec<Literal>) -> Result<Literal, LiteralOpError> {
+    match name {
+        "type" => {
+            let [value] = take_args(args)?;
+            Ok(Literal::String(value.type_name().to_owned()))
+        }
+        //Explicit conversions to numbers, like the as expressions, eg- int("12") is 12 and int(2.9) is 2
+        //a string which is not a number is a conversion error
+        "int" => {
+            let [value] = take_args(args)?;
+            value.cast(&ValueType::Number)
+        }
+        "float" => {
+            let [value] = take_args(args)?;
+            value.cast(&ValueType::Float)
+        }
+        //Explicit conversion to a boolean using the value's truthiness
+        "bool" => {
+            let [value] = take_args(args)?;
+            Ok(Literal::Bool(value.is_truthy()))
+        }
+        //Explicit conversion to a string, the way print shows the value
+        "str" => {
+            let [value] = take_args(args)?;
+            Ok(Literal::String(value.to_string()))
+        }
+        //Whether a range has a number, a tuple an item, a map a key or a string a smaller string
+        "contains" => {
+            let [value, item] = take_args(args)?;
+            Ok(Literal::Bool(value.contains(&item)?))
+        }
+        "len" => {
+            let [value] = take_args(args)?;
+            count(value.length()?)
+        }
+        "byte_len" => {
+            let [value] = take_args(args)?;
+            count(value.byte_len()?)
+        }
+        //The value of a key in a map, or none if the map does not have the key
+        "get" => {
+            let [map, key] = take_args(args)?;
+            match map {
+                Literal::Map(map) => Ok(map.get(&key).cloned().unwrap_or(Literal::None)),
+                _ => Err(LiteralOpError::InvalidTypeError),
+            }
+        }
+        //The keys, values or (key, value) pairs of a map as a tuple, in the order of the map
+        //a map is looped over by indexing the tuple, eg- keys(m)[i]
+        "keys" | "values" | "entries" => {
+            let [value] = take_args(args)?;
+            let Literal::Map(map) = value else {
+                return Err(LiteralOpError::InvalidTypeError);
+            };
+            let items = map.iter().map(|(key, value)| match name {
+                "keys" => key.clone(),
+                "values" => value.clone(),
+                _ => Literal::Tuple(vec![key.clone(), value.clone()]),
+            });
+            Ok(Literal::Tuple(items.collect()))
+        }
+        //Send a value to the programs reading a channel, functions can't be sent
+        "send" => {
+            let [channel, value] = take_args(args)?;
+            let Literal::Channel(channel) = channel else {
+                return Err(LiteralOpError::InvalidTypeError);
+            };
+            if !channel.send(&value) {
+                return Err(LiteralOpError::InvalidArgumentError);
+            }
+            Ok(Literal::None)
+        }
+        //Take a value from a channel, waiting until one is sent
+        //recv(channel, ms) waits at most ms milliseconds and gives none if no value came
+        "recv" => {
+            let (channel, timeout) = match args.len() {
+                1 => {
+                    let [channel] = take_args(args)?;
+                    (channel, None)
+                }
+                _ => {
+                    let [channel, timeout] = take_args(args)?;
+                    (channel, Some(timeout))
+                }
+            };
+            let timeout = match timeout {
+                None => None,
+                Some(Literal::Number(ms)) => Some(
+                    u64::try_from(ms)
+                        .map(Duration::from_millis)
+                        .map_err(|_| LiteralOpError::InvalidArgumentError)?,
+                ),
+                Some(_) => return Err(LiteralOpError::InvalidTypeError),
+            };
+            match channel {
+                Literal::Channel(channel) => Ok(channel.recv(timeout).unwrap_or(Literal::None)),
+                _ => Err(LiteralOpError::InvalidTypeError),
+            }
+        }
+        //Color or embolden a value for printing, eg- print color("red", "failed")
+        "color" => {
+            let [color, value] = take_args(args)?;
+            let Literal::String(color) = color else {
+                return Err(LiteralOpError::InvalidTypeError);
+            };
+            style_text(&value.to_string(), Some(&color), false)
+                .map(Literal::String)
+                .ok_or(LiteralOpError::InvalidArgumentError)
+        }
+        "bold" => {
+            let [value] = take_args(args)?;
+            style_text(&value.to_string(), None, true)
+                .map(Literal::String)
+                .ok_or(LiteralOpError::InvalidArgumentError)
+        }
+        _ => Err(LiteralOpError::UndefinedFunctionError),
+    }
+}
+
+//Convert a length to a number value
+fn count(len: usize) -> Result<Literal, LiteralOpError> {
+    i32::try_from(len)
+        .map(Literal::Number)
+        .map_err(|_| LiteralOpError::ConversionError)
+}
+
+//Check the number of arguments passed to a builtin and return them as an array
+pub fn take_args<const N: usize>(args: Vec<Literal>) -> Result<[Literal; N], LiteralOpError> {
+    args.try_into()
+        .map_err(|_| LiteralOpError::ArgumentCountError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::map::Map;
+    use super::*;
+    use crate::shared::Channel;
+
+    #[test]
+    fn type_builtin() {
+        let values = [
+            Literal::Number(5),
+            Literal::Float(2.5),
+            Literal::String("hi".to_owned()),
+            Literal::Bool(true),
+            Literal::Tuple(vec![Literal::Number(1)]),
+            Literal::Map(Map::new()),
+            Literal::Range(0, 3),
+            Literal::None,
+        ];
+        //function values are checked by the executor tests, as only it can make them
+        let names = [
+            "number", "float", "string", "bool", "tuple", "map", "range", "none",
+        ];
+        for (value, name) in values.into_iter().zip(names) {
+            assert_eq!(
+                call_builtin("type", vec![value]),
+                Ok(Literal::String(name.to_owned()))
+            );
+        }
+
+        assert_eq!(
+            call_builtin("type", Vec::new()),
+            Err(LiteralOpError::ArgumentCountError)
+        );
+        assert_eq!(
+            call_builtin("nothing", Vec::new()),
+            Err(LiteralOpError::UndefinedFunctionError)
+        );
+    }
+
+    #[test]
+    fn bool_builtin() {
+        let values = [
+            Literal::Number(0),
+            Literal::String("a".to_owned()),
+            Literal::Tuple(Vec::new()),
+            Literal::Tuple(vec![Literal::Bool(false)]),
+        ];
+        let truthiness = [false, true, false, true];
+        for (value, truthy) in values.into_iter().zip(truthiness) {
+            assert_eq!(call_builtin("bool", vec![value]), Ok(Literal::Bool(truthy)));
+        }
+    }
+
+    #[test]
+    fn str_builtin() {
+        let values = [
+            Literal::Number(1),
+            Literal::Float(2.5),
+            Literal::String("a".to_owned()),
+            Literal::Tuple(vec![Literal::String("b".to_owned()), Literal::None]),
+        ];
+        let strings = ["1", "2.5", "a", "(\"b\", none)"];
+        for (value, string) in values.into_iter().zip(strings) {
+            assert_eq!(
+                call_builtin("str", vec![value]),
+                Ok(Literal::String(string.to_owned()))
+            );
+        }
+    }
+
+    #[test]
+    fn number_builtins() {
+        let string = |text: &str| Literal::String(text.to_owned());
+        assert_eq!(
+            call_builtin("int", vec![string(" 12 ")]),
+            Ok(Literal::Number(12))
+        );
+        assert_eq!(
+            call_builtin("int", vec![Literal::Float(-2.9)]),
+            Ok(Literal::Number(-2))
+        );
+        assert_eq!(
+            call_builtin("int", vec![Literal::Bool(true)]),
+            Ok(Literal::Number(1))
+        );
+        assert_eq!(
+            call_builtin("float", vec![string("2.5")]),
+            Ok(Literal::Float(2.5))
+        );
+        assert_eq!(
+            call_builtin("float", vec![Literal::Number(3)]),
+            Ok(Literal::Float(3.0))
+        );
+        for (name, value) in [
+            ("int", string("twelve")),
+            ("int", string("1.5")),
+            ("float", Literal::None),
+            ("float", Literal::Tuple(Vec::new())),
+        ] {
+            assert_eq!(
+                call_builtin(name, vec![value]),
+                Err(LiteralOpError::ConversionError)
+            );
+        }
+        //a float is only converted if its whole part fits in a number
+        assert_eq!(
+            call_builtin("int", vec![Literal::Float(-2147483648.0)]),
+            Ok(Literal::Number(i32::MIN))
+        );
+        for value in [3e9, -3e9, f32::INFINITY, f32::NAN] {
+            assert_eq!(
+                call_builtin("int", vec![Literal::Float(value)]),
+                Err(LiteralOpError::OverflowError)
+            );
+        }
+    }
+
+    #[test]
+    fn length_builtins() {
+        let string = |text: &str| Literal::String(text.to_owned());
+        assert_eq!(
+            call_builtin("len", vec![string("héllo")]),
+            Ok(Literal::Number(5))
+        );
+        assert_eq!(
+            call_builtin("byte_len", vec![string("héllo")]),
+            Ok(Literal::Number(6))
+        );
+        assert_eq!(
+            call_builtin("len", vec![string("日本")]),
+            Ok(Literal::Number(2))
+        );
+        assert_eq!(
+            call_builtin("len", vec![Literal::Tuple(vec![Literal::Bool(true)])]),
+            Ok(Literal::Number(1))
+        );
+
+        assert_eq!(
+            call_builtin("len", vec![Literal::Number(5)]),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+        assert_eq!(
+            call_builtin("byte_len", vec![Literal::Tuple(Vec::new())]),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+        //strings are split into characters, not bytes
+        assert_eq!(
+            string("aé日").items(),
+            Ok(vec![string("a"), string("é"), string("日")])
+        );
+    }
+
+    #[test]
+    fn contains_builtin() {
+        let string = |value: &str| Literal::String(value.to_owned());
+        let contains = |value: Literal, item: Literal| call_builtin("contains", vec![value, item]);
+        assert_eq!(
+            contains(Literal::Range(1, 3), Literal::Number(2)),
+            Ok(Literal::Bool(true))
+        );
+        assert_eq!(
+            contains(Literal::Range(1, 3), string("2")),
+            Ok(Literal::Bool(false))
+        );
+        assert_eq!(
+            contains(Literal::Tuple(vec![string("a")]), string("a")),
+            Ok(Literal::Bool(true))
+        );
+        assert_eq!(
+            contains(string("hello"), string("ell")),
+            Ok(Literal::Bool(true))
+        );
+        assert_eq!(
+            contains(Literal::Number(1), Literal::Number(1)),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+    }
+
+    #[test]
+    fn channel_builtins() {
+        let channel = Literal::Channel(Channel::new());
+        assert_eq!(
+            call_builtin("send", vec![channel.clone(), Literal::Number(1)]),
+            Ok(Literal::None)
+        );
+        assert_eq!(
+            call_builtin("recv", vec![channel.clone()]),
+            Ok(Literal::Number(1))
+        );
+        assert_eq!(
+            call_builtin("recv", vec![channel.clone(), Literal::Number(1)]),
+            Ok(Literal::None)
+        );
+        assert_eq!(
+            call_builtin("recv", vec![channel.clone(), Literal::Number(-1)]),
+            Err(LiteralOpError::InvalidArgumentError)
+        );
+        assert_eq!(
+            call_builtin("send", vec![Literal::None, Literal::Number(1)]),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+        assert_eq!(
+            call_builtin("recv", vec![channel.clone(), Literal::None, Literal::None]),
+            Err(LiteralOpError::ArgumentCountError)
+        );
+    }
+
+    #[test]
+    fn map_builtins() {
+        let map = Literal::Map(
+            [
+                (Literal::Number(1), Literal::Bool(true)),
+                (Literal::Number(0), Literal::Bool(false)),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        assert_eq!(
+            call_builtin("keys", vec![map.clone()]),
+            Ok(Literal::Tuple(vec![Literal::Number(1), Literal::Number(0)]))
+        );
+        assert_eq!(
+            call_builtin("values", vec![map.clone()]),
+            Ok(Literal::Tuple(vec![
+                Literal::Bool(true),
+                Literal::Bool(false)
+            ]))
+        );
+        assert_eq!(
+            call_builtin("entries", vec![map.clone()]),
+            Ok(Literal::Tuple(vec![
+                Literal::Tuple(vec![Literal::Number(1), Literal::Bool(true)]),
+                Literal::Tuple(vec![Literal::Number(0), Literal::Bool(false)]),
+            ]))
+        );
+        assert_eq!(
+            call_builtin("get", vec![map.clone(), Literal::Number(0)]),
+            Ok(Literal::Bool(false))
+        );
+        assert_eq!(
+            call_builtin("get", vec![map.clone(), Literal::Number(2)]),
+            Ok(Literal::None)
+        );
+        assert_eq!(call_builtin("len", vec![map]), Ok(Literal::Number(2)));
+        assert_eq!(
+            call_builtin("keys", vec![Literal::Tuple(Vec::new())]),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use super::token::{Token, TokenType};
+
+//Walks over the tokens of a program, the parser reads every token through it
+//the lexer always ends the tokens with an Eof, which is returned once the end is passed
+pub struct TokenCursor<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> TokenCursor<'a> {
+    pub fn new(tokens: &'a [Token]) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    //return the token at the current position without consuming it
+    pub fn peek(&self) -> &'a Token {
+        self.get(self.pos)
+    }
+
+    //return the token after the current one without consuming anything
+    pub fn peek_next(&self) -> &'a Token {
+        self.get(self.pos + 1)
+    }
+
+    //return the token n places after the current one, peek_nth(0) is the current token
+    pub fn peek_nth(&self, n: usize) -> &'a Token {
+        self.get(self.pos + n)
+    }
+
+    //consume the current token and return it
+    pub fn advance(&mut self) -> &'a Token {
+        let token = self.peek();
+        if self.pos < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    //check if the current token is of the given class, see TokenType::is_kind
+    pub fn check(&self, class: &TokenType) -> bool {
+        self.peek().class.is_kind(class)
+    }
+
+    //consume the current token only if it is of the given class
+    pub fn consume_if(&mut self, class: &TokenType) -> Option<&'a Token> {
+        if self.check(class) {
+            Some(self.advance())
+        } else {
+            None
+        }
+    }
+
+    //consume the current token if it is of the given class
+    //otherwise the token found instead is returned as the error, and is not consumed
+    pub fn expect(&mut self, class: &TokenType) -> Result<&'a Token, &'a Token> {
+        self.consume_if(class).ok_or_else(|| self.peek())
+    }
+
+    //check if the current token ends a statement
+    //the closing brace of a block also ends its last statement
+    pub fn at_stmt_end(&self) -> bool {
+        matches!(
+            self.peek().class,
+            TokenType::StmtEnd | TokenType::Rbrace | TokenType::Eof
+        )
+    }
+
+    //Error recovery: skip the rest of the current statement
+    //the cursor stops at the token ending the statement, so the next statement starts cleanly
+    pub fn synchronize(&mut self) {
+        while !self.at_stmt_end() {
+            self.advance();
+        }
+    }
+
+    fn get(&self, pos: usize) -> &'a Token {
+        self.tokens
+            .get(pos)
+            .unwrap_or_else(|| &self.tokens[self.tokens.len() - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::lexer::Lexer;
+    use super::*;
+
+    #[test]
+    fn cursor_movement() {
+        let tokens = Lexer::new("a = 5").lex();
+        let mut cursor = TokenCursor::new(&tokens);
+        assert_eq!(cursor.peek().class, TokenType::Ident(String::from("a")));
+        assert_eq!(cursor.peek_next().class, TokenType::Assign);
+
+        assert!(cursor.consume_if(&TokenType::Assign).is_none());
+        cursor.advance();
+        assert_eq!(cursor.expect(&TokenType::Assign).unwrap().start, 2);
+        assert_eq!(cursor.expect(&TokenType::Comma).unwrap_err().start, 4);
+
+        //the cursor stays on the Eof once every token is consumed
+        cursor.advance();
+        assert!(cursor.at_stmt_end());
+        assert_eq!(cursor.advance().class, TokenType::Eof);
+        assert_eq!(cursor.peek().class, TokenType::Eof);
+    }
+
+    #[test]
+    fn cursor_synchronize() {
+        let tokens = Lexer::new("let a = (1 2\nprint 2").lex();
+        let mut cursor = TokenCursor::new(&tokens);
+        cursor.synchronize();
+        assert_eq!(cursor.advance().class, TokenType::StmtEnd);
+        assert!(!cursor.at_stmt_end());
+        cursor.synchronize();
+        assert_eq!(cursor.peek().class, TokenType::Eof);
+    }
+}
@@ -1,14 +1,46 @@
 use super::errors::LiteralOpError;
-use super::{stmt::Block, token::*};
+use super::map::Map;
+use super::stmt::Function;
+use super::token::*;
+use crate::executor::Executor;
+use std::rc::Rc;
+
+//An operation combining the values of two operands
+type BinaryOp = fn(Literal, Literal) -> Result<Literal, LiteralOpError>;
+
+//How the value of a binary operation is made from its left operand's value and its right operand
+enum Step {
+    //Solve the right operand and combine both values
+    Binary(BinaryOp),
+    //An and (decided by false) or an or (decided by true)
+    Logical(bool),
+    //An addition which may join a value to a string
+    Concat,
+}
+
+//Where the operator of an expression is in the source, None if it was not parsed from one
+//positions are left out when expressions are compared, so an expression moved in the code is still equal
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Position(pub Option<(u32, u32)>);
+
+impl PartialEq for Position {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Expr {
     Ident(String),
     Literal(Literal),
-    Div(Box<Expr>, Box<Expr>),
-    Mul(Box<Expr>, Box<Expr>),
-    Add(Box<Expr>, Box<Expr>),
-    Sub(Box<Expr>, Box<Expr>),
+    //the arithmetic operations keep the position of their operator, where they fail
+    Div(Box<Expr>, Box<Expr>, Position),
+    Mul(Box<Expr>, Box<Expr>, Position),
+    Mod(Box<Expr>, Box<Expr>, Position),
+    FloorDiv(Box<Expr>, Box<Expr>, Position),
+    Pow(Box<Expr>, Box<Expr>, Position),
+    Add(Box<Expr>, Box<Expr>, Position),
+    Sub(Box<Expr>, Box<Expr>, Position),
     Greater(Box<Expr>, Box<Expr>),
     Less(Box<Expr>, Box<Expr>),
     GreaterEqual(Box<Expr>, Box<Expr>),
@@ -17,8 +49,36 @@ pub enum Expr {
     NotEqual(Box<Expr>, Box<Expr>),
     And(Box<Expr>, Box<Expr>),
     Or(Box<Expr>, Box<Expr>),
+    //Compare(First, Comparisons), a chain of comparisons, eg- 1 < x <= 10 is 1 < x and x <= 10
+    //each operand is solved once, and the operands after a false comparison are not solved
+    Compare(Box<Expr>, Vec<(Operator, Expr)>),
     Not(Box<Expr>),
-    Negate(Box<Expr>),
+    Negate(Box<Expr>, Position),
+    Tuple(Vec<Expr>),
+    //TupleIndex(Tuple, Index)
+    TupleIndex(Box<Expr>, usize),
+    //Map(Entries), each entry is a key and its value
+    Map(Vec<(Expr, Expr)>),
+    //Record(Struct name, Fields), eg- Point { x: 1, y: 2 }
+    Record(String, Vec<(String, Expr)>),
+    //Field(Record, Name), eg- p.x
+    Field(Box<Expr>, String),
+    //Index(Value, Index), eg- m["a"]
+    Index(Box<Expr>, Box<Expr>),
+    //Slice(Value, Start, End), eg- s[1:4], a missing bound is the start or end of the value
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    //Interpolation(Parts), the values of the parts are joined into a string, eg- "a${b}"
+    Interpolation(Vec<Expr>),
+    //Call(Function name, Arguments)
+    Call(String, Vec<Expr>),
+    //A function value, which shares the local variables it sees when it is made
+    Function(Rc<Function>),
+    //Range(Start, End, Inclusive), eg- 1..10 or 1..=10
+    Range(Box<Expr>, Box<Expr>, bool),
+    //Is(Value, Type), check the type of a value
+    Is(Box<Expr>, ValueType),
+    //Cast(Value, Type), convert a value to another type
+    Cast(Box<Expr>, ValueType),
 }
 
 impl Expr {
@@ -26,16 +86,25 @@ impl Expr {
     //Constructor funtions
     //--------------------
     pub fn new_add(left: Expr, right: Expr) -> Expr {
-        Expr::Add(Box::new(left), Box::new(right))
+        Expr::Add(Box::new(left), Box::new(right), Position::default())
     }
     pub fn new_sub(left: Expr, right: Expr) -> Expr {
-        Expr::Sub(Box::new(left), Box::new(right))
+        Expr::Sub(Box::new(left), Box::new(right), Position::default())
     }
     pub fn new_mul(left: Expr, right: Expr) -> Expr {
-        Expr::Mul(Box::new(left), Box::new(right))
+        Expr::Mul(Box::new(left), Box::new(right), Position::default())
     }
     pub fn new_div(left: Expr, right: Expr) -> Expr {
-        Expr::Div(Box::new(left), Box::new(right))
+        Expr::Div(Box::new(left), Box::new(right), Position::default())
+    }
+    pub fn new_mod(left: Expr, right: Expr) -> Expr {
+        Expr::Mod(Box::new(left), Box::new(right), Position::default())
+    }
+    pub fn new_floor_div(left: Expr, right: Expr) -> Expr {
+        Expr::FloorDiv(Box::new(left), Box::new(right), Position::default())
+    }
+    pub fn new_pow(left: Expr, right: Expr) -> Expr {
+        Expr::Pow(Box::new(left), Box::new(right), Position::default())
     }
     pub fn new_greater(left: Expr, right: Expr) -> Expr {
         Expr::Greater(Box::new(left), Box::new(right))
@@ -61,12 +130,39 @@ impl Expr {
     pub fn new_or(left: Expr, right: Expr) -> Expr {
         Expr::Or(Box::new(left), Box::new(right))
     }
+    pub fn new_negate(expr: Expr) -> Expr {
+        Expr::Negate(Box::new(expr), Position::default())
+    }
     pub fn new_literal(literal: &Literal) -> Expr {
         Expr::Literal(literal.to_owned())
     }
     pub fn new_ident(ident: &str) -> Expr {
         Expr::Ident(ident.to_owned())
     }
+    pub fn new_tuple_index(tuple: Expr, index: usize) -> Expr {
+        Expr::TupleIndex(Box::new(tuple), index)
+    }
+    pub fn new_field(record: Expr, name: &str) -> Expr {
+        Expr::Field(Box::new(record), name.to_owned())
+    }
+    pub fn new_index(value: Expr, index: Expr) -> Expr {
+        Expr::Index(Box::new(value), Box::new(index))
+    }
+    pub fn new_slice(value: Expr, start: Option<Expr>, end: Option<Expr>) -> Expr {
+        Expr::Slice(Box::new(value), start.map(Box::new), end.map(Box::new))
+    }
+    pub fn new_call(name: &str, args: Vec<Expr>) -> Expr {
+        Expr::Call(name.to_owned(), args)
+    }
+    pub fn new_range(start: Expr, end: Expr, inclusive: bool) -> Expr {
+        Expr::Range(Box::new(start), Box::new(end), inclusive)
+    }
+    pub fn new_is(expr: Expr, value_type: ValueType) -> Expr {
+        Expr::Is(Box::new(expr), value_type)
+    }
+    pub fn new_cast(expr: Expr, value_type: ValueType) -> Expr {
+        Expr::Cast(Box::new(expr), value_type)
+    }
 
     #[allow(dead_code)]
     pub fn new_num_literal(num: i32) -> Expr {
@@ -79,6 +175,9 @@ impl Expr {
             Operator::Sub => Expr::new_sub(left, right),
             Operator::Mul => Expr::new_mul(left, right),
             Operator::Div => Expr::new_div(left, right),
+            Operator::Mod => Expr::new_mod(left, right),
+            Operator::FloorDiv => Expr::new_floor_div(left, right),
+            Operator::Pow => Expr::new_pow(left, right),
             Operator::Greater => Expr::new_greater(left, right),
             Operator::Less => Expr::new_less(left, right),
             Operator::GreaterEqual => Expr::new_greater_equal(left, right),
@@ -87,95 +186,261 @@ impl Expr {
             Operator::NotEqual => Expr::new_not_equal(left, right),
             Operator::And => Expr::new_and(left, right),
             Operator::Or => Expr::new_or(left, right),
+            Operator::Range => Expr::new_range(left, right, false),
+            Operator::RangeInclusive => Expr::new_range(left, right, true),
         }
     }
 
     pub fn new_unary_op(expr: Expr, opr: &Unary) -> Expr {
         match opr {
             Unary::Not => Expr::Not(Box::new(expr)),
-            Unary::Neg => Expr::Negate(Box::new(expr)),
+            Unary::Neg => Expr::new_negate(expr),
         }
     }
 
-    pub fn solve(&self, block: &Block) -> Result<Literal, LiteralOpError> {
+    //Keep where the operator of an arithmetic operation is, other expressions are left as they are
+    pub fn with_position(mut self, token: &Token) -> Expr {
+        match &mut self {
+            Expr::Div(.., position)
+            | Expr::Mul(.., position)
+            | Expr::Mod(.., position)
+            | Expr::FloorDiv(.., position)
+            | Expr::Pow(.., position)
+            | Expr::Add(.., position)
+            | Expr::Sub(.., position)
+            | Expr::Negate(_, position) => *position = Position(Some((token.line, token.start))),
+            _ => {}
+        }
+        self
+    }
+
+    //Where the operator of an arithmetic operation is, other expressions have no position
+    pub fn position(&self) -> Position {
         match self {
+            Expr::Div(.., position)
+            | Expr::Mul(.., position)
+            | Expr::Mod(.., position)
+            | Expr::FloorDiv(.., position)
+            | Expr::Pow(.., position)
+            | Expr::Add(.., position)
+            | Expr::Sub(.., position)
+            | Expr::Negate(_, position) => *position,
+            _ => Position::default(),
+        }
+    }
+
+    //The operands of a binary operation and how they are combined, None for other expressions
+    fn binary_step(&self, executor: &Executor) -> Option<(&Expr, &Expr, Step)> {
+        let semantics = &executor.semantics;
+        let (left, right, operation): (_, _, BinaryOp) = match self {
             //Division operation can only be done between two numbers
-            Expr::Div(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                left.div(right)
-            }
+            Expr::Div(left, right, _) => (left, right, Literal::div),
             //Multiplication can be done between two numbers, and a string and a number
             //"Hello" * 2  => "HelloHello"
-            Expr::Mul(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                left.mul(right)
+            Expr::Mul(left, right, _) if semantics.strict_repetition => {
+                (left, right, Literal::mul_numbers)
             }
+            Expr::Mul(left, right, _) => (left, right, Literal::mul),
+            //The remainder of integer division
+            Expr::Mod(left, right, _) => (left, right, Literal::modulo),
+            Expr::FloorDiv(left, right, _) => (left, right, Literal::floor_div),
+            Expr::Pow(left, right, _) => (left, right, Literal::pow),
             //Can add both Strings and Numbers
-            Expr::Add(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                left.add(right)
+            Expr::Add(left, right, _) if semantics.strict_concatenation => {
+                (left, right, Literal::add_strings)
             }
+            Expr::Add(left, right, _) => return Some((left, right, Step::Concat)),
             //Can only subtract numbers
-            Expr::Sub(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                left.sub(right)
+            Expr::Sub(left, right, _) => (left, right, Literal::sub),
+            Expr::Greater(left, right) => (left, right, Literal::greater),
+            Expr::Less(left, right) => (left, right, Literal::less),
+            Expr::GreaterEqual(left, right) => (left, right, Literal::greater_equal),
+            Expr::LessEqual(left, right) => (left, right, Literal::less_equal),
+            Expr::Equal(left, right) => (left, right, |left, right| Ok(left.equal(right))),
+            Expr::NotEqual(left, right) => (left, right, |left, right| Ok(left.not_equal(right))),
+            Expr::Range(left, right, false) => {
+                (left, right, |left, right| left.range(right, false))
+            }
+            Expr::Range(left, right, true) => (left, right, |left, right| left.range(right, true)),
+            Expr::And(left, right) => return Some((left, right, Step::Logical(false))),
+            Expr::Or(left, right) => return Some((left, right, Step::Logical(true))),
+            _ => return None,
+        };
+        Some((left, right, Step::Binary(operation)))
+    }
+
+    //Solve a chain of comparisons, each one compares the right operand of the one before it
+    //kept out of solve so the frame of each nested solve stays small
+    fn solve_comparisons(
+        first: &Expr,
+        comparisons: &[(Operator, Expr)],
+        executor: &mut Executor,
+    ) -> Result<Literal, LiteralOpError> {
+        let mut left = first.solve(executor)?;
+        for (operator, operand) in comparisons {
+            let right = operand.solve(executor)?;
+            if !operator
+                .compare(left, right.clone())?
+                .condition(&executor.semantics)?
+            {
+                return Ok(Literal::Bool(false));
+            }
+            left = right;
+        }
+        Ok(Literal::Bool(true))
+    }
+
+    //Solve a chain of binary operations, eg- 1 + 2 - 3 which is (1 + 2) - 3
+    //the left operands are walked down in a loop, so long chains don't nest the solve calls
+    //kept out of solve so the frame of each nested solve stays small
+    fn solve_chain(&self, executor: &mut Executor) -> Result<Literal, LiteralOpError> {
+        let mut steps = Vec::new();
+        let mut first = self;
+        while let Some((left, right, step)) = first.binary_step(executor) {
+            steps.push((right, step));
+            first = left;
+        }
+        let mut value = first.solve(executor)?;
+        for (right, step) in steps.into_iter().rev() {
+            value = match step {
+                Step::Binary(operation) => operation(value, right.solve(executor)?)?,
+                Step::Concat => Self::concat(value, right.solve(executor)?, executor)?,
+                //the right operand of and/or is only solved when the left one does not decide the result
+                //eg- x != 0 and 10 / x > 1 never divides by zero
+                Step::Logical(decided_by) => {
+                    if value.condition(&executor.semantics)? == decided_by {
+                        Literal::Bool(decided_by)
+                    } else {
+                        Literal::Bool(right.solve(executor)?.condition(&executor.semantics)?)
+                    }
+                }
+            };
+        }
+        Ok(value)
+    }
+
+    //Add two values, a value joined to a string is formatted like print shows it, eg- with the float precision
+    fn concat(
+        left: Literal,
+        right: Literal,
+        executor: &Executor,
+    ) -> Result<Literal, LiteralOpError> {
+        let format = |value: Literal| match value {
+            Literal::String(_) | Literal::None => value,
+            value => Literal::String(executor.format_value(&value)),
+        };
+        match (&left, &right) {
+            (Literal::String(_), Literal::String(_)) => left.add(right),
+            (Literal::String(_), _) => left.add(format(right)),
+            (_, Literal::String(_)) => format(left).add(right),
+            _ => left.add(right),
+        }
+    }
+
+    pub fn solve(&self, executor: &mut Executor) -> Result<Literal, LiteralOpError> {
+        match self {
+            Expr::Div(..)
+            | Expr::Mul(..)
+            | Expr::Mod(..)
+            | Expr::FloorDiv(..)
+            | Expr::Pow(..)
+            | Expr::Add(..)
+            | Expr::Sub(..)
+            | Expr::Greater(..)
+            | Expr::Less(..)
+            | Expr::GreaterEqual(..)
+            | Expr::LessEqual(..)
+            | Expr::Equal(..)
+            | Expr::NotEqual(..)
+            | Expr::And(..)
+            | Expr::Or(..)
+            | Expr::Range(..) => self.solve_chain(executor),
+            Expr::Compare(first, comparisons) => {
+                Self::solve_comparisons(first, comparisons, executor)
             }
             Expr::Literal(literal) => Ok(literal.to_owned()),
-            Expr::Ident(name) => match block.get_var(name) {
-                Some(literal) => Ok(literal.to_owned()),
-                None => Err(LiteralOpError::UndefinedVariableError),
+            Expr::Ident(name) => match executor.get_var(name) {
+                Some(literal) => Ok(literal),
+                None => executor
+                    .get_shared(name)
+                    .ok_or(LiteralOpError::UndefinedVariableError),
             },
-            Expr::Greater(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                left.greater(right)
+            Expr::Not(expr) => {
+                let expr = expr.solve(executor)?.condition(&executor.semantics)?;
+                Ok(Literal::Bool(!expr))
             }
-            Expr::Less(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                left.less(right)
+            Expr::Negate(expr, _) => {
+                let expr = expr.solve(executor)?;
+                expr.negate()
             }
-            Expr::GreaterEqual(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                left.greater_equal(right)
+            Expr::Tuple(items) => {
+                let mut values = Vec::new();
+                for item in items {
+                    values.push(item.solve(executor)?);
+                }
+                Ok(Literal::Tuple(values))
             }
-            Expr::LessEqual(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                left.less_equal(right)
+            //Only tuples can be indexed
+            Expr::TupleIndex(tuple, index) => match tuple.solve(executor)? {
+                Literal::Tuple(mut items) => {
+                    if *index < items.len() {
+                        Ok(items.swap_remove(*index))
+                    } else {
+                        Err(LiteralOpError::IndexOutOfBoundsError)
+                    }
+                }
+                _ => Err(LiteralOpError::InvalidTypeError),
+            },
+            //A key given twice keeps its last value
+            Expr::Map(entries) => {
+                let mut map = Map::new();
+                for (key, value) in entries {
+                    let key = key.solve(executor)?;
+                    map.insert(key, value.solve(executor)?);
+                }
+                Ok(Literal::Map(map))
             }
-            Expr::Equal(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                Ok(left.equal(right))
+            Expr::Record(name, fields) => executor.make_record(name, fields),
+            Expr::Field(record, name) => record.solve(executor)?.field(name),
+            Expr::Index(value, index) => {
+                let value = value.solve(executor)?;
+                value.index(index.solve(executor)?)
             }
-            Expr::NotEqual(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                Ok(left.not_equal(right))
+            Expr::Slice(value, start, end) => {
+                let value = value.solve(executor)?;
+                let start = start
+                    .as_ref()
+                    .map(|start| start.solve(executor))
+                    .transpose()?;
+                let end = end.as_ref().map(|end| end.solve(executor)).transpose()?;
+                value.slice(start, end)
             }
-            Expr::And(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                Ok(left.and(right))
+            //strings are joined as they are, other values are formatted like print shows them
+            Expr::Interpolation(parts) => {
+                let mut string = String::new();
+                for part in parts {
+                    match part.solve(executor)? {
+                        Literal::String(text) => string.push_str(&text),
+                        value => string.push_str(&executor.format_value(&value)),
+                    }
+                }
+                Ok(Literal::String(string))
             }
-            Expr::Or(left, right) => {
-                let left = left.solve(block)?;
-                let right = right.solve(block)?;
-                Ok(left.or(right))
+            Expr::Call(name, args) => {
+                let mut values = Vec::new();
+                for arg in args {
+                    values.push(arg.solve(executor)?);
+                }
+                executor.call(name, values)
             }
-            Expr::Not(expr) => {
-                let expr = expr.solve(block)?;
-                Ok(expr.not())
+            Expr::Function(function) => Ok(Literal::Function(executor.make_closure(function))),
+            Expr::Is(expr, value_type) => {
+                let expr = expr.solve(executor)?;
+                Ok(Literal::Bool(expr.value_type() == *value_type))
             }
-            Expr::Negate(expr) => {
-                let expr = expr.solve(block)?;
-                expr.negate()
+            Expr::Cast(expr, value_type) => {
+                let expr = expr.solve(executor)?;
+                expr.cast(value_type)
             }
         }
     }
@@ -189,6 +454,7 @@ pub enum ExpectType {
 
 #[cfg(test)]
 mod tests {
+    use super::super::semantics::Semantics;
     use super::*;
 
     #[test]
@@ -203,7 +469,8 @@ mod tests {
         assert_eq!(
             Expr::Add(
                 Box::new(Expr::Literal(literal.clone())),
-                Box::new(Expr::Literal(literal.clone()))
+                Box::new(Expr::Literal(literal.clone())),
+                Position::default()
             ),
             Expr::new_add(
                 Expr::Literal(literal.clone()),
@@ -213,7 +480,8 @@ mod tests {
         assert_eq!(
             Expr::Sub(
                 Box::new(Expr::Literal(literal.clone())),
-                Box::new(Expr::Literal(literal.clone()))
+                Box::new(Expr::Literal(literal.clone())),
+                Position::default()
             ),
             Expr::new_sub(
                 Expr::Literal(literal.clone()),
@@ -223,7 +491,8 @@ mod tests {
         assert_eq!(
             Expr::Mul(
                 Box::new(Expr::Literal(literal.clone())),
-                Box::new(Expr::Literal(literal.clone()))
+                Box::new(Expr::Literal(literal.clone())),
+                Position::default()
             ),
             Expr::new_mul(
                 Expr::Literal(literal.clone()),
@@ -233,7 +502,8 @@ mod tests {
         assert_eq!(
             Expr::Div(
                 Box::new(Expr::Literal(literal.clone())),
-                Box::new(Expr::Literal(literal.clone()))
+                Box::new(Expr::Literal(literal.clone())),
+                Position::default()
             ),
             Expr::new_div(
                 Expr::Literal(literal.clone()),
@@ -288,7 +558,7 @@ mod tests {
             Literal::Number(0),
         ];
         for (expr, soln) in exprs.iter().zip(solns.iter()) {
-            assert_eq!(expr.solve(&Block::new(Vec::new(), None)).unwrap(), *soln);
+            assert_eq!(expr.solve(&mut Executor::new()).unwrap(), *soln);
         }
     }
 
@@ -341,7 +611,113 @@ mod tests {
             Literal::Bool(false),
         ];
         for (expr, soln) in exprs.iter().zip(solns.iter()) {
-            assert_eq!(expr.solve(&Block::new(Vec::new(), None)).unwrap(), *soln);
+            assert_eq!(expr.solve(&mut Executor::new()).unwrap(), *soln);
         }
     }
+
+    #[test]
+    fn solve_strict_conditions() {
+        let mut executor = Executor::new();
+        let expr = Expr::new_and(
+            Expr::new_literal(&Literal::Bool(true)),
+            Expr::new_num_literal(1),
+        );
+        assert_eq!(expr.solve(&mut executor), Ok(Literal::Bool(true)));
+
+        executor.semantics.strict_conditions = true;
+        assert_eq!(
+            expr.solve(&mut executor),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+        let expr = Expr::new_and(
+            Expr::new_literal(&Literal::Bool(true)),
+            Expr::new_call("bool", vec![Expr::new_num_literal(1)]),
+        );
+        assert_eq!(expr.solve(&mut executor), Ok(Literal::Bool(true)));
+    }
+
+    #[test]
+    fn solve_strict_repetition() {
+        let mut executor = Executor::new();
+        executor.semantics = Semantics::strict();
+        let repeat = Expr::new_mul(
+            Expr::new_literal(&Literal::String(String::from("ab"))),
+            Expr::new_num_literal(2),
+        );
+        assert_eq!(
+            repeat.solve(&mut executor),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+        let product = Expr::new_mul(Expr::new_num_literal(3), Expr::new_num_literal(2));
+        assert_eq!(product.solve(&mut executor), Ok(Literal::Number(6)));
+    }
+
+    #[test]
+    fn solve_strict_concatenation() {
+        let mut executor = Executor::new();
+        let string = |text: &str| Expr::new_literal(&Literal::String(text.to_owned()));
+        let mixed = Expr::new_add(Expr::new_num_literal(1), string("a"));
+        assert_eq!(
+            mixed.solve(&mut executor),
+            Ok(Literal::String(String::from("1a")))
+        );
+
+        executor.semantics.strict_concatenation = true;
+        assert_eq!(
+            mixed.solve(&mut executor),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+        let converted = Expr::new_add(
+            Expr::new_call("str", vec![Expr::new_num_literal(1)]),
+            string("a"),
+        );
+        assert_eq!(
+            converted.solve(&mut executor),
+            Ok(Literal::String(String::from("1a")))
+        );
+        //none is still reported as a missing value
+        let missing = Expr::new_add(string("a"), Expr::new_literal(&Literal::None));
+        assert_eq!(
+            missing.solve(&mut executor),
+            Err(LiteralOpError::NoneValueError)
+        );
+    }
+
+    #[test]
+    fn solve_tuple_exprs() {
+        let tuple = Expr::Tuple(vec![
+            Expr::new_num_literal(1),
+            Expr::new_literal(&Literal::String("a".to_owned())),
+            Expr::new_add(Expr::new_num_literal(2), Expr::new_num_literal(3)),
+        ]);
+        let mut executor = Executor::new();
+        assert_eq!(
+            tuple.solve(&mut executor).unwrap(),
+            Literal::Tuple(vec![
+                Literal::Number(1),
+                Literal::String("a".to_owned()),
+                Literal::Number(5)
+            ])
+        );
+        assert_eq!(
+            Expr::new_tuple_index(tuple.clone(), 2)
+                .solve(&mut executor)
+                .unwrap(),
+            Literal::Number(5)
+        );
+        assert_eq!(
+            Expr::new_tuple_index(tuple.clone(), 3).solve(&mut executor),
+            Err(LiteralOpError::IndexOutOfBoundsError)
+        );
+        assert_eq!(
+            Expr::new_tuple_index(Expr::new_num_literal(1), 0).solve(&mut executor),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+        assert_eq!(
+            Expr::new_equal(tuple.clone(), tuple)
+                .solve(&mut executor)
+                .unwrap(),
+            Literal::Bool(true)
+        );
+    }
 }
@@ -0,0 +1,92 @@
+use super::token::Keyword;
+use std::ops::BitOr;
+
+//Parts of the language which are still changing, a program only gets them when it turns them on
+//eg- estel --features=structs game.estel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Feature {
+    //struct declarations and the records made from them, their syntax may still change
+    Structs,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 1] = [Feature::Structs];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|feature| feature.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Structs => "structs",
+        }
+    }
+
+    //The feature a statement starting with the keyword belongs to, None if the statement is stable
+    pub fn of_keyword(keyword: &Keyword) -> Option<Self> {
+        match keyword {
+            Keyword::Struct => Some(Self::Structs),
+            _ => None,
+        }
+    }
+}
+
+//The experimental features turned on for the programs parsed
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Features(u32);
+
+impl Features {
+    pub fn with(self, feature: Feature) -> Self {
+        Self(self.0 | 1 << feature as u32)
+    }
+
+    pub fn contains(&self, feature: Feature) -> bool {
+        self.0 & 1 << feature as u32 != 0
+    }
+
+    //Read a comma separated list of features, eg- "structs", returns the name which is not a feature
+    pub fn parse(list: &str) -> Result<Self, String> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .try_fold(Self::default(), |features, name| {
+                Feature::from_name(name)
+                    .map(|feature| features.with(feature))
+                    .ok_or_else(|| name.to_owned())
+            })
+    }
+}
+
+impl BitOr for Features {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+//Builtins replaced by others, as (builtin, replacement)
+//they keep working, but the analyzer warns about them until they are removed
+pub const DEPRECATED_BUILTINS: &[(&str, &str)] = &[];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_features() {
+        let features = Features::parse(" structs, ,structs").unwrap();
+        assert!(features.contains(Feature::Structs));
+        assert!(!Features::default().contains(Feature::Structs));
+        assert_eq!(Features::parse(""), Ok(Features::default()));
+        assert_eq!(
+            Features::parse("structs,records"),
+            Err(String::from("records"))
+        );
+        assert_eq!(
+            Feature::of_keyword(&Keyword::Struct),
+            Some(Feature::Structs)
+        );
+        assert_eq!(Feature::of_keyword(&Keyword::Let), None);
+    }
+}
@@ -1,17 +1,24 @@
 use super::errors::LexError;
 use super::token::*;
 
+//The deepest interpolations can be nested in each other's code, they are lexed recursively
+pub const MAX_INTERPOLATION_DEPTH: usize = 64;
+
 //source: The source code as a vector of characters
 //line: The line number the lexer is currently at
 //pos: The position of the character the lexer is currently at
 //token_start: Store the start for the next token
 //current_char: The character at the current position of the lexer, set to None once the source ends
+//interpolations: The number of interpolations the code being lexed is nested in
+//aliases: Other spellings of keywords, lexed as the keywords themselves
 pub struct Lexer {
     source: Vec<char>,
     line: u32,
     pos: u32,
     token_start: u32,
     current_char: Option<char>,
+    interpolations: usize,
+    aliases: KeywordAliases,
 }
 
 impl Lexer {
@@ -31,14 +38,49 @@ impl Lexer {
             pos: 0,
             token_start: 0,
             current_char,
+            interpolations: 0,
+            aliases: KeywordAliases::new(),
+        }
+    }
+
+    //Create a lexer which also accepts the given spellings of keywords
+    pub fn with_aliases(source: &str, aliases: &KeywordAliases) -> Lexer {
+        Self {
+            aliases: aliases.clone(),
+            ..Self::new(source)
         }
     }
 
     pub fn lex(&mut self) -> Vec<Token> {
+        self.skip_shebang();
+        let mut tokens = self.lex_tokens(false);
+
+        //add an EOF token at the end of the file
+        tokens.push(Token {
+            class: TokenType::Eof,
+            start: self.token_start,
+            line: self.line,
+        });
+        tokens
+    }
+
+    //Lex tokens until the end of the source
+    //embedded: lexing the code of an interpolation, which stops before the brace closing it
+    fn lex_tokens(&mut self, embedded: bool) -> Vec<Token> {
         let mut tokens: Vec<Token> = Vec::new();
+        //the braces opened inside an interpolation, eg- by a map literal
+        let mut braces = 0;
 
         //continue as long as we get some character, advance() sets current character to None at the end of string
         while let Some(ch) = self.current_char {
+            if embedded {
+                match ch {
+                    '{' => braces += 1,
+                    '}' if braces == 0 => break,
+                    '}' => braces -= 1,
+                    _ => {}
+                }
+            }
             //save the start of the next token
             let token_start = self.token_start;
             //save the line of this token
@@ -47,23 +89,71 @@ impl Lexer {
             let token_type: Option<TokenType> = match ch {
                 //not call advance() when another function is called to lex the characters
                 //as they call advance() on their own
-                '0'..='9' => Some(self.lex_number()),
+                '0'..='9' => {
+                    //a number right after a dot is a tuple index, so t.0.1 is lexed as two indices
+                    if let Some(Token {
+                        class: TokenType::Dot,
+                        ..
+                    }) = tokens.last()
+                    {
+                        Some(self.lex_tuple_index())
+                    } else {
+                        Some(self.lex_number())
+                    }
+                }
+                //a raw string, eg- r"C:\dir"
+                'r' if matches!(self.peek(), Some('"' | '\'')) => Some(self.lex_string()),
                 'a'..='z' | 'A'..='Z' => Some(self.lex_keyword_or_identifier()),
                 '"' | '\'' => Some(self.lex_string()),
-                '+' | '/' | '*' => {
+                //comments are skipped like whitespace
+                '#' => {
+                    self.skip_line_comment();
+                    None
+                }
+                //a block comment spanning lines ends the statement like a newline would
+                '/' if self.peek() == Some('*') => match self.skip_block_comment() {
+                    Some(true) => Self::line_end(&tokens),
+                    Some(false) => None,
+                    None => Some(TokenType::Error(LexError::UnterminatedCommentError)),
+                },
+                '*' if self.peek() == Some('*') => {
+                    self.advance();
+                    self.advance();
+                    Some(self.lex_compound_assign("**"))
+                }
+                '/' if self.peek() == Some('/') => {
+                    self.advance();
+                    self.advance();
+                    Some(self.lex_compound_assign("//"))
+                }
+                '+' if self.peek() == Some('+') => {
+                    self.advance();
+                    self.advance();
+                    Some(TokenType::Step(Operator::Add))
+                }
+                '+' | '/' | '*' | '%' => {
                     self.advance();
-                    Some(TokenType::new_operator(&ch.to_string()))
+                    Some(self.lex_compound_assign(&ch.to_string()))
                 }
                 //Check if - is an operator or unary
+                //it is only a binary operator when it follows something that ends an operand
+                //-- after anything but a literal decrements, so 5--3 is still 5 - -3
                 '-' => {
                     self.advance();
-                    if let Some(previous) = tokens.last() {
-                        match previous.class {
-                            TokenType::Operator(_) => Some(TokenType::Unary(Unary::Neg)),
-                            _ => Some(TokenType::new_operator(&ch.to_string())),
+                    match tokens.last().map(|token| &token.class) {
+                        Some(TokenType::Ident(_) | TokenType::Rparen | TokenType::Rbracket)
+                            if self.current_char == Some('-') =>
+                        {
+                            self.advance();
+                            Some(TokenType::Step(Operator::Sub))
                         }
-                    } else {
-                        Some(TokenType::Unary(Unary::Neg))
+                        Some(
+                            TokenType::Literal(_)
+                            | TokenType::Ident(_)
+                            | TokenType::Rparen
+                            | TokenType::Rbracket,
+                        ) => Some(self.lex_compound_assign(&ch.to_string())),
+                        _ => Some(TokenType::Unary(Unary::Neg)),
                     }
                 }
                 //operators which need peeking
@@ -103,6 +193,45 @@ impl Lexer {
                     self.advance();
                     Some(TokenType::Rparen)
                 }
+                '{' => {
+                    self.advance();
+                    Some(TokenType::Lbrace)
+                }
+                '}' => {
+                    self.advance();
+                    Some(TokenType::Rbrace)
+                }
+                '[' => {
+                    self.advance();
+                    Some(TokenType::Lbracket)
+                }
+                ']' => {
+                    self.advance();
+                    Some(TokenType::Rbracket)
+                }
+                ':' => {
+                    self.advance();
+                    Some(TokenType::Colon)
+                }
+                ',' => {
+                    self.advance();
+                    Some(TokenType::Comma)
+                }
+                //.. and ..= make ranges, a single dot indexes a tuple
+                '.' if self.peek() == Some('.') => {
+                    self.advance();
+                    self.advance();
+                    if self.current_char == Some('=') {
+                        self.advance();
+                        Some(TokenType::new_operator("..="))
+                    } else {
+                        Some(TokenType::new_operator(".."))
+                    }
+                }
+                '.' => {
+                    self.advance();
+                    Some(TokenType::Dot)
+                }
                 '\r' => {
                     self.advance();
                     None
@@ -115,22 +244,21 @@ impl Lexer {
                 //handle newline character by incrementing the line and advancing the lexer
                 '\n' => {
                     self.line += 1;
-                    //if the last token added was an StmtEnd, then don't add another
-                    //else add an StmtEnd token
-                    let token_type = if let Some(token) = tokens.last() {
-                        if token.class == TokenType::StmtEnd {
-                            None
-                        } else {
-                            Some(TokenType::StmtEnd)
-                        }
-                    } else {
-                        Some(TokenType::StmtEnd)
-                    };
+                    let token_type = Self::line_end(&tokens);
                     self.advance();
                     //reset the start of the token relative to the line
                     self.token_start = 0;
                     token_type
                 }
+                //a backslash at the end of a line continues the statement on the next line
+                '\\' => {
+                    self.advance();
+                    if self.skip_line_continuation() {
+                        None
+                    } else {
+                        Some(TokenType::Error(LexError::InvalidTokenError))
+                    }
+                }
                 //do nothing for whitespaces
                 ' ' | '\t' => {
                     self.advance();
@@ -141,9 +269,8 @@ impl Lexer {
             };
             if let Some(token_type) = token_type {
                 //synchronize to the next token after whitespace when error occurs
-                match token_type {
-                    TokenType::Error(_) => self.synchronize_position(),
-                    _ => {}
+                if let TokenType::Error(_) = token_type {
+                    self.synchronize_position();
                 }
 
                 tokens.push(Token {
@@ -153,35 +280,107 @@ impl Lexer {
                 })
             }
         }
-
-        //add an EOF token at the end of the file
-        tokens.push(Token {
-            class: TokenType::Eof,
-            start: self.token_start,
-            line: self.line,
-        });
         tokens
     }
 
+    //The token added at the end of a line
+    //if the last token added was an StmtEnd, then don't add another
+    //a line ending with a binary operator continues on the next line
+    //else add an StmtEnd token
+    fn line_end(tokens: &[Token]) -> Option<TokenType> {
+        match tokens.last().map(|token| &token.class) {
+            Some(TokenType::StmtEnd | TokenType::Operator(_)) => None,
+            _ => Some(TokenType::StmtEnd),
+        }
+    }
+
+    //Skip a # comment, the newline ending it is left to end the statement
+    fn skip_line_comment(&mut self) {
+        while let Some(ch) = self.current_char {
+            if ch == '\n' {
+                return;
+            }
+            self.advance();
+        }
+    }
+
+    //Skip a /* */ comment, counting the lines inside it
+    //returns whether the comment spans several lines, None if it is never closed
+    fn skip_block_comment(&mut self) -> Option<bool> {
+        //skip the /*
+        self.advance();
+        self.advance();
+        let mut multiline = false;
+        while let Some(ch) = self.current_char {
+            self.advance();
+            match ch {
+                '*' if self.current_char == Some('/') => {
+                    self.advance();
+                    return Some(multiline);
+                }
+                '\n' => {
+                    multiline = true;
+                    self.line += 1;
+                    self.token_start = 0;
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    //An arithmetic operator, or a compound assignment if = follows it, eg- += or **=
+    //the operator has already been consumed
+    fn lex_compound_assign(&mut self, operator: &str) -> TokenType {
+        match (TokenType::new_operator(operator), self.current_char) {
+            (TokenType::Operator(operator), Some('=')) => {
+                self.advance();
+                TokenType::CompoundAssign(operator)
+            }
+            (operator, _) => operator,
+        }
+    }
+
     fn lex_number(&mut self) -> TokenType {
         let mut number = String::new();
         let mut is_float = false;
+        let mut has_exponent = false;
         while let Some(ch) = self.current_char {
             match ch {
                 '0'..='9' => {
                     self.advance();
                     number.push(ch);
                 }
-                '.' => {
-                    if !is_float{
-                        is_float = true;
+                //underscores separate digits and are ignored, eg- 1_000_000
+                '_' if number.ends_with(|ch: char| ch.is_ascii_digit())
+                    && self.peek().is_some_and(|ch| ch.is_ascii_digit()) =>
+                {
+                    self.advance();
+                }
+                //an exponent makes the number a float, eg- 2.5e-3
+                'e' | 'E' if !has_exponent => {
+                    has_exponent = true;
+                    is_float = true;
+                    self.advance();
+                    number.push('e');
+                    if let Some(sign @ ('+' | '-')) = self.current_char {
                         self.advance();
-                        number.push(ch);
-                    } else {
-                        return TokenType::Error(LexError::InvalidTokenError);
+                        number.push(sign);
+                    }
+                    if !self.current_char.is_some_and(|ch| ch.is_ascii_digit()) {
+                        return TokenType::Error(LexError::MalformedExponentError);
                     }
                 }
-                ' ' | '\r' | '\n' | '\t' | ';' | ')' | '+' | '-' | '*' | '/' | '=' | '>' | '<' => {
+                //a range after the number, eg- 1..10
+                '.' if self.peek() == Some('.') => break,
+                '.' if !is_float => {
+                    is_float = true;
+                    self.advance();
+                    number.push(ch);
+                }
+                '.' => return TokenType::Error(LexError::InvalidTokenError),
+                ' ' | '\r' | '\n' | '\t' | ';' | ')' | '}' | ']' | ':' | ',' | '+' | '-' | '*'
+                | '/' | '%' | '=' | '>' | '<' | '#' => {
                     break;
                 }
                 _ => return TokenType::Error(LexError::InvalidTokenError),
@@ -189,22 +388,97 @@ impl Lexer {
         }
 
         //return the number when we reach EOF
-        if is_float{
+        if is_float {
             TokenType::new_float_literal(number.as_str())
         } else {
             TokenType::new_number_literal(number.as_str())
         }
     }
 
+    //Lex the index after a dot, only digits are consumed so the next dot is left for another index
+    //an index too large for a number is an error, like any other number literal
+    fn lex_tuple_index(&mut self) -> TokenType {
+        let mut index = String::new();
+        while let Some(ch) = self.current_char {
+            match ch {
+                '0'..='9' => {
+                    self.advance();
+                    index.push(ch);
+                }
+                _ => break,
+            }
+        }
+        TokenType::new_number_literal(index.as_str())
+    }
+
+    //Lex a string, a string with ${} interpolations is lexed into its parts
+    //a string in triple quotes can span lines, the newline right after its opening quotes is dropped
+    //a raw string, starting with r, keeps its backslashes and ${ as they are written
     fn lex_string(&mut self) -> TokenType {
         let mut string: String = String::new();
+        let mut parts: Vec<StringPart> = Vec::new();
+        //the first error found in the code of an interpolation
+        let mut error = None;
+        let raw = self.current_char == Some('r');
+        if raw {
+            self.advance();
+        }
         let start_char = self.current_char.unwrap();
+        let multiline = self.at_triple_quote(start_char);
+        if multiline {
+            self.advance();
+            self.advance();
+        }
         self.advance();
+        if multiline && self.current_char == Some('\n') {
+            self.advance();
+            self.line += 1;
+            self.token_start = 0;
+        }
         while let Some(ch) = self.current_char {
-            if ch == start_char {
-                //advance before returning to consume the ending character
+            if ch == start_char && (!multiline || self.at_triple_quote(start_char)) {
+                //advance before returning to consume the ending characters
+                if multiline {
+                    self.advance();
+                    self.advance();
+                }
+                self.advance();
+                if let Some(error) = error {
+                    return error;
+                }
+                if parts.is_empty() {
+                    return TokenType::new_string_literal(string.as_str());
+                }
+                if !string.is_empty() {
+                    parts.push(StringPart::Text(string));
+                }
+                return TokenType::Interpolation(parts);
+            } else if ch == '\n' && !multiline {
+                //the newline is left to end the statement
+                return TokenType::Error(LexError::UnterminatedStringError);
+            } else if ch == '\n' {
+                self.advance();
+                string.push(ch);
+                self.line += 1;
+                self.token_start = 0;
+            } else if raw {
+                self.advance();
+                string.push(ch);
+            } else if ch == '$' && self.peek() == Some('{') {
                 self.advance();
-                return TokenType::new_string_literal(string.as_str());
+                self.advance();
+                match self.lex_interpolation() {
+                    Ok(code) => {
+                        if !string.is_empty() {
+                            parts.push(StringPart::Text(std::mem::take(&mut string)));
+                        }
+                        parts.push(StringPart::Code(code));
+                    }
+                    //the rest of the string is still lexed, so lexing goes on after its end
+                    Err(found) => {
+                        error.get_or_insert(found);
+                    }
+                }
             } else if ch == '\\' {
                 //handle escape characters
 
@@ -219,6 +493,7 @@ impl Lexer {
                         '\\' => string.push('\\'),
                         '\'' => string.push('\''),
                         '"' => string.push('"'),
+                        '$' => string.push('$'),
                         _ => {}
                     }
                 }
@@ -229,8 +504,41 @@ impl Lexer {
                 string.push(ch);
             }
         }
-        //return an error for unterminated string
-        TokenType::Error(LexError::UnterminatedStringError)
+        //return an error for unterminated string, unless its code had an error first
+        error.unwrap_or(TokenType::Error(LexError::UnterminatedStringError))
+    }
+
+    //Whether the lexer is at three of the quote, opening or closing a string which can span lines
+    fn at_triple_quote(&self, quote: char) -> bool {
+        self.source[self.pos as usize..].starts_with(&[quote; 3])
+    }
+
+    //Lex the code of an interpolation up to its closing brace, the ${ has been consumed
+    //the tokens end with an Eof token at the closing brace, so they can be parsed on their own
+    //a lexical error in the code, or a missing brace, is returned as the error of the whole string
+    fn lex_interpolation(&mut self) -> Result<Vec<Token>, TokenType> {
+        if self.interpolations >= MAX_INTERPOLATION_DEPTH {
+            return Err(TokenType::Error(LexError::InterpolationDepthError));
+        }
+        self.interpolations += 1;
+        let mut code = self.lex_tokens(true);
+        self.interpolations -= 1;
+        if let Some(error) = code
+            .iter()
+            .find(|token| matches!(token.class, TokenType::Error(_)))
+        {
+            return Err(error.class.clone());
+        }
+        if self.current_char != Some('}') {
+            return Err(TokenType::Error(LexError::UnterminatedStringError));
+        }
+        code.push(Token {
+            class: TokenType::Eof,
+            start: self.token_start,
+            line: self.line,
+        });
+        self.advance();
+        Ok(code)
     }
 
     //Generate keyword or identifier token
@@ -243,8 +551,8 @@ impl Lexer {
                     self.advance();
                     word.push(ch);
                 }
-                ' ' | '\r' | '\n' | '\t' | ';' | '(' | ')' | '+' | '-' | '*' | '/' | '=' | '<'
-                | '>' => break,
+                ' ' | '\r' | '\n' | '\t' | ';' | '(' | ')' | '{' | '}' | '[' | ']' | ':' | ','
+                | '.' | '+' | '-' | '*' | '/' | '%' | '=' | '<' | '>' | '#' => break,
                 _ => return TokenType::Error(LexError::InvalidTokenError),
             };
         }
@@ -252,15 +560,59 @@ impl Lexer {
         //check if the word is a keyword or other types such as an operator or literal else return an identifier
         if let Some(keyword) = Keyword::new_keyword(&word) {
             TokenType::Keyword(keyword)
+        } else if let Some(keyword) = self.aliases.get(&word) {
+            TokenType::Keyword(keyword.clone())
         } else if word == "and" || word == "or" {
             TokenType::new_operator(&word)
         } else if word == "true" || word == "false" {
             TokenType::Literal(Literal::Bool(word == "true"))
+        } else if word == "none" {
+            TokenType::Literal(Literal::None)
         } else {
             TokenType::Ident(word)
         }
     }
 
+    //Skip the whitespace and newline after a line continuation backslash
+    //returns false if something other than whitespace follows the backslash on its line
+    fn skip_line_continuation(&mut self) -> bool {
+        while let Some(ch) = self.current_char {
+            match ch {
+                ' ' | '\t' | '\r' => self.advance(),
+                '\n' => {
+                    self.advance();
+                    self.line += 1;
+                    self.token_start = 0;
+                    return true;
+                }
+                _ => return false,
+            }
+        }
+        //a backslash at the end of the source continues into nothing
+        true
+    }
+
+    //Skip a #! line at the very start of a script, so scripts can be made executable on unix
+    //the line still counts, keeping the line numbers of errors right
+    fn skip_shebang(&mut self) {
+        if self.pos != 0 || !self.source.starts_with(&['#', '!']) {
+            return;
+        }
+        while let Some(ch) = self.current_char {
+            self.advance();
+            if ch == '\n' {
+                self.line += 1;
+                self.token_start = 0;
+                return;
+            }
+        }
+    }
+
+    //The character after the current one
+    fn peek(&self) -> Option<char> {
+        self.source.get(self.pos as usize + 1).copied()
+    }
+
     //function to advance the pos attribute and update the current character
     fn advance(&mut self) {
         self.pos += 1;
@@ -275,10 +627,11 @@ impl Lexer {
 
     //Incase of a lexical error, move the position of the lexer to the next whitespace character to continue lexing
     //this prevents a large cascade of errors from one error
+    //it also stops at a closing brace, which may end the code of an interpolation
     fn synchronize_position(&mut self) {
         while let Some(ch) = self.current_char {
             match ch {
-                ' ' | '\n' => return,
+                ' ' | '\n' | '}' => return,
                 _ => self.advance(),
             }
         }
@@ -295,6 +648,63 @@ mod tests {
         //lex a valid number
         let mut lexer = Lexer::new("45");
         assert_eq!(TokenType::Literal(Literal::Number(45)), lexer.lex_number());
+        lexer = Lexer::new("1_000_000");
+        assert_eq!(
+            TokenType::Literal(Literal::Number(1_000_000)),
+            lexer.lex_number()
+        );
+        lexer = Lexer::new("2.5e-3");
+        assert_eq!(
+            TokenType::Literal(Literal::Float(0.0025)),
+            lexer.lex_number()
+        );
+        lexer = Lexer::new("1E+2_0");
+        assert_eq!(TokenType::Literal(Literal::Float(1e20)), lexer.lex_number());
+        lexer = Lexer::new("3e2-1");
+        assert_eq!(
+            TokenType::Literal(Literal::Float(300.0)),
+            lexer.lex_number()
+        );
+
+        //lex invalid numbers
+        for source in ["1e", "1e+", "2.5e-x", "1ex"] {
+            lexer = Lexer::new(source);
+            assert_eq!(
+                TokenType::Error(LexError::MalformedExponentError),
+                lexer.lex_number(),
+                "{}",
+                source
+            );
+        }
+        //numbers too large for an int or a float are errors at the literal
+        for source in ["3000000000", "1_000_000_000_000", "1e999"] {
+            lexer = Lexer::new(source);
+            assert_eq!(
+                TokenType::Error(LexError::NumberTooLargeError),
+                lexer.lex_number(),
+                "{}",
+                source
+            );
+        }
+        lexer = Lexer::new("2147483647");
+        assert_eq!(
+            TokenType::Literal(Literal::Number(i32::MAX)),
+            lexer.lex_number()
+        );
+        let tokens = Lexer::new("print 3000000000").lex();
+        assert_eq!(
+            (&tokens[1].class, tokens[1].line, tokens[1].start),
+            (&TokenType::Error(LexError::NumberTooLargeError), 1, 6)
+        );
+        for source in ["1__0", "1_", "1_.5", "1e2e3", "1e2.5"] {
+            lexer = Lexer::new(source);
+            assert_eq!(
+                TokenType::Error(LexError::InvalidTokenError),
+                lexer.lex_number(),
+                "{}",
+                source
+            );
+        }
     }
 
     //test the lex_string function
@@ -319,6 +729,49 @@ mod tests {
             TokenType::Error(LexError::UnterminatedStringError),
             lexer.lex_string()
         );
+        lexer = Lexer::new("\"Hello\nworld\"");
+        assert_eq!(
+            TokenType::Error(LexError::UnterminatedStringError),
+            lexer.lex_string()
+        );
+
+        //lex strings spanning lines and raw strings
+        lexer = Lexer::new("\"\"\"\nsay \"hi\"\n\\tthere\"\"\"");
+        assert_eq!(
+            TokenType::new_string_literal("say \"hi\"\n\tthere"),
+            lexer.lex_string()
+        );
+        lexer = Lexer::new("r'C:\\new\\${dir}'");
+        assert_eq!(
+            TokenType::new_string_literal("C:\\new\\${dir}"),
+            lexer.lex_string()
+        );
+        lexer = Lexer::new("r\"\"\"a\n\\b\"\"\"");
+        assert_eq!(TokenType::new_string_literal("a\n\\b"), lexer.lex_string());
+        lexer = Lexer::new("\'\'\'a\'\'");
+        assert_eq!(
+            TokenType::Error(LexError::UnterminatedStringError),
+            lexer.lex_string()
+        );
+    }
+
+    #[test]
+    fn lex_multiline_strings() {
+        //the tokens after a string spanning lines are on its last line
+        let tokens = Lexer::new("let s = \"\"\"a\nb\"\"\" + r\"c\"").lex();
+        let classes: Vec<(TokenType, u32, u32)> = tokens
+            .into_iter()
+            .map(|token| (token.class, token.line, token.start))
+            .collect();
+        assert_eq!(
+            classes[3..],
+            [
+                (TokenType::new_string_literal("a\nb"), 1, 8),
+                (TokenType::Operator(Operator::Add), 2, 5),
+                (TokenType::new_string_literal("c"), 2, 7),
+                (TokenType::Eof, 2, 11),
+            ]
+        );
     }
 
     #[test]
@@ -457,7 +910,7 @@ mod tests {
     }
 
     #[test]
-    fn test_float_lexing(){
+    fn test_float_lexing() {
         let mut lexer = Lexer::new("25.0");
         let expected = [
             Token {
@@ -649,6 +1102,71 @@ mod tests {
         assert!(compare_lexer_outputs(expected.to_vec(), lexer.lex()));
     }
 
+    #[test]
+    fn test_tuple_lexing() {
+        let mut lexer = Lexer::new("(1, 2.5).0.1");
+        let expected = [
+            Token {
+                class: TokenType::Lparen,
+                start: 0,
+                line: 1,
+            },
+            Token {
+                class: TokenType::new_number_literal("1"),
+                start: 1,
+                line: 1,
+            },
+            Token {
+                class: TokenType::Comma,
+                start: 2,
+                line: 1,
+            },
+            Token {
+                class: TokenType::new_float_literal("2.5"),
+                start: 4,
+                line: 1,
+            },
+            Token {
+                class: TokenType::Rparen,
+                start: 7,
+                line: 1,
+            },
+            Token {
+                class: TokenType::Dot,
+                start: 8,
+                line: 1,
+            },
+            Token {
+                class: TokenType::new_number_literal("0"),
+                start: 9,
+                line: 1,
+            },
+            Token {
+                class: TokenType::Dot,
+                start: 10,
+                line: 1,
+            },
+            Token {
+                class: TokenType::new_number_literal("1"),
+                start: 11,
+                line: 1,
+            },
+            Token {
+                class: TokenType::Eof,
+                start: 12,
+                line: 1,
+            },
+        ];
+        assert!(compare_lexer_outputs(expected.to_vec(), lexer.lex()));
+
+        //an index too large for a number is an error at the index
+        let tokens = Lexer::new("t.99999999999999999999.0").lex();
+        assert_eq!(
+            (&tokens[2].class, tokens[2].start),
+            (&TokenType::Error(LexError::NumberTooLargeError), 2)
+        );
+    }
+
     //test if the lexer can skip whitespaces correctly
     #[test]
     fn test_whitespace_skips() {
@@ -697,4 +1215,311 @@ mod tests {
         ];
         assert!(compare_lexer_outputs(expected.to_vec(), lexer.lex()));
     }
+
+    #[test]
+    fn test_shebang_skip() {
+        let mut lexer = Lexer::new("#!/usr/bin/env estel\nprint 1");
+        let expected = [
+            Token {
+                class: TokenType::Keyword(Keyword::Print),
+                start: 0,
+                line: 2,
+            },
+            Token {
+                class: TokenType::new_number_literal("1"),
+                start: 6,
+                line: 2,
+            },
+            Token {
+                class: TokenType::Eof,
+                start: 7,
+                line: 2,
+            },
+        ];
+        assert!(compare_lexer_outputs(expected.to_vec(), lexer.lex()));
+
+        //a script with only a shebang has no tokens
+        let tokens = Lexer::new("#!/usr/bin/env estel").lex();
+        assert_eq!(tokens.len(), 1);
+        //later in the script #! starts a comment
+        let tokens = Lexer::new("print 1\n#!estel").lex();
+        assert_eq!(tokens.len(), 4);
+    }
+
+    #[test]
+    fn test_comments() {
+        let classes = |source: &str| {
+            Lexer::new(source)
+                .lex()
+                .into_iter()
+                .map(|token| token.class)
+                .collect::<Vec<TokenType>>()
+        };
+        let print_one = vec![
+            TokenType::Keyword(Keyword::Print),
+            TokenType::new_number_literal("1"),
+            TokenType::Eof,
+        ];
+        assert_eq!(classes("print 1 # one"), print_one);
+        assert_eq!(classes("print 1# one"), print_one);
+        assert_eq!(classes("print /* a # b */ 1"), print_one);
+        assert_eq!(classes("print 1/**/"), print_one);
+        //a comment ends at the newline, which still ends the statement
+        assert_eq!(classes("# one\nprint 1 # one\n")[3], TokenType::StmtEnd);
+        assert_eq!(
+            classes("1 + # two\n2"),
+            vec![
+                TokenType::new_number_literal("1"),
+                TokenType::new_operator("+"),
+                TokenType::new_number_literal("2"),
+                TokenType::Eof
+            ]
+        );
+        assert_eq!(classes("1 /* one\ntwo */ 2")[1], TokenType::StmtEnd);
+        assert_eq!(classes("a/2")[1], TokenType::new_operator("/"));
+
+        //lines inside block comments are counted
+        let tokens = Lexer::new("/*\n\n*/ print /* */ 1").lex();
+        assert_eq!((tokens[1].line, tokens[1].start), (3, 3));
+        assert_eq!((tokens[2].line, tokens[2].start), (3, 15));
+
+        //an unclosed comment is reported where it starts
+        let tokens = Lexer::new("print 1\nprint 2 /* two\n").lex();
+        let error = &tokens[tokens.len() - 2];
+        assert_eq!(
+            error.class,
+            TokenType::Error(LexError::UnterminatedCommentError)
+        );
+        assert_eq!((error.line, error.start), (2, 8));
+    }
+
+    #[test]
+    fn test_line_continuation() {
+        let classes = |source: &str| {
+            Lexer::new(source)
+                .lex()
+                .into_iter()
+                .map(|token| token.class)
+                .collect::<Vec<TokenType>>()
+        };
+        let expected = vec![
+            TokenType::new_number_literal("1"),
+            TokenType::new_operator("+"),
+            TokenType::new_number_literal("2"),
+            TokenType::Eof,
+        ];
+        assert_eq!(classes("1 + \\\n2"), expected);
+        assert_eq!(classes("1 \\ \r\n+ 2"), expected);
+        //lines ending with a binary operator continue without a backslash
+        assert_eq!(classes("1 +\n\n2"), expected);
+
+        //the continued tokens keep their own lines
+        let tokens = Lexer::new("1 \\\n + 2").lex();
+        assert_eq!((tokens[1].line, tokens[1].start), (2, 1));
+
+        //a backslash followed by more code is an error
+        assert_eq!(
+            classes("1 \\ 2")[1],
+            TokenType::Error(LexError::InvalidTokenError)
+        );
+    }
+
+    #[test]
+    fn lex_compound_assign() {
+        let classes = |source: &str| {
+            Lexer::new(source)
+                .lex()
+                .into_iter()
+                .map(|token| token.class)
+                .collect::<Vec<TokenType>>()
+        };
+        let ident = TokenType::Ident(String::from("a"));
+        let one = TokenType::new_number_literal("1");
+        for (source, operator) in [
+            ("a += 1", Operator::Add),
+            ("a -= 1", Operator::Sub),
+            ("a*=1", Operator::Mul),
+            ("a /= 1", Operator::Div),
+            ("a %= 1", Operator::Mod),
+            ("a **= 1", Operator::Pow),
+            ("a //= 1", Operator::FloorDiv),
+        ] {
+            assert_eq!(
+                classes(source),
+                vec![
+                    ident.clone(),
+                    TokenType::CompoundAssign(operator),
+                    one.clone(),
+                    TokenType::Eof
+                ]
+            );
+        }
+        //a - which can't be a binary operator stays a negation
+        assert_eq!(
+            classes("(-=")[1..3],
+            [TokenType::Unary(Unary::Neg), TokenType::Assign]
+        );
+        assert_eq!(
+            classes("a++; (b)--")[..6],
+            [
+                ident.clone(),
+                TokenType::Step(Operator::Add),
+                TokenType::StmtEnd,
+                TokenType::Lparen,
+                TokenType::Ident(String::from("b")),
+                TokenType::Rparen,
+            ]
+        );
+        assert_eq!(classes("(b)--")[3], TokenType::Step(Operator::Sub));
+        assert_eq!(classes("a % 1")[1], TokenType::new_operator("%"));
+        assert_eq!(classes("a**1")[1], TokenType::new_operator("**"));
+        assert_eq!(classes("a // 1")[1], TokenType::new_operator("//"));
+        assert_eq!(
+            classes("a ~ 1")[1],
+            TokenType::Error(LexError::InvalidTokenError)
+        );
+        //~ no longer ends a number or a name either
+        assert_eq!(
+            classes("1~2")[0],
+            TokenType::Error(LexError::InvalidTokenError)
+        );
+        assert_eq!(
+            classes("a~1")[0],
+            TokenType::Error(LexError::InvalidTokenError)
+        );
+    }
+
+    #[test]
+    fn lex_ranges() {
+        let classes = |source: &str| {
+            Lexer::new(source)
+                .lex()
+                .into_iter()
+                .map(|token| token.class)
+                .collect::<Vec<TokenType>>()
+        };
+        assert_eq!(
+            classes("1..10"),
+            vec![
+                TokenType::new_number_literal("1"),
+                TokenType::new_operator(".."),
+                TokenType::new_number_literal("10"),
+                TokenType::Eof
+            ]
+        );
+        assert_eq!(classes("a..=n")[1], TokenType::new_operator("..="));
+        assert_eq!(
+            classes("t.0..2")[1..4],
+            [
+                TokenType::Dot,
+                TokenType::new_number_literal("0"),
+                TokenType::new_operator(".."),
+            ]
+        );
+        assert_eq!(classes("1.5..2")[0], TokenType::new_float_literal("1.5"));
+    }
+
+    #[test]
+    fn lex_interpolation() {
+        let tokens = Lexer::new("\"a${b + 1}c${\"${d}\"}\"").lex();
+        match &tokens[0].class {
+            TokenType::Interpolation(parts) => {
+                assert_eq!(parts.len(), 4);
+                assert_eq!(parts[0], StringPart::Text(String::from("a")));
+                //the tokens of the code keep their positions in the source
+                match &parts[1] {
+                    StringPart::Code(tokens) => {
+                        assert_eq!(tokens.len(), 4);
+                        assert_eq!(tokens[0].class, TokenType::Ident(String::from("b")));
+                        assert_eq!(tokens[0].start, 4);
+                        assert_eq!(tokens[3].class, TokenType::Eof);
+                        assert_eq!(tokens[3].start, 9);
+                    }
+                    part => panic!("Expected code, got {:?}", part),
+                }
+                assert_eq!(parts[2], StringPart::Text(String::from("c")));
+                match &parts[3] {
+                    StringPart::Code(tokens) => {
+                        assert!(matches!(tokens[0].class, TokenType::Interpolation(_)))
+                    }
+                    part => panic!("Expected code, got {:?}", part),
+                }
+            }
+            class => panic!("Expected an interpolation, got {:?}", class),
+        }
+        //braces of maps inside the code don't end it
+        match &Lexer::new("\"${{1: 2}}\"").lex()[0].class {
+            TokenType::Interpolation(parts) => match &parts[..] {
+                [StringPart::Code(tokens)] => {
+                    let classes: Vec<&TokenType> =
+                        tokens.iter().map(|token| &token.class).collect();
+                    let expected: Vec<TokenType> = Lexer::new("{1: 2}")
+                        .lex()
+                        .into_iter()
+                        .map(|token| token.class)
+                        .collect();
+                    assert_eq!(classes, expected.iter().collect::<Vec<&TokenType>>());
+                }
+                parts => panic!("Expected one code part, got {:?}", parts),
+            },
+            class => panic!("Expected an interpolation, got {:?}", class),
+        }
+
+        //an escaped $ and a $ without a brace are text
+        assert_eq!(
+            Lexer::new("\"\\${a} $a\"").lex()[0].class,
+            TokenType::new_string_literal("${a} $a")
+        );
+
+        //errors in the code are errors of the whole string, lexing goes on after the string
+        let tokens = Lexer::new("\"${1 @} a\" 2").lex();
+        assert_eq!(
+            tokens[0].class,
+            TokenType::Error(LexError::InvalidTokenError)
+        );
+        assert_eq!(tokens[1].class, TokenType::new_number_literal("2"));
+        assert_eq!(
+            Lexer::new("\"${1").lex()[0].class,
+            TokenType::Error(LexError::UnterminatedStringError)
+        );
+        let nested = "\"${".repeat(MAX_INTERPOLATION_DEPTH + 1);
+        assert_eq!(
+            Lexer::new(&nested).lex()[0].class,
+            TokenType::Error(LexError::InterpolationDepthError)
+        );
+    }
+
+    #[test]
+    fn lex_keyword_aliases() {
+        let mut aliases = KeywordAliases::new();
+        assert!(aliases.add("mientras", Keyword::While));
+        assert!(aliases.add("imprimir", Keyword::Print));
+        //aliases can't take the place of reserved words or be lexed as something else
+        assert!(!aliases.add("true", Keyword::If));
+        assert!(!aliases.add("for", Keyword::While));
+        assert!(!aliases.add("2nd", Keyword::Let));
+        assert!(!aliases.add("für", Keyword::For));
+
+        let tokens = Lexer::with_aliases("mientras a { imprimir \"${imprimir}\" }", &aliases).lex();
+        assert_eq!(tokens[0].class, TokenType::Keyword(Keyword::While));
+        assert_eq!(tokens[3].class, TokenType::Keyword(Keyword::Print));
+        match &tokens[4].class {
+            TokenType::Interpolation(parts) => match &parts[0] {
+                StringPart::Code(code) => {
+                    assert_eq!(code[0].class, TokenType::Keyword(Keyword::Print))
+                }
+                part => panic!("Expected code but got {:?}", part),
+            },
+            class => panic!("Expected an interpolation but got {:?}", class),
+        }
+        //the canonical spellings still work, and the aliases are plain names without them
+        assert_eq!(
+            Lexer::with_aliases("while", &aliases).lex()[0].class,
+            TokenType::Keyword(Keyword::While)
+        );
+        assert_eq!(
+            Lexer::new("mientras").lex()[0].class,
+            TokenType::Ident(String::from("mientras"))
+        );
+    }
 }
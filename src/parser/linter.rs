@@ -0,0 +1,288 @@
+use super::errors::Warning;
+use super::expr::Expr;
+use super::stmt::{Block, Span, Stmt};
+use super::token::Literal;
+use super::visitor::{walk_expr, walk_stmt, ExprVisitor, StmtVisitor};
+
+//A style check of estel lint, each one can be turned on or off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rule {
+    //Expressions nested deeper than the limit
+    NestedExpression,
+    //Numbers written in expressions instead of being named by a constant
+    MagicNumber,
+    //Variables, functions and parameters named with a single letter, outside of loop counters
+    ShortName,
+    //Statements made of more expression nodes than the limit
+    LongStatement,
+}
+
+impl Rule {
+    pub const ALL: [Self; 4] = [
+        Self::NestedExpression,
+        Self::MagicNumber,
+        Self::ShortName,
+        Self::LongStatement,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+
+    //The name of the rule in the --rules option
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NestedExpression => "nested-expression",
+            Self::MagicNumber => "magic-number",
+            Self::ShortName => "short-name",
+            Self::LongStatement => "long-statement",
+        }
+    }
+}
+
+//The rules checked by the linter and their limits
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintConfig {
+    pub rules: Vec<Rule>,
+    //The deepest an expression can be nested, a single value is 1 deep
+    pub max_depth: usize,
+    //The most expression nodes a statement can have, the statements of nested blocks are counted on their own
+    pub max_nodes: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            rules: Rule::ALL.to_vec(),
+            max_depth: 5,
+            max_nodes: 20,
+        }
+    }
+}
+
+//Numbers whose meaning is clear without a name
+const PLAIN_NUMBERS: [i32; 3] = [0, 1, 2];
+
+//Finds code that runs as intended but is hard to read, only run by estel lint
+pub struct Linter<'a> {
+    config: &'a LintConfig,
+    //The span of the statement being checked
+    span: Span,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Linter<'a> {
+    pub fn lint(block: &Block, config: &'a LintConfig) -> Vec<Warning> {
+        let mut linter = Self {
+            config,
+            span: Span::default(),
+            warnings: Vec::new(),
+        };
+        linter.visit_block(block);
+        linter.warnings
+    }
+
+    fn checks(&self, rule: Rule) -> bool {
+        self.config.rules.contains(&rule)
+    }
+
+    fn check_names<'n>(&mut self, names: impl IntoIterator<Item = &'n String>) {
+        if !self.checks(Rule::ShortName) {
+            return;
+        }
+        for name in names {
+            //_ is the usual name of a value which is not used
+            if name.chars().count() == 1 && name != "_" {
+                self.warnings
+                    .push(Warning::ShortName(name.to_owned(), self.span));
+            }
+        }
+    }
+
+    //Check the size of the expressions of a statement, not counting its nested blocks
+    fn check_size(&mut self, stmt: &Stmt) {
+        let mut measure = Measure {
+            nodes: 0,
+            depth: 0,
+            max_depth: 0,
+        };
+        walk_stmt(&mut measure, stmt);
+        let config = self.config;
+        if self.checks(Rule::NestedExpression) && measure.max_depth > config.max_depth {
+            self.warnings.push(Warning::NestedExpression(
+                measure.max_depth,
+                config.max_depth,
+                self.span,
+            ));
+        }
+        if self.checks(Rule::LongStatement) && measure.nodes > config.max_nodes {
+            self.warnings.push(Warning::LongStatement(
+                measure.nodes,
+                config.max_nodes,
+                self.span,
+            ));
+        }
+    }
+}
+
+//A number given straight to a variable is already named by it, eg- let limit = 10
+fn is_named_number(stmt: &Stmt) -> bool {
+    let (Stmt::Assign(_, expr) | Stmt::Const(_, expr) | Stmt::Reassign(_, expr)) = stmt else {
+        return false;
+    };
+    let expr = match expr {
+        Expr::Negate(expr, _) => expr,
+        expr => expr,
+    };
+    matches!(expr, Expr::Literal(Literal::Number(_) | Literal::Float(_)))
+}
+
+impl ExprVisitor for Linter<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Literal(literal @ (Literal::Number(_) | Literal::Float(_))) => {
+                let plain = match literal {
+                    Literal::Number(number) => PLAIN_NUMBERS.contains(number),
+                    _ => PLAIN_NUMBERS
+                        .iter()
+                        .any(|&number| Literal::Float(number as f32) == *literal),
+                };
+                if !plain && self.checks(Rule::MagicNumber) {
+                    self.warnings
+                        .push(Warning::MagicNumber(literal.to_string(), self.span));
+                }
+            }
+            Expr::Function(function) => {
+                self.check_names(&function.params);
+                self.visit_block(&function.body);
+            }
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+impl StmtVisitor for Linter<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        self.check_size(stmt);
+        match stmt {
+            Stmt::Assign(name, _) | Stmt::Const(name, _) => self.check_names([name]),
+            Stmt::Destructure(names, _) => self.check_names(names),
+            Stmt::FnDecl(function) => {
+                self.check_names([&function.name]);
+                self.check_names(&function.params);
+            }
+            //the variables declared by the init are loop counters, which are often a single letter
+            Stmt::For(Some(init), ..) => walk_stmt(self, init),
+            _ => {}
+        }
+        match stmt {
+            Stmt::For(_, condition, step, body) => {
+                if let Some(condition) = condition {
+                    self.visit_expr(condition);
+                }
+                if let Some(step) = step {
+                    self.visit_stmt(step);
+                }
+                self.visit_block(body);
+            }
+            stmt if is_named_number(stmt) => {}
+            stmt => walk_stmt(self, stmt),
+        }
+    }
+
+    //the statement holding the block is checked on after it, eg- the condition of a do while
+    fn visit_block(&mut self, block: &Block) {
+        let outer = self.span;
+        for (stmt, span) in block.iter() {
+            self.span = *span;
+            self.visit_stmt(stmt);
+        }
+        self.span = outer;
+    }
+}
+
+//Counts the expression nodes of a statement and how deep they are nested
+struct Measure {
+    nodes: usize,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl ExprVisitor for Measure {
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.nodes += 1;
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+        walk_expr(self, expr);
+        self.depth -= 1;
+    }
+}
+
+impl StmtVisitor for Measure {
+    //nested blocks are measured as statements of their own
+    fn visit_block(&mut self, _: &Block) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{lexer::Lexer, parser::Parser};
+    use super::*;
+
+    fn lint(source: &str, config: &LintConfig) -> Vec<(String, (u32, u32))> {
+        let tokens = Lexer::new(source).lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        Linter::lint(&block, config)
+            .iter()
+            .map(|warning| (warning.get_message(), warning.get_position()))
+            .collect()
+    }
+
+    #[test]
+    fn lint_rules() {
+        let config = LintConfig::default();
+        let warnings = lint(
+            "const LIMIT = 60\nlet n = LIMIT * 2\nfor (let i = 0; i < 10; i++) { print i * 1.5 }\nlet rate = -0.5",
+            &config,
+        );
+        assert_eq!(
+            warnings,
+            [
+                (String::from("Single letter name n"), (2, 0)),
+                (String::from("Magic number 10"), (3, 0)),
+                (String::from("Magic number 1.5"), (3, 31)),
+            ]
+        );
+
+        let source = "fn area(w, height) {\n  return ((w + 1) * (height + 1) - (w - 1) * (height - 1)) / 4\n}";
+        assert_eq!(
+            lint(source, &config),
+            [
+                (String::from("Single letter name w"), (1, 0)),
+                (String::from("Magic number 4"), (2, 2)),
+            ]
+        );
+        let config = LintConfig {
+            rules: vec![Rule::NestedExpression, Rule::LongStatement],
+            max_depth: 4,
+            max_nodes: 10,
+        };
+        assert_eq!(
+            lint(source, &config),
+            [
+                (String::from("Expression is nested 5 deep"), (2, 2)),
+                (String::from("Statement has 17 expression nodes"), (2, 2)),
+            ]
+        );
+
+        //function values are checked where they are written
+        let config = LintConfig::default();
+        assert_eq!(
+            lint("let double = fn(x) { return x * 3 }", &config),
+            [
+                (String::from("Single letter name x"), (1, 0)),
+                (String::from("Magic number 3"), (1, 21)),
+            ]
+        );
+    }
+}
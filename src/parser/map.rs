@@ -0,0 +1,120 @@
+use super::token::Literal;
+use std::cmp::Ordering;
+
+//A map from keys to values, eg- {"a": 1, "b": 2}
+//any value can be a key, floats included, so entries are kept in a list rather than hashed
+//the entries stay in the order their keys were first inserted, which is the order they print in
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+    entries: Vec<(Literal, Literal)>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &Literal) -> Option<&Literal> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_mut(&mut self, key: &Literal) -> Option<&mut Literal> {
+        self.entries
+            .iter_mut()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
+    }
+
+    //Set the value of a key, an existing key keeps its place in the map
+    pub fn insert(&mut self, key: Literal, value: Literal) {
+        match self.get_mut(&key) {
+            Some(old) => *old = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Literal, Literal)> {
+        self.entries.iter()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Literal> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Literal> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl FromIterator<(Literal, Literal)> for Map {
+    fn from_iter<T: IntoIterator<Item = (Literal, Literal)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+//Two maps are equal when they hold the same entries, whatever order they were inserted in
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+//Maps have no order, they only compare as equal or not
+impl PartialOrd for Map {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self == other).then_some(Ordering::Equal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_entries() {
+        let mut map: Map = [
+            (Literal::String("a".to_string()), Literal::Number(1)),
+            (Literal::Number(2), Literal::Bool(true)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(map.get(&Literal::Number(2)), Some(&Literal::Bool(true)));
+        assert_eq!(map.get(&Literal::String("b".to_string())), None);
+
+        //inserting an existing key replaces its value in place
+        map.insert(Literal::String("a".to_string()), Literal::Number(3));
+        map.insert(Literal::Float(0.5), Literal::Number(4));
+        assert_eq!(map.len(), 3);
+        assert_eq!(
+            map.values().cloned().collect::<Vec<_>>(),
+            vec![Literal::Number(3), Literal::Bool(true), Literal::Number(4)]
+        );
+
+        //the order of the entries does not matter for equality
+        let mut reversed: Vec<_> = map.iter().cloned().collect();
+        reversed.reverse();
+        let reversed: Map = reversed.into_iter().collect();
+        assert_eq!(map, reversed);
+        assert_eq!(map.partial_cmp(&reversed), Some(Ordering::Equal));
+        assert_ne!(map, Map::new());
+        assert_eq!(map.partial_cmp(&Map::new()), None);
+    }
+}
@@ -1,7 +1,18 @@
+pub mod analyzer;
+pub mod builtins;
+pub mod cursor;
 pub mod expr;
+pub mod features;
 pub mod lexer;
+pub mod linter;
+pub mod map;
+pub mod optimizer;
+#[allow(clippy::module_inception)]
 pub mod parser;
+pub mod record;
+pub mod semantics;
 pub mod stmt;
 pub mod token;
+pub mod visitor;
 
 pub use crate::errors;
@@ -0,0 +1,235 @@
+use super::analyzer::fold_node;
+use super::expr::Expr;
+use super::stmt::{Accessor, Block, Stmt};
+use super::token::Literal;
+use super::visitor::{walk_expr, ExprVisitor};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+//Rewrites a parsed program so it does less work while running, without changing what it does
+//arithmetic made only of literals is computed once here instead of on every run of a loop body
+pub struct Optimizer;
+
+impl Optimizer {
+    pub fn optimize(block: &mut Block) {
+        Self::fold_block(block);
+    }
+
+    fn fold_block(block: &mut Block) {
+        for stmt in block.stmts.iter_mut() {
+            Self::fold_stmt(stmt);
+        }
+    }
+
+    fn fold_stmt(stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Expr(expr)
+            | Stmt::Print(expr)
+            | Stmt::Assign(_, expr)
+            | Stmt::Const(_, expr)
+            | Stmt::Destructure(_, expr)
+            | Stmt::Reassign(_, expr)
+            | Stmt::Return(Some(expr))
+            | Stmt::Exit(Some(expr))
+            | Stmt::Throw(expr) => Self::fold_expr(expr),
+            Stmt::IndexAssign(_, accessors, expr) => {
+                accessors
+                    .iter_mut()
+                    .filter_map(Accessor::index_mut)
+                    .for_each(Self::fold_expr);
+                Self::fold_expr(expr);
+            }
+            Stmt::Block(block) => Self::fold_block(block),
+            //a declaration is only shared once the program runs
+            Stmt::FnDecl(function) => {
+                if let Some(function) = Rc::get_mut(function) {
+                    Self::fold_block(&mut function.body);
+                }
+            }
+            Stmt::If(condition, then, otherwise) => {
+                Self::fold_expr(condition);
+                Self::fold_block(then);
+                if let Some(otherwise) = otherwise {
+                    Self::fold_block(otherwise);
+                }
+            }
+            Stmt::While(condition, body) | Stmt::DoWhile(condition, body) => {
+                Self::fold_expr(condition);
+                Self::fold_block(body);
+            }
+            Stmt::For(init, condition, step, body) => {
+                if let Some(init) = init {
+                    Self::fold_stmt(init);
+                }
+                if let Some(condition) = condition {
+                    Self::fold_expr(condition);
+                }
+                if let Some(step) = step {
+                    Self::fold_stmt(step);
+                }
+                Self::fold_block(body);
+            }
+            Stmt::Assert(condition, message) => {
+                Self::fold_expr(condition);
+                if let Some(message) = message {
+                    Self::fold_expr(message);
+                }
+            }
+            Stmt::Try(body, _, catch) => {
+                Self::fold_block(body);
+                Self::fold_block(catch);
+            }
+            Stmt::Struct(_)
+            | Stmt::Return(None)
+            | Stmt::Exit(None)
+            | Stmt::Break
+            | Stmt::Continue => {}
+        }
+    }
+
+    //Replace the largest constant parts of an expression with their values
+    //expressions which fail or overflow are left to report their error when they run
+    //the operands are folded first, so each node is folded once its operands are literals
+    fn fold_expr(expr: &mut Expr) {
+        match expr {
+            //a variable is only known once the program runs
+            Expr::Ident(_) | Expr::Literal(_) => return,
+            Expr::Function(function) => {
+                if let Some(function) = Rc::get_mut(function) {
+                    Self::fold_block(&mut function.body);
+                }
+            }
+            Expr::Div(left, right, _)
+            | Expr::Mul(left, right, _)
+            | Expr::Mod(left, right, _)
+            | Expr::FloorDiv(left, right, _)
+            | Expr::Pow(left, right, _)
+            | Expr::Add(left, right, _)
+            | Expr::Sub(left, right, _)
+            | Expr::Greater(left, right)
+            | Expr::Less(left, right)
+            | Expr::GreaterEqual(left, right)
+            | Expr::LessEqual(left, right)
+            | Expr::Equal(left, right)
+            | Expr::NotEqual(left, right)
+            | Expr::And(left, right)
+            | Expr::Or(left, right)
+            | Expr::Index(left, right)
+            | Expr::Range(left, right, _) => {
+                Self::fold_expr(left);
+                Self::fold_expr(right);
+            }
+            Expr::Not(expr)
+            | Expr::Negate(expr, _)
+            | Expr::TupleIndex(expr, _)
+            | Expr::Field(expr, _)
+            | Expr::Is(expr, _)
+            | Expr::Cast(expr, _) => Self::fold_expr(expr),
+            Expr::Record(_, fields) => {
+                for (_, value) in fields {
+                    Self::fold_expr(value);
+                }
+            }
+            Expr::Compare(first, comparisons) => {
+                Self::fold_expr(first);
+                for (_, operand) in comparisons {
+                    Self::fold_expr(operand);
+                }
+            }
+            Expr::Tuple(exprs) | Expr::Interpolation(exprs) | Expr::Call(_, exprs) => {
+                exprs.iter_mut().for_each(Self::fold_expr)
+            }
+            Expr::Slice(value, start, end) => {
+                Self::fold_expr(value);
+                for bound in [start, end].into_iter().flatten() {
+                    Self::fold_expr(bound);
+                }
+            }
+            Expr::Map(entries) => {
+                for (key, value) in entries {
+                    Self::fold_expr(key);
+                    Self::fold_expr(value);
+                }
+            }
+        }
+        let mut operands = Operands(Vec::new());
+        walk_expr(&mut operands, expr);
+        if let Some(value) = fold_node(expr, operands.0, &HashMap::new()) {
+            *expr = Expr::Literal(value);
+        }
+    }
+}
+
+//The values of the operands of an expression, None for an operand which was not folded into a literal
+struct Operands(Vec<Option<Literal>>);
+
+impl ExprVisitor for Operands {
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.0.push(match expr {
+            Expr::Literal(_) => fold_node(expr, Vec::new(), &HashMap::new()),
+            _ => None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{lexer::Lexer, parser::Parser, token::Literal};
+    use super::*;
+
+    fn optimize(source: &str) -> Block {
+        let tokens = Lexer::new(source).lex();
+        let mut block = Parser::new(&tokens).parse().unwrap();
+        Optimizer::optimize(&mut block);
+        block
+    }
+
+    #[test]
+    fn fold_loop_constants() {
+        let block = optimize("let i = 0\nwhile i < 10 * 10 {\n  i = i + 60 * -60\n}");
+        match &block.stmts[1] {
+            Stmt::While(Expr::Less(_, limit), body) => {
+                assert!(matches!(**limit, Expr::Literal(Literal::Number(100))));
+                match &body.stmts[0] {
+                    Stmt::Reassign(_, Expr::Add(left, right, _)) => {
+                        assert!(matches!(**left, Expr::Ident(_)));
+                        assert!(matches!(**right, Expr::Literal(Literal::Number(-3600))));
+                    }
+                    stmt => panic!("Expected a reassignment, got {:?}", stmt),
+                }
+            }
+            stmt => panic!("Expected a while loop, got {:?}", stmt),
+        }
+
+        //function bodies are folded too
+        match &optimize("fn f(x) { return x * (2 + 3) }").stmts[0] {
+            Stmt::FnDecl(function) => match &function.body.stmts[0] {
+                Stmt::Return(Some(Expr::Mul(_, right, _))) => {
+                    assert!(matches!(**right, Expr::Literal(Literal::Number(5))))
+                }
+                stmt => panic!("Expected a return, got {:?}", stmt),
+            },
+            stmt => panic!("Expected a function, got {:?}", stmt),
+        }
+
+        //chained comparisons are folded once every operand is known
+        for (source, value) in [("print 1 < 2 <= 2 * 1", true), ("print 1 < 0 < x", false)] {
+            match &optimize(source).stmts[0] {
+                Stmt::Print(Expr::Literal(Literal::Bool(folded))) => assert_eq!(*folded, value),
+                stmt => panic!("Expected {} to be folded, got {:?}", source, stmt),
+            }
+        }
+        assert!(matches!(
+            &optimize("print 0 < x < 1 + 1").stmts[0],
+            Stmt::Print(Expr::Compare(_, comparisons))
+                if comparisons[1].1 == Expr::Literal(Literal::Number(2))
+        ));
+
+        //failing arithmetic is kept to report its error when it runs
+        for source in ["print 1 - none", "print 2147483647 + 1", "print 1 + \"a\""] {
+            if let Stmt::Print(Expr::Literal(_)) = &optimize(source).stmts[0] {
+                panic!("{} was folded", source);
+            }
+        }
+    }
+}
@@ -1,149 +1,713 @@
+use super::cursor::TokenCursor;
 use super::errors::{ExprError, StmtError, StmtErrors};
 use super::expr::*;
+use super::features::{Feature, Features};
 use super::stmt::*;
 use super::token::*;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 pub struct Parser<'a> {
-    tokens: &'a Vec<Token>,
-    pos: u32,
+    cursor: TokenCursor<'a>,
+    //The id given to the next statement
+    next_id: u32,
+    //Errors of all statements, including the ones inside blocks
+    errors: Vec<StmtError>,
+    //The number of blocks the cursor is inside
+    depth: usize,
+    //The number of function bodies the cursor is inside, return is only valid inside one
+    functions: usize,
+    //The number of loops the cursor is inside within the current function
+    //break and continue are only valid inside one
+    loops: usize,
+    //Tokens which also end the expressions being parsed when they are outside of parenthesis
+    //eg- the brace after the condition of an if, or the parenthesis closing the header of a for loop
+    ends_at: Vec<TokenType>,
+    //The number of map entries and indexes the expression being parsed is nested in
+    nesting: usize,
+    //The variables declared in each block around the cursor, the first holds the global ones
+    //only kept when variables have to be declared before they are used, or can't be shadowed
+    declared: Option<Vec<HashSet<String>>>,
+    //Whether variables have to be declared before they are used
+    check_uses: bool,
+    //Whether a block may declare a variable with the name of one declared in a block around it
+    shadowing: bool,
+    //The experimental features the program may use
+    features: Features,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &'a Vec<Token>) -> Parser<'a> {
-        Self { tokens, pos: 0 }
+    pub fn new(tokens: &'a [Token]) -> Parser<'a> {
+        Self {
+            cursor: TokenCursor::new(tokens),
+            next_id: 0,
+            errors: Vec::new(),
+            depth: 0,
+            functions: 0,
+            loops: 0,
+            ends_at: Vec::new(),
+            nesting: 0,
+            declared: None,
+            check_uses: false,
+            shadowing: true,
+            features: Features::default(),
+        }
+    }
+
+    //Allow the program to use experimental features
+    pub fn with_features(self, features: Features) -> Self {
+        Self { features, ..self }
+    }
+
+    //Create a parser which checks that every variable is declared before it is used
+    //globals: the variables declared before the program, eg- by a prelude or earlier prompts
+    pub fn with_declared(tokens: &'a [Token], globals: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            declared: Some(vec![globals.into_iter().collect()]),
+            check_uses: true,
+            ..Self::new(tokens)
+        }
+    }
+
+    //Don't allow a block to declare a variable already declared in a block around it
+    //globals: the variables declared before the program, used if the declarations are not kept yet
+    pub fn without_shadowing(self, globals: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            declared: self
+                .declared
+                .or_else(|| Some(vec![globals.into_iter().collect()])),
+            shadowing: false,
+            ..self
+        }
+    }
+
+    //parse the tokens into a block of statements
+    pub fn parse(&mut self) -> Result<Block, StmtErrors> {
+        let block = self.make_stmts();
+        //check if errors occured
+        if !self.errors.is_empty() {
+            Err(StmtErrors {
+                errors: std::mem::take(&mut self.errors),
+            })
+        } else {
+            Ok(block)
+        }
     }
 
-    //parse the tokens into an expression
-    //can take in global scope variables
-    pub fn parse(&mut self, global: Option<&'a mut Block<'a>>) -> Result<Block<'a>, StmtErrors> {
+    //parse statements until the end of the file, or the end of the current block
+    //errors are saved and the invalid statements skipped, so all errors are found in one pass
+    fn make_stmts(&mut self) -> Block {
         let mut stmts = Vec::new();
-        let mut errs: Vec<StmtError> = Vec::new();
-        while self.get_current_token().class != TokenType::Eof {
-            //find the stmtend token and save all tokens before it
-            let mut stmt_tokens = Vec::new();
-            while self.get_current_token().class != TokenType::StmtEnd {
-                stmt_tokens.push(self.get_current_token().to_owned());
-                self.consume();
-                if self.get_current_token().class == TokenType::Eof {
+        let mut spans = Vec::new();
+        while !self.cursor.check(&TokenType::Eof) {
+            //skip empty statements
+            if self.cursor.consume_if(&TokenType::StmtEnd).is_some() {
+                continue;
+            }
+            if self.cursor.check(&TokenType::Rbrace) {
+                if self.depth > 0 {
                     break;
                 }
-            }
-            if stmt_tokens.is_empty() {
-                self.consume();
+                //a closing brace without a block to close
+                self.errors
+                    .push(StmtError::InvalidStartToken(self.cursor.advance().clone()));
                 continue;
             }
-            let stmt = self.make_statement(stmt_tokens);
-            match stmt {
-                Ok(stmt) => stmts.push(stmt),
+            let span = self.next_span();
+            match self.make_statement() {
+                Ok(stmt) => {
+                    stmts.push(stmt);
+                    spans.push(span);
+                }
                 Err(err) => {
-                    errs.push(err);
-                    self.consume();
+                    self.errors.push(err);
+                    //skip what is left of the invalid statement
+                    self.cursor.synchronize();
                 }
             }
         }
-        //check if errors occured
-        if !errs.is_empty() {
-            Err(StmtErrors { errors: errs })
+        Block::new(stmts, spans)
+    }
+
+    //give the statement starting at the cursor its span
+    fn next_span(&mut self) -> Span {
+        let first = self.cursor.peek();
+        self.next_id += 1;
+        Span {
+            id: self.next_id - 1,
+            line: first.line,
+            start: first.start,
+        }
+    }
+
+    //function to create a stmt from the tokens at the cursor
+    //the cursor is left at the token ending the statement
+    fn make_statement(&mut self) -> Result<Stmt, StmtError> {
+        let token = self.cursor.peek();
+        match &token.class {
+            TokenType::Keyword(Keyword::Let) => self.make_let_stmt(),
+            TokenType::Keyword(Keyword::Const) => self.make_const_stmt(),
+            TokenType::Keyword(Keyword::Print) => self.make_print_stmt(),
+            TokenType::Keyword(Keyword::Fn) => self.make_fn_decl(),
+            TokenType::Keyword(Keyword::Struct) => self.make_struct_decl(),
+            TokenType::Keyword(Keyword::Return) => self.make_return_stmt(),
+            TokenType::Keyword(Keyword::Exit) => self.make_exit_stmt(),
+            TokenType::Keyword(Keyword::Assert) => self.make_assert_stmt(),
+            TokenType::Keyword(Keyword::If) => self.make_if_stmt(),
+            TokenType::Keyword(Keyword::While) => self.make_while_stmt(),
+            TokenType::Keyword(Keyword::Do) => self.make_do_while_stmt(),
+            TokenType::Keyword(Keyword::For) => self.make_for_stmt(),
+            TokenType::Keyword(Keyword::Try) => self.make_try_stmt(),
+            TokenType::Keyword(Keyword::Throw) => self.make_throw_stmt(),
+            TokenType::Keyword(Keyword::Break | Keyword::Continue) => self.make_loop_control(),
+            TokenType::Ident(_) => self.make_ident_stmt(),
+            TokenType::Literal(_)
+            | TokenType::Interpolation(_)
+            | TokenType::Lparen
+            | TokenType::Unary(_) => self.make_expr_stmt(),
+            TokenType::Lbrace => Ok(Stmt::Block(self.make_block()?)),
+            _ => Err(StmtError::InvalidStartToken(token.clone())),
+        }
+    }
+
+    //parse the statements between braces
+    fn make_block(&mut self) -> Result<Block, StmtError> {
+        let lbrace = self.cursor.advance();
+        //blocks are parsed and run recursively, so the nesting is limited to keep the stack from overflowing
+        if self.depth >= MAX_BLOCK_DEPTH {
+            self.skip_block();
+            return Err(StmtError::TooDeep(lbrace.clone()));
+        }
+        self.depth += 1;
+        let block = self.in_scope(Self::make_stmts);
+        self.depth -= 1;
+        match self.cursor.expect(&TokenType::Rbrace) {
+            Ok(_) => Ok(block),
+            Err(_) => Err(StmtError::UnterminatedBlock(lbrace.clone())),
+        }
+    }
+
+    //skip the rest of a block whose opening brace was consumed, including its closing brace
+    fn skip_block(&mut self) {
+        let mut open = 1;
+        while open > 0 {
+            match self.cursor.advance().class {
+                TokenType::Lbrace => open += 1,
+                TokenType::Rbrace => open -= 1,
+                TokenType::Eof => break,
+                _ => {}
+            }
+        }
+    }
+
+    //skip the rest of an invalid statement header and the block following it
+    //so the statements of the block are not parsed as being outside of it
+    fn skip_header(&mut self) {
+        while !self.cursor.at_stmt_end() {
+            if self.cursor.advance().class == TokenType::Lbrace {
+                self.skip_block();
+                break;
+            }
+        }
+    }
+
+    //check that the cursor is at the opening brace of a block
+    fn expect_block(&self) -> Result<(), StmtError> {
+        if self.cursor.check(&TokenType::Lbrace) {
+            Ok(())
         } else {
-            Ok(Block::new(stmts, global))
+            Err(StmtError::ExpectToken(
+                TokenType::Lbrace,
+                self.cursor.peek().clone(),
+            ))
         }
     }
 
-    //function to create a stmt from a vector of tokens
-    fn make_statement(&mut self, mut stmt_tokens: Vec<Token>) -> Result<Stmt, StmtError> {
-        match &stmt_tokens[0].class {
-            TokenType::Keyword(Keyword::Let) => self.make_let_stmt(stmt_tokens),
-            TokenType::Keyword(Keyword::Print) => self.make_print_stmt(stmt_tokens),
-            TokenType::Ident(_) => self.make_ident_stmt(stmt_tokens),
-            TokenType::Literal(_) | TokenType::Lparen | TokenType::Unary(_) => {
-                self.make_expr_stmt(stmt_tokens)
+    //fn name(a, b) { ... }
+    fn make_fn_decl(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        let header = self
+            .expect_ident()
+            .and_then(|name| self.make_params().map(|params| (name, params)));
+        let (name, params) = header.inspect_err(|_| self.skip_header())?;
+        let body = self.make_fn_body(&params, false)?;
+        Ok(Stmt::FnDecl(Rc::new(Function { name, params, body })))
+    }
+
+    //struct Name { a, b }, the fields are separated by commas and can be on lines of their own
+    fn make_struct_decl(&mut self) -> Result<Stmt, StmtError> {
+        let keyword = self.cursor.advance();
+        let name = self
+            .expect_ident()
+            .and_then(|name| self.expect_block().map(|_| name))
+            .inspect_err(|_| self.skip_header())?;
+        self.cursor.advance();
+        let fields = self.make_fields().inspect_err(|_| self.skip_block())?;
+        self.check_feature(keyword)?;
+        Ok(Stmt::Struct(Rc::new(Struct { name, fields })))
+    }
+
+    //refuse a statement of an experimental feature which is not turned on
+    //it is checked after the statement is parsed, so parsing carries on after it
+    fn check_feature(&self, keyword: &Token) -> Result<(), StmtError> {
+        let TokenType::Keyword(class) = &keyword.class else {
+            return Ok(());
+        };
+        match Feature::of_keyword(class) {
+            Some(feature) if !self.features.contains(feature) => {
+                Err(StmtError::ExperimentalFeature(keyword.clone(), feature))
             }
-            //use swap remove since we dont care about the vector anymore
-            _ => Err(StmtError::InvalidStartToken(stmt_tokens.swap_remove(0))),
+            _ => Ok(()),
         }
     }
 
-    fn make_let_stmt(&mut self, mut tokens: Vec<Token>) -> Result<Stmt, StmtError> {
-        let ident;
-        if tokens.len() < 3 {
-            return Err(StmtError::IncompleteStatement(tokens.swap_remove(0)));
+    //parse the fields of a struct, from after its opening brace to after its closing brace
+    fn make_fields(&mut self) -> Result<Vec<String>, StmtError> {
+        let mut fields: Vec<String> = Vec::new();
+        loop {
+            self.skip_newlines();
+            if self.cursor.consume_if(&TokenType::Rbrace).is_some() {
+                break;
+            }
+            let token = self.cursor.peek();
+            let field = self.expect_ident()?;
+            if fields.contains(&field) {
+                return Err(StmtError::DuplicateField(token.clone()));
+            }
+            fields.push(field);
+            self.skip_newlines();
+            if self.cursor.consume_if(&TokenType::Comma).is_none() {
+                self.expect(TokenType::Rbrace)?;
+                break;
+            }
         }
-        //check for identifier after the let keyword
-        match &tokens[1].class {
-            TokenType::Ident(name) => {
-                ident = name.to_owned();
+        Ok(fields)
+    }
+
+    //fn(a, b) { ... }, a function value, the cursor is after the fn keyword
+    fn make_fn_expr(&mut self) -> Result<Expr, StmtError> {
+        let params = self.make_params().inspect_err(|_| self.skip_header())?;
+        let body = self.make_fn_body(&params, true)?;
+        Ok(Expr::Function(Rc::new(Function {
+            name: String::new(),
+            params,
+            body,
+        })))
+    }
+
+    //parse the parameters of a function, leaving the cursor at the opening brace of its body
+    fn make_params(&mut self) -> Result<Vec<String>, StmtError> {
+        self.expect(TokenType::Lparen)?;
+        let mut params: Vec<String> = Vec::new();
+        //parameters are identifiers separated by commas, a trailing comma is allowed
+        while self.cursor.consume_if(&TokenType::Rparen).is_none() {
+            let token = self.cursor.peek();
+            let param = self.expect_ident()?;
+            if params.contains(&param) {
+                return Err(StmtError::DuplicateParameter(token.clone()));
             }
-            _ => {
-                return Err(StmtError::ExpectToken(
-                    TokenType::Ident(String::new()),
-                    tokens.swap_remove(1),
-                ))
+            params.push(param);
+            if self.cursor.consume_if(&TokenType::Comma).is_none() {
+                self.expect(TokenType::Rparen)?;
+                break;
             }
+        }
+        self.expect_block()?;
+        Ok(params)
+    }
+
+    //parse the body of a function, the loops around the function can't be left from inside it
+    //captures: whether the body sees the local variables around it, as a function value does
+    //a declared function only sees the global variables and its parameters
+    fn make_fn_body(&mut self, params: &[String], captures: bool) -> Result<Block, StmtError> {
+        let loops = std::mem::take(&mut self.loops);
+        let outer = self.declared.as_mut().map(|declared| {
+            let mut scopes = if captures {
+                declared.clone()
+            } else {
+                vec![declared[0].clone()]
+            };
+            scopes.push(params.iter().cloned().collect());
+            std::mem::replace(declared, scopes)
+        });
+        self.functions += 1;
+        //a function value is part of an expression, but its statements end where statements do
+        let body = self.with_ends_at(Vec::new(), Self::make_block);
+        self.functions -= 1;
+        self.loops = loops;
+        if let Some(outer) = outer {
+            self.declared = Some(outer);
+        }
+        body
+    }
+
+    //if condition { ... } else if condition { ... } else { ... }
+    fn make_if_stmt(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        let condition = self.make_condition()?;
+        let then = self.make_block()?;
+        if self
+            .cursor
+            .consume_if(&TokenType::Keyword(Keyword::Else))
+            .is_none()
+        {
+            return Ok(Stmt::If(condition, then, None));
+        }
+        let otherwise = if self.cursor.check(&TokenType::Keyword(Keyword::If)) {
+            let span = self.next_span();
+            Block::new(vec![self.make_if_stmt()?], vec![span])
+        } else {
+            self.expect_block()?;
+            self.make_block()?
         };
-        //check for assign token after the identifier
-        match &tokens[2].class {
-            TokenType::Assign => {}
-            _ => {
-                return Err(StmtError::ExpectToken(
-                    TokenType::Assign,
-                    tokens.swap_remove(2),
-                ))
+        Ok(Stmt::If(condition, then, Some(otherwise)))
+    }
+
+    //while condition { ... }
+    fn make_while_stmt(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        let condition = self.make_condition()?;
+        self.loops += 1;
+        let body = self.make_block();
+        self.loops -= 1;
+        Ok(Stmt::While(condition, body?))
+    }
+
+    //do { ... } while condition
+    //the while follows the closing brace on the same line, like an else
+    fn make_do_while_stmt(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        self.expect_block().inspect_err(|_| self.skip_header())?;
+        self.loops += 1;
+        let body = self.make_block();
+        self.loops -= 1;
+        let body = body?;
+        self.expect(TokenType::Keyword(Keyword::While))?;
+        let condition = self.make_expr();
+        Ok(Stmt::DoWhile(self.check_expression(condition)?, body))
+    }
+
+    //try { ... } catch (e) { ... }
+    //the catch follows the closing brace on the same line, like an else
+    fn make_try_stmt(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        self.expect_block()?;
+        let body = self.make_block()?;
+        self.expect(TokenType::Keyword(Keyword::Catch))?;
+        let header = self.expect(TokenType::Lparen).and_then(|_| {
+            let name = self.expect_ident()?;
+            self.expect(TokenType::Rparen)?;
+            self.expect_block()?;
+            Ok(name)
+        });
+        let name = header.inspect_err(|_| self.skip_header())?;
+        //the error is declared in a scope around the catch block
+        let catch = self.in_scope(|parser| {
+            parser.declare(&name);
+            parser.make_block()
+        })?;
+        Ok(Stmt::Try(body, name, catch))
+    }
+
+    //throw value, the value is needed
+    fn make_throw_stmt(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        let expr = self.make_expr();
+        Ok(Stmt::Throw(self.check_expression(expr)?))
+    }
+
+    //for (init; condition; step) { ... }
+    //each part can be left empty, a loop without a condition runs until it is left with break or return
+    fn make_for_stmt(&mut self) -> Result<Stmt, StmtError> {
+        //a variable declared by the init is only seen by the loop
+        self.in_scope(Self::make_for_loop)
+    }
+
+    fn make_for_loop(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        self.expect(TokenType::Lparen)
+            .inspect_err(|_| self.skip_header())?;
+        let header = self.make_for_header().inspect_err(|_| {
+            //the header has statement ends of its own, so skip to its closing parenthesis first
+            let mut open = 1;
+            while open > 0 && !self.cursor.check(&TokenType::Eof) {
+                match self.cursor.advance().class {
+                    TokenType::Lparen => open += 1,
+                    TokenType::Rparen => open -= 1,
+                    _ => {}
+                }
             }
+            self.skip_header();
+        })?;
+        self.expect(TokenType::Rparen)
+            .and_then(|_| self.expect_block())
+            .inspect_err(|_| self.skip_header())?;
+        let (init, condition, step) = header;
+        self.loops += 1;
+        let body = self.make_block();
+        self.loops -= 1;
+        Ok(Stmt::For(init, condition, step, body?))
+    }
+
+    //parse the init, condition and step of a for loop, leaving the cursor at the closing parenthesis
+    fn make_for_header(&mut self) -> Result<ForHeader, StmtError> {
+        //the init declares or changes the loop variable
+        let init = match &self.cursor.peek().class {
+            TokenType::StmtEnd => None,
+            TokenType::Keyword(Keyword::Let) => Some(Box::new(self.make_let_stmt()?)),
+            TokenType::Ident(_) => Some(Box::new(self.make_ident_stmt()?)),
+            _ => return Err(StmtError::InvalidStartToken(self.cursor.peek().clone())),
         };
+        self.expect(TokenType::StmtEnd)?;
+        let condition = self.make_expr().map_err(StmtError::InvalidExpression)?;
+        self.expect(TokenType::StmtEnd)?;
+        let step = self.with_ends_at(vec![TokenType::Rparen], |parser| {
+            match &parser.cursor.peek().class {
+                TokenType::Rparen => Ok(None),
+                TokenType::Ident(_) => parser.make_ident_stmt().map(|stmt| Some(Box::new(stmt))),
+                _ => Err(StmtError::InvalidStartToken(parser.cursor.peek().clone())),
+            }
+        });
+        Ok((init, condition, step?))
+    }
 
-        let expr = self.make_expr(tokens[3..].to_vec());
-        Ok(Stmt::Assign(ident, self.check_expression(expr)?))
+    //parse the condition of an if or while, leaving the cursor at the opening brace of its block
+    //a map literal in the condition needs parenthesis, since its brace would start the block
+    fn make_condition(&mut self) -> Result<Expr, StmtError> {
+        let expr = self.with_ends_at(vec![TokenType::Lbrace], Self::make_expr);
+        self.check_expression(expr)
+            .and_then(|expr| self.expect_block().map(|_| expr))
+            .inspect_err(|_| self.skip_header())
     }
 
-    fn make_print_stmt(&mut self, tokens: Vec<Token>) -> Result<Stmt, StmtError> {
-        let expr = self.make_expr(tokens[1..].to_vec());
-        Ok(Stmt::Print(self.check_expression(expr)?))
+    //break or continue
+    fn make_loop_control(&mut self) -> Result<Stmt, StmtError> {
+        let token = self.cursor.advance();
+        if self.loops == 0 {
+            return Err(StmtError::OutsideLoop(token.clone()));
+        }
+        if !self.cursor.at_stmt_end() {
+            return Err(StmtError::ExpectToken(
+                TokenType::StmtEnd,
+                self.cursor.peek().clone(),
+            ));
+        }
+        match token.class {
+            TokenType::Keyword(Keyword::Break) => Ok(Stmt::Break),
+            _ => Ok(Stmt::Continue),
+        }
+    }
+
+    fn make_return_stmt(&mut self) -> Result<Stmt, StmtError> {
+        let token = self.cursor.advance();
+        if self.functions == 0 {
+            return Err(StmtError::ReturnOutsideFunction(token.clone()));
+        }
+        if self.cursor.at_stmt_end() {
+            return Ok(Stmt::Return(None));
+        }
+        let expr = self.make_expr();
+        Ok(Stmt::Return(Some(self.check_expression(expr)?)))
     }
 
-    fn make_ident_stmt(&mut self, mut tokens: Vec<Token>) -> Result<Stmt, StmtError> {
-        //check the length of the vector, if only one its an expression statement
+    //exit can be used anywhere, eg- exit or exit(1)
+    fn make_exit_stmt(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        if self.cursor.at_stmt_end() {
+            return Ok(Stmt::Exit(None));
+        }
+        let expr = self.make_expr();
+        Ok(Stmt::Exit(Some(self.check_expression(expr)?)))
+    }
 
-        if tokens.len() == 1 {
-            let expr = self.make_expr(tokens);
-            return Ok(Stmt::Expr(self.check_expression(expr)?));
+    //assert condition, or assert condition, message
+    fn make_assert_stmt(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        let condition = self.with_ends_at(vec![TokenType::Comma], Self::make_expr);
+        let condition = self.check_expression(condition)?;
+        if self.cursor.consume_if(&TokenType::Comma).is_none() {
+            return Ok(Stmt::Assert(condition, None));
         }
+        let message = self.make_expr();
+        Ok(Stmt::Assert(
+            condition,
+            Some(self.check_expression(message)?),
+        ))
+    }
 
-        //check for assignment operator after the identifier
-        //if there is no assignment operator, return an expression statement
-        if let TokenType::Assign = &tokens[1].class {
-            let expr = self.make_expr(tokens[2..].to_vec());
-            Ok(Stmt::Reassign(
-                match tokens.swap_remove(0).class {
-                    TokenType::Ident(name) => name,
-                    _ => panic!(),
-                },
-                self.check_expression(expr)?,
-            ))
+    fn make_let_stmt(&mut self) -> Result<Stmt, StmtError> {
+        let let_token = self.cursor.advance();
+        let mut idents = Vec::new();
+        //the identifiers can be written like a tuple, eg- let (a, b) = pair
+        let parenthesized = self.cursor.consume_if(&TokenType::Lparen).is_some();
+        let last = if parenthesized {
+            TokenType::Rparen
+        } else {
+            TokenType::Assign
+        };
+        //check for identifiers after the let keyword, separated by commas
+        //stops after the token following the last identifier
+        loop {
+            if self.cursor.at_stmt_end() {
+                return Err(StmtError::IncompleteStatement(let_token.clone()));
+            }
+            self.check_shadowing(self.cursor.peek())?;
+            idents.push(self.expect_ident()?);
+            if self.cursor.at_stmt_end() {
+                return Err(StmtError::IncompleteStatement(let_token.clone()));
+            }
+            //check for a comma or the token ending the identifiers
+            if self.cursor.consume_if(&TokenType::Comma).is_none() {
+                self.expect(last)?;
+                break;
+            }
+        }
+        if parenthesized {
+            if self.cursor.at_stmt_end() {
+                return Err(StmtError::IncompleteStatement(let_token.clone()));
+            }
+            self.expect(TokenType::Assign)?;
+        }
+
+        let expr = self.make_expr();
+        let expr = self.check_expression(expr)?;
+        idents.iter().for_each(|name| self.declare(name));
+        //more than one identifier, or any in parentheses, destructures a tuple
+        if idents.len() == 1 && !parenthesized {
+            Ok(Stmt::Assign(idents.swap_remove(0), expr))
         } else {
-            let expr = self.make_expr(tokens);
-            Ok(Stmt::Expr(self.check_expression(expr)?))
+            Ok(Stmt::Destructure(idents, expr))
+        }
+    }
+
+    //const name = value
+    fn make_const_stmt(&mut self) -> Result<Stmt, StmtError> {
+        let const_token = self.cursor.advance();
+        if self.cursor.at_stmt_end() {
+            return Err(StmtError::IncompleteStatement(const_token.clone()));
+        }
+        self.check_shadowing(self.cursor.peek())?;
+        let name = self.expect_ident()?;
+        if self.cursor.at_stmt_end() {
+            return Err(StmtError::IncompleteStatement(const_token.clone()));
+        }
+        self.expect(TokenType::Assign)?;
+        let expr = self.make_expr();
+        let expr = self.check_expression(expr)?;
+        self.declare(&name);
+        Ok(Stmt::Const(name, expr))
+    }
+
+    fn make_print_stmt(&mut self) -> Result<Stmt, StmtError> {
+        self.cursor.advance();
+        let expr = self.make_expr();
+        Ok(Stmt::Print(self.check_expression(expr)?))
+    }
+
+    fn make_ident_stmt(&mut self) -> Result<Stmt, StmtError> {
+        //an identifier followed by the assignment operator reassigns a variable
+        if self.cursor.peek_next().class == TokenType::Assign {
+            self.check_declared(self.cursor.peek())
+                .map_err(StmtError::InvalidExpression)?;
+            let name = self.expect_ident()?;
+            self.expect(TokenType::Assign)?;
+            let expr = self.make_expr();
+            return Ok(Stmt::Reassign(name, self.check_expression(expr)?));
+        }
+        //a compound assignment is a reassignment using the variable's value, eg- a += 1 is a = a + (1)
+        if let TokenType::CompoundAssign(operator) = &self.cursor.peek_next().class {
+            self.check_declared(self.cursor.peek())
+                .map_err(StmtError::InvalidExpression)?;
+            let name = self.expect_ident()?;
+            let assign = self.cursor.peek();
+            self.cursor.advance();
+            let expr = self.make_expr();
+            let expr = Expr::new_binary_op(
+                Expr::new_ident(&name),
+                self.check_expression(expr)?,
+                operator,
+            );
+            return Ok(Stmt::Reassign(name, expr.with_position(assign)));
+        }
+        //i++ and i-- are reassignments adding or subtracting one
+        if let TokenType::Step(operator) = &self.cursor.peek_next().class {
+            self.check_declared(self.cursor.peek())
+                .map_err(StmtError::InvalidExpression)?;
+            let name = self.expect_ident()?;
+            let step = self.cursor.peek();
+            self.cursor.advance();
+            let expr =
+                Expr::new_binary_op(Expr::new_ident(&name), Expr::new_num_literal(1), operator);
+            return Ok(Stmt::Reassign(name, expr.with_position(step)));
         }
+        //anything else is an expression statement, unless an assignment follows the expression
+        //eg- m["a"] = 1 sets an item of the map held by m
+        let mut ends_at = self.ends_at.clone();
+        ends_at.push(TokenType::Assign);
+        let target = self.with_ends_at(ends_at, Self::make_expr);
+        let target = self.check_expression(target)?;
+        let Some(assign) = self.cursor.consume_if(&TokenType::Assign) else {
+            return Ok(Stmt::Expr(target));
+        };
+        let (name, accessors) =
+            index_target(target).ok_or_else(|| StmtError::InvalidAssignTarget(assign.clone()))?;
+        let expr = self.make_expr();
+        Ok(Stmt::IndexAssign(
+            name,
+            accessors,
+            self.check_expression(expr)?,
+        ))
     }
 
-    fn make_expr_stmt(&mut self, tokens: Vec<Token>) -> Result<Stmt, StmtError> {
-        let expr = self.make_expr(tokens);
+    fn make_expr_stmt(&mut self) -> Result<Stmt, StmtError> {
+        let expr = self.make_expr();
         Ok(Stmt::Expr(self.check_expression(expr)?))
     }
 
+    //Consume a token of the expected class
+    //otherwise return an "expected X, got Y" error at the token found instead
+    fn expect(&mut self, class: TokenType) -> Result<&'a Token, StmtError> {
+        self.cursor
+            .expect(&class)
+            .map_err(|got| StmtError::ExpectToken(class, got.clone()))
+    }
+
+    //Consume an identifier and return its name
+    fn expect_ident(&mut self) -> Result<String, StmtError> {
+        match &self.expect(TokenType::Ident(String::new()))?.class {
+            TokenType::Ident(name) => Ok(name.to_owned()),
+            _ => unreachable!(),
+        }
+    }
+
     //Create an expression tree using shunting yard algorithm
-    fn make_expr(&mut self, mut tokens: Vec<Token>) -> Result<Option<Expr>, ExprError> {
-        let mut operands: Vec<Expr> = Vec::new();
+    //reads tokens until the end of the statement, leaving the cursor at the token ending it
+    fn make_expr(&mut self) -> Result<Option<Expr>, ExprError> {
+        Ok(self.make_expr_tree()?.map(|(expr, _)| expr))
+    }
+
+    //make_expr, also returning the depth of the expression tree
+    fn make_expr_tree(&mut self) -> Result<Option<(Expr, usize)>, ExprError> {
+        let mut operands = Operands::new();
         let mut operators: Vec<Token> = Vec::new();
         //Holds the currently expected token, eg- expecting an operator after operand
         let mut expect = ExpectType::Operand;
-        tokens.reverse();
+        //Number of commas seen inside each open parenthesis
+        //a parenthesis with commas makes a tuple, or separates arguments in a call
+        let mut paren_commas: Vec<usize> = Vec::new();
+        //The class of the previous token, allows a trailing comma or an empty call before a closing parenthesis
+        let mut previous: Option<TokenType> = None;
 
-        //check for empty list of tokens
-        if tokens.is_empty() {
+        //check for an empty expression
+        if self.at_expr_end() {
             return Ok(None);
         }
 
-        while let Some(token) = tokens.pop() {
+        //the token ending the expression early is only checked outside of parenthesis
+        while !(self.cursor.at_stmt_end() || (paren_commas.is_empty() && self.at_expr_end())) {
+            let token = self.cursor.advance().clone();
+            let follows = previous.replace(token.class.clone());
+            let follows_comma = follows == Some(TokenType::Comma);
             match &token.class {
                 TokenType::Literal(lit) => {
                     if expect == ExpectType::Operator {
@@ -152,40 +716,72 @@ impl<'a> Parser<'a> {
                     operands.push(Expr::new_literal(lit));
                     expect = ExpectType::Operator;
                 }
-                TokenType::Ident(name) => {
+                TokenType::Interpolation(parts) => {
+                    if expect == ExpectType::Operator {
+                        return Err(ExprError::ExpectTokenError(expect, token));
+                    }
+                    let (expr, depth) = self.make_interpolation(parts, &token)?;
+                    operands.push_tree(expr, depth, &token)?;
+                    expect = ExpectType::Operator;
+                }
+                TokenType::Keyword(Keyword::Fn) => {
                     if expect == ExpectType::Operator {
                         return Err(ExprError::ExpectTokenError(expect, token));
                     }
-                    operands.push(Expr::new_ident(name));
+                    let function = self
+                        .make_fn_expr()
+                        .map_err(|err| ExprError::InvalidFunction(Box::new(err)))?;
+                    operands.push(function);
                     expect = ExpectType::Operator;
                 }
+                TokenType::Ident(name) => {
+                    if expect == ExpectType::Operator {
+                        //an assigned variable after an operand starts a statement missing the ; before it
+                        if matches!(
+                            self.cursor.peek().class,
+                            TokenType::Assign | TokenType::CompoundAssign(_) | TokenType::Step(_)
+                        ) {
+                            return Err(ExprError::MissingStmtEnd(token));
+                        }
+                        return Err(ExprError::ExpectTokenError(expect, token));
+                    }
+                    //an identifier followed by a parenthesis is a function call
+                    //the name waits on the operator stack until the call's parenthesis is closed
+                    if self.cursor.check(&TokenType::Lparen) {
+                        operators.push(token);
+                    } else if self.at_record() {
+                        let lbrace = self.cursor.advance();
+                        let (record, depth) = self.make_record(name, lbrace)?;
+                        operands.push_tree(record, depth, &token)?;
+                        expect = ExpectType::Operator;
+                    } else {
+                        self.check_declared(&token)?;
+                        operands.push(Expr::new_ident(name));
+                        expect = ExpectType::Operator;
+                    }
+                }
                 TokenType::Operator(op) => {
                     if expect == ExpectType::Operand {
                         return Err(ExprError::ExpectTokenError(expect, token));
                     }
-                    match operators.last().map(|t| &t.class) {
-                        Some(TokenType::Operator(top)) => {
-                            if top.precedence() >= op.precedence() {
-                                let right = operands.pop().unwrap();
-                                let expr = Expr::new_binary_op(operands.pop().unwrap(), right, top);
-                                operands.push(expr);
-                                operators.pop();
-                                operators.push(token);
-                            } else {
-                                operators.push(token);
+                    //reduce every operator binding tighter, and those binding as tight unless op groups from the right
+                    //unary operators bind tighter than everything but **, so -2 ** 2 is -(2 ** 2)
+                    while let Some(top) = operators.last() {
+                        let reduce = match &top.class {
+                            TokenType::Operator(top) => {
+                                top.precedence() > op.precedence()
+                                    || (top.precedence() == op.precedence()
+                                        && !op.is_right_associative())
                             }
+                            TokenType::Unary(_) => *op != Operator::Pow,
+                            _ => false,
+                        };
+                        if !reduce {
+                            break;
                         }
-                        Some(TokenType::Unary(top)) => {
-                            let right = operands.pop().unwrap();
-                            let expr = Expr::new_unary_op(right, top);
-                            operands.push(expr);
-                            operators.pop();
-                            operators.push(token);
-                        }
-                        _ => {
-                            operators.push(token);
-                        }
+                        operands.reduce_operator(&operators.pop().unwrap())?;
                     }
+                    operators.push(token);
                     expect = ExpectType::Operand;
                 }
                 TokenType::Unary(_) => {
@@ -200,26 +796,156 @@ impl<'a> Parser<'a> {
                         return Err(ExprError::ExpectTokenError(expect, token));
                     }
                     operators.push(token);
+                    paren_commas.push(0);
                 }
                 TokenType::Rparen => {
-                    //Expect Rparen after an operand
+                    //Expect Rparen after an operand, after the trailing comma of a tuple, or to end an empty call
+                    let empty = follows == Some(TokenType::Lparen);
+                    if expect == ExpectType::Operand && !follows_comma && !empty {
+                        return Err(ExprError::ExpectTokenError(expect, token));
+                    }
+                    Self::reduce_parenthesis(&mut operands, &mut operators)?;
+                    operators.pop();
+                    let commas = paren_commas.pop().unwrap_or(0);
+                    //a call takes the operands inside its parenthesis as arguments
+                    if let Some(TokenType::Ident(name)) = operators.last().map(|t| &t.class) {
+                        let len = if empty || follows_comma {
+                            commas
+                        } else {
+                            commas + 1
+                        };
+                        operands.reduce(len, &token, |args| Expr::new_call(name, args))?;
+                        operators.pop();
+                        expect = ExpectType::Operator;
+                        continue;
+                    }
+                    //an empty parenthesis is only valid for a call
+                    if empty {
+                        return Err(ExprError::ExpectTokenError(expect, token));
+                    }
+                    //collect the items into a tuple if the parenthesis had commas
+                    if commas > 0 {
+                        let len = if follows_comma { commas } else { commas + 1 };
+                        operands.reduce(len, &token, Expr::Tuple)?;
+                    } else {
+                        operands.end_chain();
+                    }
+                    expect = ExpectType::Operator;
+                }
+                TokenType::Comma => {
+                    //commas can only separate operands inside a parenthesis
                     if expect == ExpectType::Operand {
                         return Err(ExprError::ExpectTokenError(expect, token));
                     }
-                    while let Some(top) = operators.last() {
-                        if let TokenType::Lparen = top.class {
-                            operators.pop();
-                            break;
+                    match paren_commas.last_mut() {
+                        Some(commas) => *commas += 1,
+                        None => return Err(ExprError::ExpectTokenError(expect, token)),
+                    }
+                    Self::reduce_parenthesis(&mut operands, &mut operators)?;
+                    expect = ExpectType::Operand;
+                }
+                TokenType::Keyword(keyword @ (Keyword::Is | Keyword::As)) => {
+                    //type checks and conversions apply to the operand right before them
+                    if expect == ExpectType::Operand {
+                        return Err(ExprError::ExpectTokenError(expect, token));
+                    }
+                    //unary operators bind tighter, so -x as string converts -x
+                    while let Some(TokenType::Unary(_)) = operators.last().map(|t| &t.class) {
+                        operands.reduce_operator(&operators.pop().unwrap())?;
+                    }
+                    let type_token = self.next_expr_token();
+                    let value_type = match &type_token.class {
+                        TokenType::Ident(name) => ValueType::new_value_type(name),
+                        //none is lexed as a value, but also names its own type
+                        TokenType::Literal(Literal::None) => Some(ValueType::None),
+                        _ => None,
+                    };
+                    let value_type = match value_type {
+                        Some(value_type) => value_type,
+                        None => return Err(ExprError::ExpectTypeName(type_token)),
+                    };
+                    operands.reduce(1, &token, |mut exprs| {
+                        let expr = exprs.pop().unwrap();
+                        if *keyword == Keyword::Is {
+                            Expr::new_is(expr, value_type)
                         } else {
-                            let right = operands.pop().unwrap();
-                            if let TokenType::Operator(opr) = &top.class {
-                                let expr = Expr::new_binary_op(operands.pop().unwrap(), right, opr);
-                                operands.push(expr);
-                                operators.pop();
+                            Expr::new_cast(expr, value_type)
+                        }
+                    })?;
+                }
+                TokenType::Dot => {
+                    //field and index access apply to the operand right before the dot
+                    if expect == ExpectType::Operand {
+                        return Err(ExprError::ExpectTokenError(expect, token));
+                    }
+                    match self.next_expr_token() {
+                        Token {
+                            class: TokenType::Ident(name),
+                            ..
+                        } => {
+                            operands.reduce(1, &token, |mut exprs| {
+                                Expr::new_field(exprs.pop().unwrap(), &name)
+                            })?;
+                        }
+                        Token {
+                            class: TokenType::Literal(Literal::Number(index)),
+                            ..
+                        } => {
+                            operands.reduce(1, &token, |mut exprs| {
+                                Expr::new_tuple_index(exprs.pop().unwrap(), index as usize)
+                            })?;
+                        }
+                        token => return Err(ExprError::InvalidTupleIndex(token)),
+                    }
+                }
+                TokenType::Lbracket => {
+                    //an index or slice applies to the operand right before the bracket, like a tuple index
+                    if expect == ExpectType::Operand {
+                        return Err(ExprError::ExpectTokenError(expect, token));
+                    }
+                    match self.make_subscript(&token)? {
+                        Subscript::Index(index, depth) => {
+                            operands.push_tree(index, depth, &token)?;
+                            operands.reduce(2, &token, |mut exprs| {
+                                let index = exprs.pop().unwrap();
+                                Expr::new_index(exprs.pop().unwrap(), index)
+                            })?;
+                        }
+                        Subscript::Slice(start, end) => {
+                            let (has_start, has_end) = (start.is_some(), end.is_some());
+                            let bounds = [start, end].into_iter().flatten();
+                            let count = bounds.clone().count();
+                            for (bound, depth) in bounds {
+                                operands.push_tree(bound, depth, &token)?;
                             }
+                            operands.reduce(count + 1, &token, |mut exprs| {
+                                let end = if has_end { exprs.pop() } else { None };
+                                let start = if has_start { exprs.pop() } else { None };
+                                Expr::new_slice(exprs.pop().unwrap(), start, end)
+                            })?;
                         }
                     }
                 }
+                TokenType::Lbrace => {
+                    //a brace in place of an operand starts a map literal
+                    if expect == ExpectType::Operator {
+                        return Err(ExprError::ExpectTokenError(expect, token));
+                    }
+                    let (map, depth) = self.make_map(&token)?;
+                    operands.push_tree(map, depth, &token)?;
+                    expect = ExpectType::Operator;
+                }
+                //a statement can't follow an operand on the same line, eg- print a print b
+                TokenType::Keyword(keyword)
+                    if expect == ExpectType::Operator
+                        && !matches!(keyword, Keyword::Else | Keyword::Catch) =>
+                {
+                    return Err(ExprError::MissingStmtEnd(token))
+                }
+                TokenType::Assign if expect == ExpectType::Operator => {
+                    return Err(ExprError::AssignInExpression(token))
+                }
+                TokenType::Step(_) => return Err(ExprError::InvalidStep(token)),
                 _ => return Err(ExprError::ExpectTokenError(ExpectType::Operand, token)),
             }
         }
@@ -228,32 +954,285 @@ impl<'a> Parser<'a> {
         if expect == ExpectType::Operand {
             return Err(ExprError::ExpectTokenError(
                 expect,
-                self.get_current_token().clone(),
+                self.cursor.peek().clone(),
             ));
         }
 
         //Pop the remaining operators
-        while let Some(top) = operators.last() {
-            match &top.class {
-                TokenType::Lparen => {
-                    return Err(ExprError::UnterminatedParenthesis(top.clone()));
+        while let Some(top) = operators.pop() {
+            if top.class == TokenType::Lparen {
+                return Err(ExprError::UnterminatedParenthesis(top));
+            }
+            operands.reduce_operator(&top)?;
+        }
+        //return the last operand
+        Ok(operands.pop())
+    }
+
+    //parse an index or slice, from after its opening bracket to after its closing bracket
+    fn make_subscript(&mut self, lbracket: &Token) -> Result<Subscript, ExprError> {
+        let ends_at = vec![TokenType::Colon, TokenType::Rbracket];
+        let start = match self.cursor.check(&TokenType::Colon) {
+            true => None,
+            false => Some(self.make_sub_expr(ends_at.clone(), lbracket)?),
+        };
+        let subscript = match (start, self.cursor.consume_if(&TokenType::Colon)) {
+            (Some((index, depth)), None) => Subscript::Index(index, depth),
+            (start, _) if self.cursor.check(&TokenType::Rbracket) => Subscript::Slice(start, None),
+            (start, _) => Subscript::Slice(start, Some(self.make_sub_expr(ends_at, lbracket)?)),
+        };
+        self.cursor
+            .expect(&TokenType::Rbracket)
+            .map_err(|got| ExprError::ExpectToken(TokenType::Rbracket, got.clone()))?;
+        Ok(subscript)
+    }
+
+    //parse the entries of a map literal, from after its opening brace to after its closing brace
+    //entries are separated by commas and can be on lines of their own, eg- {"a": 1, "b": 2,}
+    fn make_map(&mut self, lbrace: &Token) -> Result<(Expr, usize), ExprError> {
+        //an invalid map is skipped up to its closing brace, which would otherwise end the statement early
+        self.make_map_entries(lbrace)
+            .inspect_err(|_| self.skip_block())
+    }
+
+    fn make_map_entries(&mut self, lbrace: &Token) -> Result<(Expr, usize), ExprError> {
+        let (entries, depth) = self.make_entries(lbrace, |parser| {
+            parser.make_sub_expr(vec![TokenType::Colon], lbrace)
+        })?;
+        Ok((Expr::Map(entries), depth + 1))
+    }
+
+    //check if the cursor is at the brace of a record literal following the name of its struct
+    //a record gives at least one field, so the block of eg- if a { b: ... } can't be one
+    fn at_record(&self) -> bool {
+        if !self.cursor.check(&TokenType::Lbrace) {
+            return false;
+        }
+        let mut ahead = 1;
+        while self.cursor.peek_nth(ahead).class == TokenType::StmtEnd {
+            ahead += 1;
+        }
+        self.cursor
+            .peek_nth(ahead)
+            .class
+            .is_kind(&TokenType::Ident(String::new()))
+            && self.cursor.peek_nth(ahead + 1).class == TokenType::Colon
+    }
+
+    //parse the fields of a record literal, like the entries of a map with field names as keys
+    //eg- Point { x: 1, y: 2 }, the cursor is after the opening brace
+    fn make_record(&mut self, name: &str, lbrace: &Token) -> Result<(Expr, usize), ExprError> {
+        let (fields, depth) = self
+            .make_entries(lbrace, |parser| {
+                let token = parser.cursor.peek().clone();
+                match &token.class {
+                    TokenType::Ident(_) => {
+                        parser.cursor.advance();
+                        Ok((token, 0))
+                    }
+                    _ => Err(ExprError::ExpectToken(
+                        TokenType::Ident(String::new()),
+                        token,
+                    )),
                 }
-                TokenType::Operator(opr) => {
-                    let right = operands.pop().unwrap();
-                    let expr = Expr::new_binary_op(operands.pop().unwrap(), right, opr);
-                    operands.push(expr);
-                    operators.pop();
+            })
+            .inspect_err(|_| self.skip_block())?;
+        let mut names: Vec<String> = Vec::new();
+        let mut entries = Vec::new();
+        for (token, value) in fields {
+            let TokenType::Ident(field) = &token.class else {
+                unreachable!()
+            };
+            if names.contains(field) {
+                return Err(ExprError::DuplicateField(token));
+            }
+            names.push(field.to_owned());
+            entries.push((field.to_owned(), value));
+        }
+        Ok((Expr::Record(name.to_owned(), entries), depth + 1))
+    }
+
+    //parse the entries of a map or record literal up to and including its closing brace
+    //returns the entries with the depth of the deepest one
+    fn make_entries<K>(
+        &mut self,
+        lbrace: &Token,
+        mut make_key: impl FnMut(&mut Self) -> Result<(K, usize), ExprError>,
+    ) -> Result<(Vec<(K, Expr)>, usize), ExprError> {
+        let mut entries = Vec::new();
+        let mut depth = 0;
+        loop {
+            self.skip_newlines();
+            match self.cursor.peek().class {
+                TokenType::Rbrace => break,
+                TokenType::Eof => return Err(ExprError::UnterminatedMap(lbrace.clone())),
+                _ => {}
+            }
+            let (key, key_depth) = make_key(self)?;
+            self.cursor
+                .expect(&TokenType::Colon)
+                .map_err(|got| ExprError::ExpectToken(TokenType::Colon, got.clone()))?;
+            let (value, value_depth) = self.make_sub_expr(vec![TokenType::Comma], lbrace)?;
+            entries.push((key, value));
+            depth = depth.max(key_depth).max(value_depth);
+            self.skip_newlines();
+            match &self.cursor.peek().class {
+                TokenType::Comma => {
+                    self.cursor.advance();
                 }
-                TokenType::Unary(unr) => {
-                    let expr = Expr::new_unary_op(operands.pop().unwrap(), unr);
-                    operands.push(expr);
-                    operators.pop();
+                TokenType::Rbrace => break,
+                TokenType::Eof => return Err(ExprError::UnterminatedMap(lbrace.clone())),
+                _ => {
+                    return Err(ExprError::ExpectToken(
+                        TokenType::Rbrace,
+                        self.cursor.peek().clone(),
+                    ))
                 }
-                _ => {}
             }
         }
-        //return the last operand
-        Ok(Some(operands.pop().unwrap()))
+        self.cursor.advance();
+        Ok((entries, depth))
+    }
+
+    //Parse the parts of an interpolated string, the code of each part is parsed on its own
+    fn make_interpolation(
+        &self,
+        parts: &[StringPart],
+        token: &Token,
+    ) -> Result<(Expr, usize), ExprError> {
+        let mut exprs = Vec::new();
+        let mut depth = 0;
+        for part in parts {
+            match part {
+                StringPart::Text(text) => exprs.push(Expr::Literal(Literal::String(text.clone()))),
+                StringPart::Code(tokens) => {
+                    //the code is nested in the string, like the entries of a map are
+                    let mut parser = Parser::new(tokens);
+                    parser.nesting = self.nesting;
+                    parser.declared = self.declared.clone();
+                    parser.check_uses = self.check_uses;
+                    let (expr, expr_depth) = parser.make_sub_expr(Vec::new(), token)?;
+                    //the code is a single expression, ending at its closing brace
+                    let end = parser.cursor.peek();
+                    if end.class != TokenType::Eof {
+                        return Err(ExprError::ExpectToken(TokenType::Rbrace, end.clone()));
+                    }
+                    exprs.push(expr);
+                    depth = depth.max(expr_depth);
+                }
+            }
+        }
+        Ok((Expr::Interpolation(exprs), depth + 1))
+    }
+
+    //parse an expression nested in another one, eg- a map entry or an index
+    //it ends at one of the given tokens, the tokens ending the outer expression don't apply inside it
+    //token: the token starting the nesting, where the error is reported if it is too deep
+    fn make_sub_expr(
+        &mut self,
+        ends_at: Vec<TokenType>,
+        token: &Token,
+    ) -> Result<(Expr, usize), ExprError> {
+        //checked before parsing, so deeply nested maps can't overflow the stack of the parser
+        if self.nesting >= MAX_NESTING_DEPTH {
+            return Err(ExprError::TooDeep(token.clone()));
+        }
+        self.nesting += 1;
+        let tree = self.with_ends_at(ends_at, Self::make_expr_tree);
+        self.nesting -= 1;
+        match tree? {
+            Some(tree) => Ok(tree),
+            None => Err(ExprError::ExpectTokenError(
+                ExpectType::Operand,
+                self.cursor.peek().clone(),
+            )),
+        }
+    }
+
+    //Parse with a scope of its own for the variables declared
+    fn in_scope<T>(&mut self, parse: impl FnOnce(&mut Self) -> T) -> T {
+        if let Some(declared) = &mut self.declared {
+            declared.push(HashSet::new());
+        }
+        let result = parse(self);
+        if let Some(declared) = &mut self.declared {
+            declared.pop();
+        }
+        result
+    }
+
+    //Record a variable declared in the innermost scope
+    fn declare(&mut self, name: &str) {
+        if let Some(scope) = self
+            .declared
+            .as_mut()
+            .and_then(|declared| declared.last_mut())
+        {
+            scope.insert(name.to_owned());
+        }
+    }
+
+    //Check that the variable named by an identifier token was declared, if declarations are checked
+    fn check_declared(&self, token: &Token) -> Result<(), ExprError> {
+        let (Some(declared), TokenType::Ident(name), true) =
+            (&self.declared, &token.class, self.check_uses)
+        else {
+            return Ok(());
+        };
+        if declared.iter().any(|scope| scope.contains(name)) {
+            Ok(())
+        } else {
+            Err(ExprError::UndeclaredVariable(token.clone()))
+        }
+    }
+
+    //Check that the variable about to be declared by an identifier token does not shadow another
+    //a variable declared again in the same block is replaced, not shadowed
+    fn check_shadowing(&self, token: &Token) -> Result<(), StmtError> {
+        let (Some(declared), TokenType::Ident(name), false) =
+            (&self.declared, &token.class, self.shadowing)
+        else {
+            return Ok(());
+        };
+        let outer = &declared[..declared.len() - 1];
+        if outer.iter().any(|scope| scope.contains(name)) {
+            Err(StmtError::ShadowedVariable(token.clone()))
+        } else {
+            Ok(())
+        }
+    }
+
+    //parse with other tokens ending the expressions, the previous ones apply again afterwards
+    fn with_ends_at<T>(
+        &mut self,
+        ends_at: Vec<TokenType>,
+        parse: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let outer = std::mem::replace(&mut self.ends_at, ends_at);
+        let result = parse(self);
+        self.ends_at = outer;
+        result
+    }
+
+    //skip the line breaks between the entries of a map
+    fn skip_newlines(&mut self) {
+        while self.cursor.consume_if(&TokenType::StmtEnd).is_some() {}
+    }
+
+    //Pops operators off the stack into expressions until a left parenthesis is at the top
+    //the parenthesis itself is left on the stack
+    fn reduce_parenthesis(
+        operands: &mut Operands,
+        operators: &mut Vec<Token>,
+    ) -> Result<(), ExprError> {
+        while let Some(top) = operators.last() {
+            if top.class == TokenType::Lparen {
+                break;
+            }
+            operands.reduce_operator(top)?;
+            operators.pop();
+        }
+        Ok(())
     }
 
     //Checks the expression, if invalid return a StmtError else return the unwrapped Expr
@@ -266,9 +1245,7 @@ impl<'a> Parser<'a> {
                 if let Some(expr) = expr {
                     Ok(expr)
                 } else {
-                    Err(StmtError::ExpectedExpression(
-                        self.get_current_token().clone(),
-                    ))
+                    Err(StmtError::ExpectedExpression(self.cursor.peek().clone()))
                 }
             }
             Err(err) => {
@@ -279,37 +1256,217 @@ impl<'a> Parser<'a> {
         }
     }
 
-    //advances the position
-    fn consume(&mut self) {
-        self.pos += 1;
+    //check if the current token ends the expression being parsed
+    fn at_expr_end(&self) -> bool {
+        self.cursor.at_stmt_end() || self.ends_at.iter().any(|end| self.cursor.check(end))
     }
 
-    //return the token at the current pos
-    //return the last EOF otherwise
-    fn get_current_token(&self) -> &Token {
-        let pos = self.pos as usize;
-        if self.is_eof(pos) {
-            return &self.tokens[self.tokens.len() - 1];
+    //consume the next token of an expression
+    //at the end of the expression the ending token is returned without consuming it
+    fn next_expr_token(&mut self) -> Token {
+        if self.at_expr_end() {
+            self.cursor.peek().clone()
+        } else {
+            self.cursor.advance().clone()
         }
-        &self.tokens[pos]
-    }
-
-    fn is_eof(&self, pos: usize) -> bool {
-        pos >= self.tokens.len()
     }
 }
 
+//The init, condition and step of a for loop
+type ForHeader = (Option<Box<Stmt>>, Option<Expr>, Option<Box<Stmt>>);
+
+//The inside of the brackets following a value, each expression with the depth of its tree
+enum Subscript {
+    //Index(Index, Depth), eg- m["a"]
+    Index(Expr, usize),
+    //Slice(Start, End), eg- s[1:4], a bound is None when it is left out
+    Slice(Option<(Expr, usize)>, Option<(Expr, usize)>),
+}
+
+//Split the target of an index assignment into its variable and accessors, eg- m["a"]["b"] or p.pos.x
+//returns None if the target is not a variable indexed or accessed at least once
+fn index_target(mut target: Expr) -> Option<(String, Vec<Accessor>)> {
+    let mut accessors = Vec::new();
+    loop {
+        match target {
+            Expr::Index(value, index) => {
+                accessors.push(Accessor::Index(*index));
+                target = *value;
+            }
+            Expr::Field(record, field) => {
+                accessors.push(Accessor::Field(field));
+                target = *record;
+            }
+            Expr::Ident(name) if !accessors.is_empty() => {
+                accessors.reverse();
+                return Some((name, accessors));
+            }
+            _ => return None,
+        }
+    }
+}
+
+//The deepest blocks can be nested
+pub const MAX_BLOCK_DEPTH: usize = 64;
+
+//The deepest map entries and indexes can be nested, each one is parsed recursively
+pub const MAX_NESTING_DEPTH: usize = 64;
+
+//Expressions are evaluated recursively, deeper expressions are rejected so they can't overflow the stack
+//the left operands of a chain of binary operators are solved in a loop, so 1 + 2 + 3 is only 2 deep
+pub const MAX_EXPR_DEPTH: usize = 256;
+
+//The operand stack of the shunting yard, keeping the depth of each expression tree on it
+struct Operands {
+    exprs: Vec<Expr>,
+    depths: Vec<usize>,
+    //Whether each operand is a comparison which a following comparison chains onto
+    chains: Vec<bool>,
+}
+
+impl Operands {
+    fn new() -> Self {
+        Self {
+            exprs: Vec::new(),
+            depths: Vec::new(),
+            chains: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, expr: Expr) {
+        self.exprs.push(expr);
+        self.depths.push(1);
+        self.chains.push(false);
+    }
+
+    //A comparison in parenthesis is compared as a whole, so (1 < x) < 10 is not a chain
+    fn end_chain(&mut self) {
+        if let Some(chain) = self.chains.last_mut() {
+            *chain = false;
+        }
+    }
+
+    //Push an expression parsed on its own, eg- a map literal, with the depth of its tree
+    fn push_tree(&mut self, expr: Expr, depth: usize, token: &Token) -> Result<(), ExprError> {
+        if depth > MAX_EXPR_DEPTH {
+            return Err(ExprError::TooDeep(token.clone()));
+        }
+        self.exprs.push(expr);
+        self.depths.push(depth);
+        self.chains.push(false);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<(Expr, usize)> {
+        self.chains.pop();
+        self.exprs.pop().zip(self.depths.pop())
+    }
+
+    //Replace the last count operands with an expression built from them
+    //token: the token making the expression, where the error is reported if it is too deep
+    fn reduce(
+        &mut self,
+        count: usize,
+        token: &Token,
+        build: impl FnOnce(Vec<Expr>) -> Expr,
+    ) -> Result<(), ExprError> {
+        let at = self.exprs.len() - count;
+        let depth = self.depths[at..].iter().max().unwrap_or(&0) + 1;
+        self.reduce_to_depth(count, depth, token, build)
+    }
+
+    //Replace the two operands at the top with a binary operation on them
+    //the left operand is not nested deeper, Expr::solve walks down chains of them in a loop
+    fn reduce_binary(
+        &mut self,
+        token: &Token,
+        build: impl FnOnce(Vec<Expr>) -> Expr,
+    ) -> Result<(), ExprError> {
+        let [left, right] = self.depths[self.depths.len() - 2..] else {
+            unreachable!("a binary operator is only reduced with two operands")
+        };
+        self.reduce_to_depth(2, left.max(right + 1), token, build)
+    }
+
+    fn reduce_to_depth(
+        &mut self,
+        count: usize,
+        depth: usize,
+        token: &Token,
+        build: impl FnOnce(Vec<Expr>) -> Expr,
+    ) -> Result<(), ExprError> {
+        let at = self.exprs.len() - count;
+        self.depths.truncate(at);
+        if depth > MAX_EXPR_DEPTH {
+            return Err(ExprError::TooDeep(token.clone()));
+        }
+        let exprs = self.exprs.split_off(at);
+        self.exprs.push(build(exprs));
+        self.depths.push(depth);
+        self.chains.truncate(at);
+        self.chains.push(false);
+        Ok(())
+    }
+
+    //Apply a binary or unary operator to the operands at the top
+    fn reduce_operator(&mut self, token: &Token) -> Result<(), ExprError> {
+        match &token.class {
+            //a comparison following another one compares its right operand, like in maths
+            //1 < x < 10 is 1 < x and x < 10, with x only solved once
+            TokenType::Operator(opr) if opr.is_ordering() => {
+                let chained = self.chains[self.chains.len() - 2];
+                self.reduce_binary(token, |mut exprs| {
+                    let right = exprs.pop().unwrap();
+                    let left = exprs.pop().unwrap();
+                    if chained {
+                        chain_comparison(left, opr, right)
+                    } else {
+                        Expr::new_binary_op(left, right, opr)
+                    }
+                })?;
+                self.chains.pop();
+                self.chains.push(true);
+                Ok(())
+            }
+            TokenType::Operator(opr) => self.reduce_binary(token, |mut exprs| {
+                let right = exprs.pop().unwrap();
+                Expr::new_binary_op(exprs.pop().unwrap(), right, opr).with_position(token)
+            }),
+            TokenType::Unary(unr) => self.reduce(1, token, |mut exprs| {
+                Expr::new_unary_op(exprs.pop().unwrap(), unr).with_position(token)
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+//Add a comparison to a chain, a single comparison becomes a chain of two
+fn chain_comparison(chain: Expr, opr: &Operator, right: Expr) -> Expr {
+    let (first, mut comparisons) = match chain {
+        Expr::Compare(first, comparisons) => (first, comparisons),
+        Expr::Greater(left, middle) => (left, vec![(Operator::Greater, *middle)]),
+        Expr::Less(left, middle) => (left, vec![(Operator::Less, *middle)]),
+        Expr::GreaterEqual(left, middle) => (left, vec![(Operator::GreaterEqual, *middle)]),
+        Expr::LessEqual(left, middle) => (left, vec![(Operator::LessEqual, *middle)]),
+        _ => unreachable!("a chain only holds comparisons"),
+    };
+    comparisons.push((opr.clone(), right));
+    Expr::Compare(first, comparisons)
+}
+
 #[cfg(test)]
 mod tests {
+    use super::super::analyzer::Analyzer;
     use super::super::lexer::*;
     use super::*;
+    use crate::executor::Executor;
 
     fn compare_results(src: &[&str], expected: &[Expr]) {
         for (line, expect) in src.iter().zip(expected) {
             let mut lexer = Lexer::new(line);
             let tokens = lexer.lex();
 
-            let parse_result = Parser::new(&tokens).parse(None);
+            let parse_result = Parser::new(&tokens).parse();
             println!("{:?}", parse_result);
             match &parse_result.unwrap().stmts[0] {
                 Stmt::Expr(expr) => assert_eq!(expr, expect),
@@ -372,6 +1529,36 @@ mod tests {
         compare_results(&src, &expected);
     }
 
+    #[test]
+    fn parse_power_ops() {
+        let num = Expr::new_num_literal;
+        let neg = |expr| Expr::new_unary_op(expr, &Unary::Neg);
+        let src = [
+            "2 - 3 * 4 + 5",
+            "2 ** 3 ** 2",
+            "-2 ** 2",
+            "2 ** -1 * 3",
+            "7 // 2 * 3 % 4",
+            "1 + 2 ** 3 // 4",
+        ];
+        let expected = [
+            //lower operators after a higher one reduce every operator before them
+            Expr::new_add(Expr::new_sub(num(2), Expr::new_mul(num(3), num(4))), num(5)),
+            Expr::new_pow(num(2), Expr::new_pow(num(3), num(2))),
+            neg(Expr::new_pow(num(2), num(2))),
+            Expr::new_mul(Expr::new_pow(num(2), neg(num(1))), num(3)),
+            Expr::new_mod(
+                Expr::new_mul(Expr::new_floor_div(num(7), num(2)), num(3)),
+                num(4),
+            ),
+            Expr::new_add(
+                num(1),
+                Expr::new_floor_div(Expr::new_pow(num(2), num(3)), num(4)),
+            ),
+        ];
+        compare_results(&src, &expected);
+    }
+
     #[test]
     fn parse_identifier_ops() {
         let src = [
@@ -419,16 +1606,13 @@ mod tests {
             "!a and b",
         ];
         let expected = [
-            Expr::Negate(Box::new(Expr::new_num_literal(5))),
-            Expr::Negate(Box::new(Expr::new_ident("a"))),
-            Expr::Negate(Box::new(Expr::new_add(
+            Expr::new_negate(Expr::new_num_literal(5)),
+            Expr::new_negate(Expr::new_ident("a")),
+            Expr::new_negate(Expr::new_add(
                 Expr::new_num_literal(5),
                 Expr::new_num_literal(5),
-            ))),
-            Expr::Negate(Box::new(Expr::new_add(
-                Expr::new_ident("a"),
-                Expr::new_ident("b"),
-            ))),
+            )),
+            Expr::new_negate(Expr::new_add(Expr::new_ident("a"), Expr::new_ident("b"))),
             Expr::Not(Box::new(Expr::new_ident("a"))),
             Expr::Not(Box::new(Expr::Or(
                 Box::new(Expr::new_ident("a")),
@@ -442,9 +1626,1010 @@ mod tests {
         compare_results(&src, &expected);
     }
 
+    #[test]
+    fn parse_tuples() {
+        let src = [
+            "(1, 2)",
+            "(a + 1, -b,)",
+            "(5,)",
+            "t.0",
+            "((1, 2), 3).0.1",
+            "-t.1 * 2",
+        ];
+        let expected = [
+            Expr::Tuple(vec![Expr::new_num_literal(1), Expr::new_num_literal(2)]),
+            Expr::Tuple(vec![
+                Expr::new_add(Expr::new_ident("a"), Expr::new_num_literal(1)),
+                Expr::new_negate(Expr::new_ident("b")),
+            ]),
+            Expr::Tuple(vec![Expr::new_num_literal(5)]),
+            Expr::new_tuple_index(Expr::new_ident("t"), 0),
+            Expr::new_tuple_index(
+                Expr::new_tuple_index(
+                    Expr::Tuple(vec![
+                        Expr::Tuple(vec![Expr::new_num_literal(1), Expr::new_num_literal(2)]),
+                        Expr::new_num_literal(3),
+                    ]),
+                    0,
+                ),
+                1,
+            ),
+            Expr::new_mul(
+                Expr::new_negate(Expr::new_tuple_index(Expr::new_ident("t"), 1)),
+                Expr::new_num_literal(2),
+            ),
+        ];
+        compare_results(&src, &expected);
+    }
+
+    #[test]
+    fn parse_maps() {
+        let string = |text: &str| Expr::new_literal(&Literal::String(text.to_owned()));
+        let src = [
+            "({})",
+            "({\"a\": 1, 2: (b, c),})",
+            "({\n  \"a\": {\"b\": 1},\n  c: d\n})",
+            "m[\"a\"][i + 1]",
+            "-m[0] * 2",
+            "keys(m)[0].1",
+        ];
+        let expected = [
+            Expr::Map(Vec::new()),
+            Expr::Map(vec![
+                (string("a"), Expr::new_num_literal(1)),
+                (
+                    Expr::new_num_literal(2),
+                    Expr::Tuple(vec![Expr::new_ident("b"), Expr::new_ident("c")]),
+                ),
+            ]),
+            Expr::Map(vec![
+                (
+                    string("a"),
+                    Expr::Map(vec![(string("b"), Expr::new_num_literal(1))]),
+                ),
+                (Expr::new_ident("c"), Expr::new_ident("d")),
+            ]),
+            Expr::new_index(
+                Expr::new_index(Expr::new_ident("m"), string("a")),
+                Expr::new_add(Expr::new_ident("i"), Expr::new_num_literal(1)),
+            ),
+            Expr::new_mul(
+                Expr::new_negate(Expr::new_index(
+                    Expr::new_ident("m"),
+                    Expr::new_num_literal(0),
+                )),
+                Expr::new_num_literal(2),
+            ),
+            Expr::new_tuple_index(
+                Expr::new_index(
+                    Expr::new_call("keys", vec![Expr::new_ident("m")]),
+                    Expr::new_num_literal(0),
+                ),
+                1,
+            ),
+        ];
+        compare_results(&src, &expected);
+
+        //an assignment to an index sets an item of the variable's map
+        let tokens = Lexer::new("m[\"a\"][b] = 1\nlet n = {1: 2}").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        match &block.stmts[0] {
+            Stmt::IndexAssign(name, accessors, expr) => {
+                assert_eq!(name, "m");
+                assert_eq!(
+                    accessors,
+                    &vec![
+                        Accessor::Index(string("a")),
+                        Accessor::Index(Expr::new_ident("b"))
+                    ]
+                );
+                assert_eq!(expr, &Expr::new_num_literal(1));
+            }
+            stmt => panic!("Expected an index assignment, got {:?}", stmt),
+        }
+        assert!(matches!(block.stmts[1], Stmt::Assign(_, Expr::Map(_))));
+    }
+
+    #[test]
+    fn parse_interpolation() {
+        let text = |text: &str| Expr::Literal(Literal::String(text.to_owned()));
+        compare_results(
+            &["\"a${b * 2}\" + \"${\"c${d}\"}\""],
+            &[Expr::new_add(
+                Expr::Interpolation(vec![
+                    text("a"),
+                    Expr::new_mul(Expr::new_ident("b"), Expr::new_num_literal(2)),
+                ]),
+                Expr::Interpolation(vec![Expr::Interpolation(vec![
+                    text("c"),
+                    Expr::new_ident("d"),
+                ])]),
+            )],
+        );
+
+        let error = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            Parser::new(&tokens).parse().unwrap_err().errors.remove(0)
+        };
+        //errors in the code point into the string, the end of the code is its closing brace
+        assert_eq!(error("print \"${1 +}\"").get_position(), (1, 12));
+        assert_eq!(error("print \"${}\"").get_message(), "Expected an operand");
+        assert_eq!(
+            error("print \"${1; 2}\"").get_message(),
+            "Expected }, got the end of statement instead"
+        );
+        assert_eq!(error("print \"${a}\" \"b\"").get_position(), (1, 13));
+    }
+
+    #[test]
+    fn parse_slices() {
+        let src = ["s[1:4]", "s[:n - 1]", "s[2:][0]", "s[:]"];
+        let number = Expr::new_num_literal;
+        let expected = [
+            Expr::new_slice(Expr::new_ident("s"), Some(number(1)), Some(number(4))),
+            Expr::new_slice(
+                Expr::new_ident("s"),
+                None,
+                Some(Expr::new_sub(Expr::new_ident("n"), number(1))),
+            ),
+            Expr::new_index(
+                Expr::new_slice(Expr::new_ident("s"), Some(number(2)), None),
+                number(0),
+            ),
+            Expr::new_slice(Expr::new_ident("s"), None, None),
+        ];
+        compare_results(&src, &expected);
+
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            Parser::new(&tokens).parse().unwrap_err().errors
+        };
+        assert_eq!(
+            errors("s[1:2:3]")[0].get_message(),
+            "Expected ], got : instead"
+        );
+        //a slice can't be assigned to
+        assert_eq!(
+            errors("s[1:] = \"a\"")[0].get_message(),
+            "Invalid assignment target"
+        );
+    }
+
+    #[test]
+    fn test_map_errors() {
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            Parser::new(&tokens).parse().unwrap_err().errors
+        };
+        let token = |class, line, start| Token { class, line, start };
+
+        assert_eq!(
+            errors("let m = {\"a\"}"),
+            vec![StmtError::InvalidExpression(ExprError::ExpectToken(
+                TokenType::Colon,
+                token(TokenType::Rbrace, 1, 12)
+            ))]
+        );
+        //the rest of an invalid map is skipped, nested maps included
+        assert_eq!(errors("let m = {\"a\" 1, \"b\": {}}\nprint {1 2}").len(), 2);
+        assert_eq!(
+            errors("let m = {\"a\": 1,\n"),
+            vec![StmtError::InvalidExpression(ExprError::UnterminatedMap(
+                token(TokenType::Lbrace, 1, 8)
+            ))]
+        );
+        assert_eq!(
+            errors("print m[1\nprint 2"),
+            vec![StmtError::InvalidExpression(ExprError::ExpectToken(
+                TokenType::Rbracket,
+                token(TokenType::StmtEnd, 1, 9)
+            ))]
+        );
+        assert_eq!(errors("m[] = 2")[0].get_message(), "Expected an operand");
+        //only the items of variables can be assigned to
+        assert_eq!(
+            errors("f()[0] = 1\nt.0 = 1"),
+            vec![
+                StmtError::InvalidAssignTarget(token(TokenType::Assign, 1, 7)),
+                StmtError::InvalidAssignTarget(token(TokenType::Assign, 2, 4)),
+            ]
+        );
+        //a brace after a condition starts its block, so a map needs parenthesis there
+        assert_eq!(
+            errors("if m == {} {}")[0].get_message(),
+            "Expected an operand"
+        );
+        let tokens = Lexer::new("if m == ({}) {}").lex();
+        assert!(Parser::new(&tokens).parse().is_ok());
+    }
+
+    #[test]
+    fn parse_calls() {
+        let src = ["type(a)", "f()", "f(1, b + 2)", "-f(1).0", "type(f(1),)"];
+        let expected = [
+            Expr::new_call("type", vec![Expr::new_ident("a")]),
+            Expr::new_call("f", Vec::new()),
+            Expr::new_call(
+                "f",
+                vec![
+                    Expr::new_num_literal(1),
+                    Expr::new_add(Expr::new_ident("b"), Expr::new_num_literal(2)),
+                ],
+            ),
+            Expr::new_negate(Expr::new_tuple_index(
+                Expr::new_call("f", vec![Expr::new_num_literal(1)]),
+                0,
+            )),
+            Expr::new_call(
+                "type",
+                vec![Expr::new_call("f", vec![Expr::new_num_literal(1)])],
+            ),
+        ];
+        compare_results(&src, &expected);
+    }
+
+    #[test]
+    fn parse_type_ops() {
+        let src = [
+            "a is number",
+            "-a as string + b",
+            "a is bool and b is tuple",
+        ];
+        let expected = [
+            Expr::new_is(Expr::new_ident("a"), ValueType::Number),
+            Expr::new_add(
+                Expr::new_cast(Expr::new_negate(Expr::new_ident("a")), ValueType::String),
+                Expr::new_ident("b"),
+            ),
+            Expr::new_and(
+                Expr::new_is(Expr::new_ident("a"), ValueType::Bool),
+                Expr::new_is(Expr::new_ident("b"), ValueType::Tuple),
+            ),
+        ];
+        compare_results(&src, &expected);
+    }
+
+    #[test]
+    fn parse_destructure() {
+        let tokens = Lexer::new("let a, b = (1, 2)").lex();
+        match &Parser::new(&tokens).parse().unwrap().stmts[0] {
+            Stmt::Destructure(names, expr) => {
+                assert_eq!(names, &["a", "b"]);
+                assert_eq!(
+                    expr,
+                    &Expr::Tuple(vec![Expr::new_num_literal(1), Expr::new_num_literal(2)])
+                );
+            }
+            stmt => panic!("Expected a destructure statement but got {:?}", stmt),
+        }
+
+        let tokens = Lexer::new("let (a, b) = pair\nlet (c) = single").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        assert!(matches!(&block.stmts[0], Stmt::Destructure(names, _) if names == &["a", "b"]));
+        assert!(matches!(&block.stmts[1], Stmt::Destructure(names, _) if names == &["c"]));
+        for source in ["let (a, b = pair", "let (a, b)", "let (a, b) pair"] {
+            assert!(Parser::new(&Lexer::new(source).lex()).parse().is_err());
+        }
+    }
+
+    #[test]
+    fn parse_blocks() {
+        let tokens = Lexer::new("{\n  let a = 1\n  {print a}\n}; {}").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(block.stmts.len(), 2);
+        match &block.stmts[0] {
+            Stmt::Block(inner) => {
+                assert!(matches!(inner.stmts[0], Stmt::Assign(..)));
+                match &inner.stmts[1] {
+                    Stmt::Block(innermost) => {
+                        assert!(matches!(innermost.stmts[0], Stmt::Print(..)));
+                        //ids are given in source order across blocks
+                        assert_eq!((innermost.spans[0].id, innermost.spans[0].line), (3, 3));
+                    }
+                    stmt => panic!("Expected a block but got {:?}", stmt),
+                }
+            }
+            stmt => panic!("Expected a block but got {:?}", stmt),
+        }
+        assert!(matches!(&block.stmts[1], Stmt::Block(inner) if inner.stmts.is_empty()));
+    }
+
+    #[test]
+    fn test_block_errors() {
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            Parser::new(&tokens).parse().unwrap_err().errors
+        };
+        let brace = |class, line, start| Token { class, line, start };
+
+        assert_eq!(
+            errors("{\nprint 1"),
+            [StmtError::UnterminatedBlock(brace(TokenType::Lbrace, 1, 0))]
+        );
+        assert_eq!(
+            errors("print 1 }"),
+            [StmtError::InvalidStartToken(brace(TokenType::Rbrace, 1, 8))]
+        );
+        //errors inside blocks are reported along with the rest
+        assert_eq!(errors("{ let }\n{ print }").len(), 2);
+
+        let source = "{".repeat(MAX_BLOCK_DEPTH + 1) + &"}".repeat(MAX_BLOCK_DEPTH + 1);
+        assert_eq!(
+            errors(&source),
+            [StmtError::TooDeep(brace(
+                TokenType::Lbrace,
+                1,
+                MAX_BLOCK_DEPTH as u32
+            ))]
+        );
+        //the deepest allowed blocks parse, run and are analyzed within a test thread's stack
+        let source =
+            "{".repeat(MAX_BLOCK_DEPTH) + "let a = 1; a = 2" + &"}".repeat(MAX_BLOCK_DEPTH);
+        let tokens = Lexer::new(&source).lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(Analyzer::analyze(&block).len(), 1);
+        Executor::new().execute_code(&block, false);
+    }
+
+    #[test]
+    fn parse_functions() {
+        let tokens =
+            Lexer::new("fn add(a, b,) {\n  return a + b\n}\nfn nothing() { return }").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        match &block.stmts[0] {
+            Stmt::FnDecl(function) => {
+                assert_eq!(function.name, "add");
+                assert_eq!(function.params, ["a", "b"]);
+                match &function.body.stmts[0] {
+                    Stmt::Return(Some(expr)) => assert_eq!(
+                        expr,
+                        &Expr::new_add(Expr::new_ident("a"), Expr::new_ident("b"))
+                    ),
+                    stmt => panic!("Expected a return statement but got {:?}", stmt),
+                }
+            }
+            stmt => panic!("Expected a function declaration but got {:?}", stmt),
+        }
+        match &block.stmts[1] {
+            Stmt::FnDecl(function) => {
+                assert!(function.params.is_empty());
+                assert!(matches!(function.body.stmts[0], Stmt::Return(None)));
+            }
+            stmt => panic!("Expected a function declaration but got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn parse_exit() {
+        let tokens = Lexer::new("exit\nif a { exit(a + 1) }").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        assert!(matches!(block.stmts[0], Stmt::Exit(None)));
+        match &block.stmts[1] {
+            Stmt::If(_, then, None) => assert!(matches!(then.stmts[..], [Stmt::Exit(Some(_))])),
+            stmt => panic!("Expected an if statement but got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn parse_assert() {
+        let tokens = Lexer::new("assert a == (1, 2)\nassert f(a, b), \"a\" + b").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        assert!(matches!(
+            block.stmts[0],
+            Stmt::Assert(Expr::Equal(..), None)
+        ));
+        assert!(matches!(
+            block.stmts[1],
+            Stmt::Assert(Expr::Call(..), Some(Expr::Add(..)))
+        ));
+
+        let tokens = Lexer::new("assert a,").lex();
+        assert!(matches!(
+            Parser::new(&tokens).parse().unwrap_err().errors[..],
+            [StmtError::ExpectedExpression(_)]
+        ));
+    }
+
+    #[test]
+    fn parse_try() {
+        let source = "try {\n  let a = 1 / 0\n} catch (e) {\n  print e[\"kind\"]\n}";
+        let tokens = Lexer::new(source).lex();
+        match &Parser::new(&tokens).parse().unwrap().stmts[0] {
+            Stmt::Try(body, name, catch) => {
+                assert!(matches!(body.stmts[..], [Stmt::Assign(..)]));
+                assert_eq!(name, "e");
+                assert!(matches!(catch.stmts[..], [Stmt::Print(_)]));
+            }
+            stmt => panic!("Expected a try, got {:?}", stmt),
+        }
+        //the error is only declared inside the catch block
+        assert!(Parser::with_declared(&tokens, []).parse().is_ok());
+        let tokens = Lexer::new("try {} catch (e) {}\nprint e").lex();
+        assert!(Parser::with_declared(&tokens, []).parse().is_err());
+
+        for source in [
+            "try {}",
+            "try {} catch e {}",
+            "try {} catch () {}",
+            "try print 1",
+        ] {
+            let tokens = Lexer::new(source).lex();
+            assert!(
+                Parser::new(&tokens).parse().is_err(),
+                "{} was parsed",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn parse_throw() {
+        let tokens = Lexer::new("throw \"no \" + name\nthrow").lex();
+        let errors = Parser::new(&tokens).parse().unwrap_err().errors;
+        assert!(matches!(errors[..], [StmtError::ExpectedExpression(_)]));
+        let tokens = Lexer::new("if a { throw (1, a) }").lex();
+        match &Parser::new(&tokens).parse().unwrap().stmts[0] {
+            Stmt::If(_, then, None) => {
+                assert!(matches!(then.stmts[..], [Stmt::Throw(Expr::Tuple(_))]))
+            }
+            stmt => panic!("Expected an if, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn parse_structs() {
+        let source = "struct Point {\n  x,\n  y,\n}\nlet p = Point { x: 1,\n  y: a.0 }\np.pos.x = p.y\nif ready { x }";
+        let tokens = Lexer::new(source).lex();
+        let structs = Features::default().with(Feature::Structs);
+        let block = Parser::new(&tokens).with_features(structs).parse().unwrap();
+        match &block.stmts[0] {
+            Stmt::Struct(declared) => {
+                assert_eq!(declared.name, "Point");
+                assert_eq!(declared.fields, ["x", "y"]);
+            }
+            stmt => panic!("Expected a struct, got {:?}", stmt),
+        }
+        match &block.stmts[1] {
+            Stmt::Assign(_, Expr::Record(name, fields)) => {
+                assert_eq!(name, "Point");
+                assert_eq!(
+                    fields,
+                    &vec![
+                        (String::from("x"), Expr::new_num_literal(1)),
+                        (
+                            String::from("y"),
+                            Expr::new_tuple_index(Expr::new_ident("a"), 0)
+                        ),
+                    ]
+                );
+            }
+            stmt => panic!("Expected a record, got {:?}", stmt),
+        }
+        match &block.stmts[2] {
+            Stmt::IndexAssign(name, accessors, expr) => {
+                assert_eq!(name, "p");
+                assert_eq!(
+                    accessors,
+                    &vec![
+                        Accessor::Field(String::from("pos")),
+                        Accessor::Field(String::from("x"))
+                    ]
+                );
+                assert_eq!(expr, &Expr::new_field(Expr::new_ident("p"), "y"));
+            }
+            stmt => panic!("Expected an index assignment, got {:?}", stmt),
+        }
+        //a brace after a name only starts a record if a field name and colon follow it
+        assert!(matches!(block.stmts[3], Stmt::If(Expr::Ident(_), _, None)));
+
+        for source in [
+            "struct P { x, x }",
+            "struct P { 1 }",
+            "struct P x, y",
+            "let p = P { x: 1, x: 2 }",
+            "let p = P { x: 1, \"y\": 2 }",
+            "p. = 1",
+        ] {
+            let tokens = Lexer::new(source).lex();
+            assert!(
+                Parser::new(&tokens).with_features(structs).parse().is_err(),
+                "{} was parsed",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn experimental_features() {
+        let tokens = Lexer::new("let a = 1\nstruct P {\n  x\n}\nlet p = P(a)").lex();
+        //the whole declaration is parsed, so only the error for the feature is reported
+        let errors = Parser::new(&tokens).parse().unwrap_err().errors;
+        assert_eq!(
+            errors,
+            [StmtError::ExperimentalFeature(
+                Token {
+                    class: TokenType::Keyword(Keyword::Struct),
+                    line: 2,
+                    start: 0
+                },
+                Feature::Structs
+            )]
+        );
+        assert_eq!(
+            errors[0].get_message(),
+            "struct is experimental, turn it on with --features=structs"
+        );
+
+        let features = Features::default().with(Feature::Structs);
+        assert!(Parser::new(&tokens).with_features(features).parse().is_ok());
+    }
+
+    #[test]
+    fn test_fn_errors() {
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            Parser::new(&tokens).parse().unwrap_err().errors
+        };
+        let token = |class, line, start| Token { class, line, start };
+
+        assert_eq!(
+            errors("return 1"),
+            [StmtError::ReturnOutsideFunction(token(
+                TokenType::Keyword(Keyword::Return),
+                1,
+                0
+            ))]
+        );
+        assert_eq!(
+            errors("fn f(a, a) {}"),
+            [StmtError::DuplicateParameter(token(
+                TokenType::Ident(String::from("a")),
+                1,
+                8
+            ))]
+        );
+        assert_eq!(
+            errors("fn f(a)\n{}"),
+            [StmtError::ExpectToken(
+                TokenType::Lbrace,
+                token(TokenType::StmtEnd, 1, 7)
+            )]
+        );
+        //the body of a function with an invalid header is skipped, its return is not reported
+        assert_eq!(errors("fn f(1) {\n  return 1\n}").len(), 1);
+        //a block after a function body is not part of the function
+        assert_eq!(errors("fn f() {}\n{ return }").len(), 1);
+    }
+
+    #[test]
+    fn parse_branches_and_loops() {
+        let tokens = Lexer::new(
+            "if a > 1 { print a } else if a { b } else {}\nwhile (a) { break; continue }\ndo { continue } while a > 1;",
+        )
+        .lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        match &block.stmts[0] {
+            Stmt::If(condition, then, Some(otherwise)) => {
+                assert_eq!(
+                    condition,
+                    &Expr::new_greater(Expr::new_ident("a"), Expr::new_num_literal(1))
+                );
+                assert!(matches!(then.stmts[0], Stmt::Print(_)));
+                //an else if is an else block holding the inner if
+                assert_eq!(otherwise.spans[0].start, 26);
+                assert!(matches!(otherwise.stmts[0], Stmt::If(_, _, Some(_))));
+            }
+            stmt => panic!("Expected an if statement but got {:?}", stmt),
+        }
+        match &block.stmts[1] {
+            Stmt::While(Expr::Ident(name), body) => {
+                assert_eq!(name, "a");
+                assert!(matches!(body.stmts[..], [Stmt::Break, Stmt::Continue]));
+            }
+            stmt => panic!("Expected a while loop but got {:?}", stmt),
+        }
+        match &block.stmts[2] {
+            Stmt::DoWhile(Expr::Greater(..), body) => {
+                assert!(matches!(body.stmts[..], [Stmt::Continue]))
+            }
+            stmt => panic!("Expected a do while loop but got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn test_loop_errors() {
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            Parser::new(&tokens).parse().unwrap_err().errors
+        };
+        let token = |class, line, start| Token { class, line, start };
+
+        assert_eq!(
+            errors("if true { break }"),
+            [StmtError::OutsideLoop(token(
+                TokenType::Keyword(Keyword::Break),
+                1,
+                10
+            ))]
+        );
+        //a function inside a loop can't leave the loop
+        assert_eq!(
+            errors("while true { fn f() { continue } }")[0].get_message(),
+            "Continue outside of a loop"
+        );
+        assert_eq!(
+            errors("while true { break 1 }"),
+            [StmtError::ExpectToken(
+                TokenType::StmtEnd,
+                token(TokenType::Literal(Literal::Number(1)), 1, 19)
+            )]
+        );
+        assert_eq!(
+            errors("if true\n{}"),
+            [StmtError::ExpectToken(
+                TokenType::Lbrace,
+                token(TokenType::StmtEnd, 1, 7)
+            )]
+        );
+        //the block of an invalid condition is skipped
+        assert_eq!(errors("while 1 + {\n  break\n}").len(), 1);
+        assert_eq!(errors("if {}").len(), 1);
+        //the while of a do while loop is on the line of its closing brace
+        assert_eq!(
+            errors("do {}\nwhile a")[0],
+            StmtError::ExpectToken(
+                TokenType::Keyword(Keyword::While),
+                token(TokenType::StmtEnd, 1, 5)
+            )
+        );
+        assert_eq!(
+            errors("do { break } while")[0].get_message(),
+            "Expected an expression"
+        );
+    }
+
+    #[test]
+    fn parse_fn_expr() {
+        let tokens = Lexer::new("let f = fn(a, b) {\n  return a + b\n}\nprint (f, fn() {})").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        match &block.stmts[0] {
+            Stmt::Assign(_, Expr::Function(function)) => {
+                assert_eq!(function.params, ["a", "b"]);
+                assert!(matches!(function.body.stmts[..], [Stmt::Return(Some(_))]));
+            }
+            stmt => panic!("Expected a function value but got {:?}", stmt),
+        }
+        assert!(matches!(&block.stmts[1], Stmt::Print(Expr::Tuple(items)) if items.len() == 2));
+        //the body of a function value in a condition ends at its own closing brace
+        let tokens = Lexer::new("if fn() { return 1 } { print 1 }").lex();
+        assert!(Parser::new(&tokens).parse().is_ok());
+
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            let errors = Parser::new(&tokens).parse().unwrap_err().errors;
+            errors
+                .iter()
+                .map(|e| e.get_message())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(errors("let f = fn(a, a) {}"), ["Duplicate parameter name"]);
+        assert_eq!(errors("let f = 1 fn() {}")[0], "Expected an operator");
+        assert_eq!(
+            errors("let f = fn() { break }"),
+            ["Break outside of a loop"]
+        );
+        //a function value sees the local variables around it
+        let tokens = Lexer::new("{ let a = 1; let f = fn() { return a } }").lex();
+        assert!(Parser::with_declared(&tokens, []).parse().is_ok());
+    }
+
+    #[test]
+    fn parse_const() {
+        let tokens = Lexer::new("const a = 1 + 2").lex();
+        match &Parser::new(&tokens).parse().unwrap().stmts[0] {
+            Stmt::Const(name, expr) => {
+                assert_eq!(name, "a");
+                assert_eq!(
+                    expr,
+                    &Expr::new_add(Expr::new_num_literal(1), Expr::new_num_literal(2))
+                );
+            }
+            stmt => panic!("Expected a const statement but got {:?}", stmt),
+        }
+        for source in ["const", "const a", "const a, b = (1, 2)", "const 1 = 1"] {
+            let tokens = Lexer::new(source).lex();
+            assert!(Parser::new(&tokens).parse().is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_declare_before_use() {
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            match Parser::with_declared(&tokens, [String::from("pre")]).parse() {
+                Ok(_) => Vec::new(),
+                Err(errors) => errors.errors.iter().map(|e| e.get_message()).collect(),
+            }
+        };
+        let undeclared = |name: &str| format!("Variable {} is used before it is declared", name);
+
+        assert!(errors(
+            "let a = pre\nconst b = a\nlet c, d = (a, b)\nc += d\nfn f(x) { return x + a }"
+        )
+        .is_empty());
+        assert!(
+            errors("for (let i = 0; i < 3; i += 1) { print i }\n{ let j = 1; { j = 2 } }")
+                .is_empty()
+        );
+        assert_eq!(errors("let a = a"), [undeclared("a")]);
+        assert_eq!(
+            errors("b = 1\nc += 1\nm[\"k\"] = 1"),
+            [undeclared("b"), undeclared("c"), undeclared("m")]
+        );
+        //variables end with their block, and functions only see the global variables
+        assert_eq!(errors("{ let a = 1 }\nprint a"), [undeclared("a")]);
+        assert_eq!(
+            errors("{\n  let a = 1\n  fn f() { return a }\n}"),
+            [undeclared("a")]
+        );
+        assert_eq!(errors("print \"${a}\""), [undeclared("a")]);
+
+        //declarations are only checked when asked for
+        let tokens = Lexer::new("print a").lex();
+        assert!(Parser::new(&tokens).parse().is_ok());
+    }
+
+    #[test]
+    fn parse_without_shadowing() {
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            let mut parser = Parser::new(&tokens).without_shadowing([String::from("pre")]);
+            match parser.parse() {
+                Ok(_) => Vec::new(),
+                Err(errors) => errors
+                    .errors
+                    .iter()
+                    .map(|e| (e.get_message(), e.get_position()))
+                    .collect(),
+            }
+        };
+        let shadows =
+            |name: &str| format!("Variable {} shadows a variable of an outer block", name);
+
+        //declaring again in the same block replaces the variable
+        assert!(errors(
+            "let a = 1
+let a = 2
+let pre = 3"
+        )
+        .is_empty());
+        assert!(errors(
+            "{ let a = 1 }
+{ let a = 2 }
+let a = 3"
+        )
+        .is_empty());
+        assert_eq!(
+            errors(
+                "let a = 1
+if a > 0 {
+  let a = 2
+}"
+            ),
+            [(shadows("a"), (3, 6))]
+        );
+        assert_eq!(errors("{ const pre = 1 }"), [(shadows("pre"), (1, 8))]);
+        assert_eq!(
+            errors(
+                "let i = 0
+for (let i = 0; i < 3; i++) {}"
+            ),
+            [(shadows("i"), (2, 9))]
+        );
+        assert_eq!(
+            errors(
+                "fn f(x) {
+  let (y, x) = (1, 2)
+}"
+            ),
+            [(shadows("x"), (2, 10))]
+        );
+        //undeclared variables are still allowed
+        assert!(errors("print b").is_empty());
+    }
+
+    #[test]
+    fn parse_compound_assign() {
+        let reassign = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            match Parser::new(&tokens).parse().unwrap().stmts.remove(0) {
+                Stmt::Reassign(name, expr) => (name, expr),
+                stmt => panic!("Expected a reassign statement but got {:?}", stmt),
+            }
+        };
+        let a = || Expr::new_ident("a");
+        let num = Expr::new_num_literal;
+        //the whole right side is the operand, whatever the precedence of its operators
+        assert_eq!(
+            reassign("a *= 2 + 3"),
+            (
+                String::from("a"),
+                Expr::new_mul(a(), Expr::new_add(num(2), num(3)))
+            )
+        );
+        assert_eq!(
+            reassign("a -= a - 1"),
+            (
+                String::from("a"),
+                Expr::new_sub(a(), Expr::new_sub(a(), num(1)))
+            )
+        );
+        assert_eq!(
+            reassign("a %= -b.0 * 2 == 4"),
+            (
+                String::from("a"),
+                Expr::new_mod(
+                    a(),
+                    Expr::new_equal(
+                        Expr::new_mul(
+                            Expr::new_unary_op(
+                                Expr::new_tuple_index(Expr::new_ident("b"), 0),
+                                &Unary::Neg
+                            ),
+                            num(2)
+                        ),
+                        num(4)
+                    )
+                )
+            )
+        );
+
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            Parser::new(&tokens).parse().unwrap_err().errors
+        };
+        assert_eq!(errors("a += ").len(), 1);
+        assert_eq!(errors("1 += 2").len(), 1);
+    }
+
+    #[test]
+    fn parse_chained_comparisons() {
+        let x = || Expr::new_ident("x");
+        let num = Expr::new_num_literal;
+        compare_results(
+            &[
+                "1 < x <= 10",
+                "0 < x < 5 > 2",
+                "(1 < x) < 10",
+                "1 < x == true",
+            ],
+            &[
+                Expr::Compare(
+                    Box::new(num(1)),
+                    vec![(Operator::Less, x()), (Operator::LessEqual, num(10))],
+                ),
+                Expr::Compare(
+                    Box::new(num(0)),
+                    vec![
+                        (Operator::Less, x()),
+                        (Operator::Less, num(5)),
+                        (Operator::Greater, num(2)),
+                    ],
+                ),
+                Expr::new_less(Expr::new_less(num(1), x()), num(10)),
+                Expr::new_equal(
+                    Expr::new_less(num(1), x()),
+                    Expr::new_literal(&Literal::Bool(true)),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn parse_increments() {
+        let tokens = Lexer::new("a++\nb--").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        let one = || Box::new(Expr::new_num_literal(1));
+        assert!(matches!(
+            &block.stmts[0],
+            Stmt::Reassign(name, Expr::Add(left, right, _))
+                if name == "a" && **left == Expr::new_ident("a") && *right == one()
+        ));
+        assert!(matches!(
+            &block.stmts[1],
+            Stmt::Reassign(name, Expr::Sub(left, right, _))
+                if name == "b" && **left == Expr::new_ident("b") && *right == one()
+        ));
+
+        //only a variable at the start of a statement can be incremented
+        for source in ["5++", "m[\"a\"]--", "print a++", "(a)++"] {
+            let tokens = Lexer::new(source).lex();
+            let errors = Parser::new(&tokens).parse().unwrap_err().errors;
+            assert!(matches!(
+                &errors[0],
+                StmtError::InvalidExpression(ExprError::InvalidStep(_))
+            ));
+        }
+        let tokens = Lexer::new("a++").lex();
+        let error = Parser::with_declared(&tokens, Vec::new())
+            .parse()
+            .unwrap_err();
+        assert_eq!(
+            error.errors[0].get_message(),
+            "Variable a is used before it is declared"
+        );
+    }
+
+    #[test]
+    fn parse_for_loops() {
+        let tokens =
+            Lexer::new("for (let i = 0; i < (3); i = i + 1) { print i }\nfor (;;) {}").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        match &block.stmts[0] {
+            Stmt::For(Some(init), Some(condition), Some(step), body) => {
+                assert!(matches!(**init, Stmt::Assign(..)));
+                assert_eq!(
+                    condition,
+                    &Expr::new_less(Expr::new_ident("i"), Expr::new_num_literal(3))
+                );
+                match &**step {
+                    Stmt::Reassign(name, expr) => {
+                        assert_eq!(name, "i");
+                        assert_eq!(
+                            expr,
+                            &Expr::new_add(Expr::new_ident("i"), Expr::new_num_literal(1))
+                        );
+                    }
+                    stmt => panic!("Expected a reassign statement but got {:?}", stmt),
+                }
+                assert!(matches!(body.stmts[0], Stmt::Print(_)));
+            }
+            stmt => panic!("Expected a for loop but got {:?}", stmt),
+        }
+        assert!(matches!(block.stmts[1], Stmt::For(None, None, None, _)));
+
+        let errors = |source: &str| {
+            let tokens = Lexer::new(source).lex();
+            Parser::new(&tokens).parse().unwrap_err().errors
+        };
+        //an invalid header skips the rest of the loop
+        assert_eq!(errors("for (print 1; true; ) {\n  break\n}").len(), 1);
+        assert_eq!(errors("for (let i = 0; i < 1; i = i + ) {}").len(), 1);
+        assert_eq!(
+            errors("for i {}")[0].get_message(),
+            "Expected (, got an identifier instead"
+        );
+    }
+
+    #[test]
+    fn statement_spans() {
+        let tokens = Lexer::new("let a = 1\n  print a; a = 2").lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        assert_eq!(
+            block.spans,
+            [
+                Span {
+                    id: 0,
+                    line: 1,
+                    start: 0
+                },
+                Span {
+                    id: 1,
+                    line: 2,
+                    start: 2
+                },
+                Span {
+                    id: 2,
+                    line: 2,
+                    start: 11
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_expr_errors() {
-        let src = vec!["5 + ;", "5 + 5 + \n", "5 + 5 + *", "5 + ="];
+        let src = ["5 + ;", "5 + 5 + \n", "5 + 5 + *", "5 + ="];
         let error = vec![
             ExprError::ExpectTokenError(
                 ExpectType::Operand,
@@ -454,12 +2639,13 @@ mod tests {
                     start: 4,
                 },
             ),
+            //the line continues after the operator, so the operand is missing at the end of file
             ExprError::ExpectTokenError(
                 ExpectType::Operand,
                 Token {
-                    class: TokenType::StmtEnd,
-                    line: 1,
-                    start: 8,
+                    class: TokenType::Eof,
+                    line: 2,
+                    start: 0,
                 },
             ),
             ExprError::ExpectTokenError(
@@ -483,7 +2669,7 @@ mod tests {
         for (line, expect) in src.iter().zip(error) {
             let mut lexer = Lexer::new(line);
             let tokens = lexer.lex();
-            let parse_result = Parser::new(&tokens).parse(None);
+            let parse_result = Parser::new(&tokens).parse();
             if let Err(errors) = parse_result {
                 if let StmtError::InvalidExpression(err) = &errors.errors[0] {
                     assert_eq!(err, &expect);
@@ -499,9 +2685,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tuple_errors() {
+        let src = ["1, 2", "(1,,2)", "t.\"a\""];
+        let error = [
+            ExprError::ExpectTokenError(
+                ExpectType::Operator,
+                Token {
+                    class: TokenType::Comma,
+                    line: 1,
+                    start: 1,
+                },
+            ),
+            ExprError::ExpectTokenError(
+                ExpectType::Operand,
+                Token {
+                    class: TokenType::Comma,
+                    line: 1,
+                    start: 3,
+                },
+            ),
+            ExprError::InvalidTupleIndex(Token {
+                class: TokenType::Literal(Literal::String("a".to_owned())),
+                line: 1,
+                start: 2,
+            }),
+        ];
+
+        for (line, expect) in src.iter().zip(error) {
+            let tokens = Lexer::new(line).lex();
+            match Parser::new(&tokens).parse() {
+                Err(errors) => {
+                    assert_eq!(errors.errors[0], StmtError::InvalidExpression(expect))
+                }
+                Ok(_) => panic!("Expected an error but got none"),
+            }
+        }
+    }
+
     #[test]
     fn test_stmt_errors() {
-        let src = vec!["let", "let a", "let = 5"];
+        let src = ["let", "let a", "let = 5", "let a, = 5", "let a, b"];
         let expecte = vec![
             StmtError::IncompleteStatement(Token {
                 class: TokenType::Keyword(Keyword::Let),
@@ -521,11 +2745,24 @@ mod tests {
                     start: 4,
                 },
             ),
+            StmtError::ExpectToken(
+                TokenType::Ident(String::new()),
+                Token {
+                    class: TokenType::Assign,
+                    line: 1,
+                    start: 7,
+                },
+            ),
+            StmtError::IncompleteStatement(Token {
+                class: TokenType::Keyword(Keyword::Let),
+                line: 1,
+                start: 0,
+            }),
         ];
         for (line, err) in src.iter().zip(expecte) {
             let mut lexer = Lexer::new(line);
             let tokens = lexer.lex();
-            let parse_result = Parser::new(&tokens).parse(None);
+            let parse_result = Parser::new(&tokens).parse();
             if let Err(errors) = parse_result {
                 //make sure only 1 error occured
                 assert!(errors.errors.len() == 1);
@@ -535,4 +2772,59 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn expression_depth_limit() {
+        let parse = |source: String| {
+            let tokens = Lexer::new(&source).lex();
+            Parser::new(&tokens).parse()
+        };
+        //the deepest allowed expressions still parse, and evaluate within a test thread's stack
+        let source = "-".repeat(MAX_EXPR_DEPTH - 1) + "1";
+        assert!(parse(source).is_ok());
+        let source = "let a = 1\nprint ".to_owned()
+            + &"a * (".repeat(MAX_EXPR_DEPTH - 1)
+            + "a"
+            + &")".repeat(MAX_EXPR_DEPTH - 1);
+        let block = parse(source).unwrap();
+        Analyzer::analyze(&block);
+        Executor::new().execute_code(&block, false);
+
+        //parenthesis alone don't make the tree deeper
+        assert!(parse("(".repeat(10_000) + "1" + &")".repeat(10_000)).is_ok());
+
+        //a chain of left associative operators is solved in a loop, so it is not nested however long it is
+        let source = "1".to_owned() + &" + 1".repeat(MAX_EXPR_DEPTH * 8);
+        let block = parse(source).unwrap();
+        assert_eq!(
+            Executor::new().execute_code(&block, false),
+            Some(Literal::Number(MAX_EXPR_DEPTH as i32 * 8 + 1))
+        );
+        let source = "0 < 1".to_owned() + &" and 1 - 1 == 0".repeat(MAX_EXPR_DEPTH * 8);
+        let block = parse(source).unwrap();
+        assert_eq!(
+            Executor::new().execute_code(&block, false),
+            Some(Literal::Bool(true))
+        );
+
+        //nesting on the right does count
+        let source = "(1 + ".repeat(MAX_EXPR_DEPTH) + "1" + &")".repeat(MAX_EXPR_DEPTH);
+        let errors = parse(source).unwrap_err();
+        assert_eq!(
+            errors.errors[0],
+            StmtError::InvalidExpression(ExprError::TooDeep(Token {
+                class: TokenType::new_operator("+"),
+                line: 1,
+                start: 3,
+            }))
+        );
+        assert!(parse("-(".repeat(100_000) + "1" + &")".repeat(100_000)).is_err());
+
+        //nested maps and indexes are limited before they are parsed, so they can't overflow the stack
+        let map = |depth| "let m = ".to_owned() + &"{1: ".repeat(depth) + "1" + &"}".repeat(depth);
+        assert!(parse(map(MAX_NESTING_DEPTH)).is_ok());
+        assert!(parse(map(MAX_NESTING_DEPTH + 1)).is_err());
+        assert!(parse(map(100_000)).is_err());
+        assert!(parse("m[".repeat(100_000) + "1" + &"]".repeat(100_000)).is_err());
+    }
 }
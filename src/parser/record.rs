@@ -0,0 +1,52 @@
+use super::errors::LiteralOpError;
+use super::token::Literal;
+use std::cmp::Ordering;
+
+//A value of a struct declared by the program, eg- Point(1, 2) for struct Point { x, y }
+//the fields are kept in the order they are declared in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    name: String,
+    fields: Vec<(String, Literal)>,
+}
+
+impl Record {
+    pub fn new(name: &str, fields: Vec<(String, Literal)>) -> Self {
+        Self {
+            name: name.to_owned(),
+            fields,
+        }
+    }
+
+    //The name of the struct the record was made from
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get(&self, field: &str) -> Result<&Literal, LiteralOpError> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value)
+            .ok_or(LiteralOpError::UndefinedFieldError)
+    }
+
+    pub fn get_mut(&mut self, field: &str) -> Result<&mut Literal, LiteralOpError> {
+        self.fields
+            .iter_mut()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value)
+            .ok_or(LiteralOpError::UndefinedFieldError)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(String, Literal)> {
+        self.fields.iter()
+    }
+}
+
+//Records only compare as equal or not, like maps
+impl PartialOrd for Record {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self == other).then_some(Ordering::Equal)
+    }
+}
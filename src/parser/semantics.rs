@@ -0,0 +1,27 @@
+//Options changing how programs are evaluated, set on the interpreter and shared by every block
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Semantics {
+    //Only allow booleans in conditions, other values need an explicit bool() or as bool
+    pub strict_conditions: bool,
+    //Don't repeat strings by multiplying them with numbers, eg- "ab" * 2 is an error
+    pub strict_repetition: bool,
+    //Only add strings to strings, eg- 1 + "a" is an error but str(1) + "a" is not
+    pub strict_concatenation: bool,
+    //Check that every variable is declared before it is used, when the program is parsed
+    pub declare_before_use: bool,
+    //Don't declare a variable in a block when a block around it has one of the same name
+    pub no_shadowing: bool,
+}
+
+impl Semantics {
+    //The checks of the strict mode, which turn mistakes that usually run silently into errors
+    pub fn strict() -> Self {
+        Self {
+            strict_conditions: true,
+            strict_repetition: true,
+            strict_concatenation: true,
+            declare_before_use: true,
+            no_shadowing: true,
+        }
+    }
+}
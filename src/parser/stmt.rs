@@ -1,6 +1,5 @@
-use std::collections::HashMap;
-
-use super::{expr::*, token::*};
+use super::expr::*;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub enum Stmt {
@@ -8,115 +7,123 @@ pub enum Stmt {
     Print(Expr),
     //Assign(Identifier, Expression)
     Assign(String, Expr),
+    //Const(Identifier, Expression)
+    //Declare a variable which can't be reassigned or declared again in its scope
+    Const(String, Expr),
+    //Destructure(Identifiers, Expression)
+    //Assign each item of a tuple to an identifier
+    Destructure(Vec<String>, Expr),
     //Reassign(Identifier, Expression)
     //Only assign if the variable exists in scope
     Reassign(String, Expr),
+    //IndexAssign(Identifier, Accessors, Expression), eg- m["a"]["b"] = 1 or p.pos.x = 1
+    //set an item of a map or a field of a record held by a variable, the accessors before the last lead to nested values
+    IndexAssign(String, Vec<Accessor>, Expr),
+    //Statements between braces, run in their own scope
+    Block(Block),
+    //Declare a function in the current scope
+    FnDecl(Rc<Function>),
+    //Declare a struct in the current scope, whose records are made with its name
+    Struct(Rc<Struct>),
+    //Return(Value), leave the running function, a missing value returns none
+    Return(Option<Expr>),
+    //If(Condition, Then, Else)
+    //an else if is kept as an else block holding only the inner if statement
+    If(Expr, Block, Option<Block>),
+    //While(Condition, Body)
+    While(Expr, Block),
+    //DoWhile(Condition, Body)
+    //the condition is checked after each run of the body, so the body runs at least once
+    DoWhile(Expr, Block),
+    //For(Init, Condition, Step, Body)
+    //the init runs once in a scope holding the whole loop, the step after each run of the body
+    For(Option<Box<Stmt>>, Option<Expr>, Option<Box<Stmt>>, Block),
+    Break,
+    Continue,
+    //Exit(Code), stop the program, a missing code exits with 0
+    Exit(Option<Expr>),
+    //Assert(Condition, Message), stop the program with an error if the condition is false
+    Assert(Expr, Option<Expr>),
+    //Try(Body, Name, Catch)
+    //a runtime error in the body stops it and runs the catch block with the error bound to the name
+    Try(Block, String, Block),
+    //Throw(Value), raise an error holding the value, caught by the innermost try around it
+    Throw(Expr),
 }
 
-impl Stmt {
-    //variables: contains the variables in the current scope
-    //print_expr_result: whether to print the result of an an Expr statement (printed in prompt mode)
-    pub fn execute(&self, block: &mut Block, print_expr_result: bool) {
-        match self {
-            Stmt::Print(expr) => {
-                let res = expr.solve(block);
-                match res {
-                    Ok(literal) => println!("{}", literal.to_string()),
-                    Err(err) => {
-                        eprintln!("{:?}", err);
-                    }
-                }
-            }
-            Stmt::Assign(name, expr) => {
-                let res = expr.solve(block);
-                match res {
-                    Ok(value) => block.insert_var(name, value),
-                    Err(err) => {
-                        eprintln!("{:?}", err);
-                    }
-                }
-            }
-            //Reassign only if the current variable exists in scope
-            Stmt::Reassign(name, expr) => {
-                let res = expr.solve(block);
-                match res {
-                    Ok(value) => {
-                        if !block.insert_if_exists(name, value) {
-                            eprintln!("Error: Variable {} does not exist in scope", name);
-                        }
-                    }
-                    Err(err) => {
-                        eprintln!("{:?}", err);
-                    }
-                }
-            }
-            Stmt::Expr(expr) => {
-                let res = expr.solve(block);
-                match res {
-                    Ok(literal) => {
-                        if print_expr_result {
-                            println!("{}", literal.to_string());
-                        }
-                    }
-                    Err(err) => {
-                        eprintln!("{:?}", err);
-                    }
-                }
-            }
-        }
+//A user defined function, shared between its declaration and the scope it is declared in
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Block,
+}
+
+//a function is only equal to itself, so expressions holding functions can still be compared
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
     }
 }
 
-#[derive(Debug)]
-pub struct Block<'a> {
-    pub stmts: Vec<Stmt>,
-    //The list of variables in the scope of the current block
-    pub vars: HashMap<String, Literal>,
-    pub parent: Option<Box<&'a mut Block<'a>>>,
+//A struct declared by the program, eg- struct Point { x, y }
+#[derive(Debug, PartialEq)]
+pub struct Struct {
+    pub name: String,
+    //The names of the fields in the order they are declared, each record holds them in this order
+    pub fields: Vec<String>,
 }
 
-impl<'a> Block<'a> {
-    pub fn new(stmts: Vec<Stmt>, parent: Option<&'a mut Block<'a>>) -> Self {
-        let parent = parent.map(Box::new);
-        Self {
-            stmts,
-            vars: HashMap::new(),
-            parent,
-        }
-    }
+//One step of the path to the value set by an index assignment
+#[derive(Debug, Clone, PartialEq)]
+pub enum Accessor {
+    //An item of a map, eg- ["a"]
+    Index(Expr),
+    //A field of a record, eg- .x
+    Field(String),
+}
 
-    pub fn execute(&mut self, print_expr_result: bool) {
-        let stmts = &self.stmts.clone();
-        for stmt in stmts.iter() {
-            stmt.execute(self, print_expr_result);
+impl Accessor {
+    //The expression of an index, fields have none
+    pub fn index(&self) -> Option<&Expr> {
+        match self {
+            Self::Index(index) => Some(index),
+            Self::Field(_) => None,
         }
     }
 
-    pub fn get_var(&self, name: &str) -> Option<&Literal> {
-        if self.vars.contains_key(name) {
-            return self.vars.get(name);
-        }
-        match &self.parent {
-            Some(parent) => parent.get_var(name),
-            None => None,
+    pub fn index_mut(&mut self) -> Option<&mut Expr> {
+        match self {
+            Self::Index(index) => Some(index),
+            Self::Field(_) => None,
         }
     }
+}
+
+//Identifies a statement and where it starts in the source, for tools mapping runtime events back to code
+//id: Unique within a parsed program, given in source order starting from 0
+//line, start: The position of the statement's first token
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub id: u32,
+    pub line: u32,
+    pub start: u32,
+}
 
-    pub fn insert_var(&mut self, name: &str, value: Literal) {
-        self.vars.insert(name.to_owned(), value);
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    //The span of each statement, in the same order as stmts
+    pub spans: Vec<Span>,
+}
+
+impl Block {
+    pub fn new(stmts: Vec<Stmt>, spans: Vec<Span>) -> Self {
+        Self { stmts, spans }
     }
 
-    //Insert a variable into the block's map only if it exists
-    //Also checks the parent scope and modifies them if it exists in parent scope
-    //Return true if the variable was found and modified
-    pub fn insert_if_exists(&mut self, name: &str, value: Literal) -> bool {
-        if self.vars.contains_key(name) {
-            self.vars.insert(name.to_owned(), value);
-            true
-        } else if let Some(ref mut parent) = self.parent {
-            parent.insert_if_exists(name, value)
-        } else {
-            false
-        }
+    //Iterate over the statements together with their spans
+    pub fn iter(&self) -> impl Iterator<Item = (&Stmt, &Span)> {
+        self.stmts.iter().zip(self.spans.iter())
     }
 }
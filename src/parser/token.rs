@@ -1,4 +1,12 @@
 use super::errors::{LexError, LiteralOpError};
+use super::map::Map;
+use super::record::Record;
+use super::semantics::Semantics;
+use crate::executor::Closure;
+use crate::shared::Channel;
+use std::collections::HashMap;
+use std::fmt;
+use std::num::IntErrorKind;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Token {
@@ -10,6 +18,8 @@ pub struct Token {
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenType {
     Literal(Literal),
+    //A string with ${} interpolations
+    Interpolation(Vec<StringPart>),
     Operator(Operator),
     Unary(Unary),
     Error(LexError),
@@ -19,22 +29,48 @@ pub enum TokenType {
     Ident(String),
     Lparen,
     Rparen,
+    //Braces around a block of statements, or the entries of a map
+    Lbrace,
+    Rbrace,
+    //Brackets around an index, eg- m["key"]
+    Lbracket,
+    Rbracket,
+    //Separates the key and value of a map entry
+    Colon,
+    //Separates the items of a tuple
+    Comma,
+    //Used for tuple index access, eg- t.0
+    Dot,
     // = for assignment
     Assign,
+    //An operator followed by =, eg- += changes a variable by adding to it
+    CompoundAssign(Operator),
+    //++ or -- after a variable, which adds or subtracts one from it
+    Step(Operator),
     //Semicolon or newline used to terminate statements
     StmtEnd,
     Eof,
 }
 
 impl TokenType {
+    //a number too large for an int is an error rather than a wrapped or rounded value
     pub fn new_number_literal(text: &str) -> TokenType {
-        let number = Literal::Number(text.parse().unwrap());
-        Self::Literal(number)
+        match text.parse() {
+            Ok(number) => Self::Literal(Literal::Number(number)),
+            Err(error) if matches!(error.kind(), IntErrorKind::PosOverflow) => {
+                Self::Error(LexError::NumberTooLargeError)
+            }
+            Err(_) => Self::Error(LexError::InvalidTokenError),
+        }
     }
 
+    //a float too large to be represented, eg- 1e999, is an error rather than inf
     pub fn new_float_literal(text: &str) -> TokenType {
-        let float = Literal::Float(text.parse().unwrap());
-        Self::Literal(float)
+        match text.parse::<f32>() {
+            Ok(float) if float.is_finite() => Self::Literal(Literal::Float(float)),
+            Ok(_) => Self::Error(LexError::NumberTooLargeError),
+            Err(_) => Self::Error(LexError::InvalidTokenError),
+        }
     }
 
     pub fn new_string_literal(text: &str) -> TokenType {
@@ -47,6 +83,9 @@ impl TokenType {
             "-" => Self::Operator(Operator::Sub),
             "*" => Self::Operator(Operator::Mul),
             "/" => Self::Operator(Operator::Div),
+            "%" => Self::Operator(Operator::Mod),
+            "**" => Self::Operator(Operator::Pow),
+            "//" => Self::Operator(Operator::FloorDiv),
             ">" => Self::Operator(Operator::Greater),
             "<" => Self::Operator(Operator::Less),
             ">=" => Self::Operator(Operator::GreaterEqual),
@@ -55,6 +94,8 @@ impl TokenType {
             "!=" => Self::Operator(Operator::NotEqual),
             "or" => Self::Operator(Operator::Or),
             "and" => Self::Operator(Operator::And),
+            ".." => Self::Operator(Operator::Range),
+            "..=" => Self::Operator(Operator::RangeInclusive),
             _ => panic!("Invalid operator"),
         }
     }
@@ -70,19 +111,57 @@ impl TokenType {
     //Useful for error messages
     pub fn to_string(&self) -> &str {
         match self {
-            Self::Literal(_) => "a literal",
+            Self::Literal(_) | Self::Interpolation(_) => "a literal",
             Self::Operator(_) => "an operator",
             Self::Unary(_) => "a unary operator",
             Self::Error(_) => "error",
-            Self::Keyword(_) => "a keyword",
+            Self::Keyword(keyword) => keyword.name(),
             Self::Ident(_) => "an identifier",
             Self::Lparen => "(",
             Self::Rparen => ")",
+            Self::Lbrace => "{",
+            Self::Rbrace => "}",
+            Self::Lbracket => "[",
+            Self::Rbracket => "]",
+            Self::Colon => ":",
+            Self::Comma => ",",
+            Self::Dot => ".",
             Self::Assign => "=",
+            Self::CompoundAssign(_) => "a compound assignment",
+            Self::Step(Operator::Sub) => "--",
+            Self::Step(_) => "++",
             Self::StmtEnd => "the end of statement",
             Self::Eof => "the end of file",
         }
     }
+
+    //Check if a token is of the expected class
+    //any identifier or literal matches an expected identifier or literal, whatever its value
+    pub fn is_kind(&self, expected: &TokenType) -> bool {
+        match (self, expected) {
+            (Self::Ident(_), Self::Ident(_)) | (Self::Literal(_), Self::Literal(_)) => true,
+            _ => self == expected,
+        }
+    }
+}
+
+//Whether a name can be used for a variable, made of letters, digits and _ and not reserved by the language
+pub fn is_identifier(name: &str) -> bool {
+    let valid_name = name
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        && name.starts_with(|ch: char| !ch.is_ascii_digit());
+    let reserved = Keyword::new_keyword(name).is_some()
+        || matches!(name, "and" | "or" | "true" | "false" | "none");
+    valid_name && !reserved
+}
+
+//A part of an interpolated string, eg- "a${b}" has the text a and the code b
+//the tokens of code end with an Eof token
+#[derive(Debug, PartialEq, Clone)]
+pub enum StringPart {
+    Text(String),
+    Code(Vec<Token>),
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
@@ -91,23 +170,379 @@ pub enum Literal {
     String(String),
     Float(f32),
     Bool(bool),
+    Tuple(Vec<Literal>),
+    Map(Map),
+    //A function made by a function expression, eg- fn(x) { return x * 2 }
+    Function(Closure),
+    //Range(Start, End), the numbers from start up to but not including end
+    //an inclusive range is kept with the end after its last number, so 1..=9 is 1..10
+    Range(i32, i32),
+    //A queue shared with programs running on other threads
+    Channel(Channel),
+    //A value of a struct declared by the program, eg- Point { x: 1, y: 2 }
+    Record(Record),
+    //The absence of a value, eg- the result of a function without a return
+    None,
 }
 
+//A precision given to the formatter, eg- {:.2}, sets the digits shown after the point of floats
+//it is passed on to the items of tuples and maps and ignored by the other values
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Number(num) => write!(f, "{}", num),
+            Self::String(string) => write!(f, "{}", string),
+            Self::Float(float) => match f.precision() {
+                Some(precision) => write!(f, "{:.*}", precision, float),
+                None => write!(f, "{}", float),
+            },
+            Self::Bool(boolean) => write!(f, "{}", boolean),
+            Self::None => write!(f, "none"),
+            Self::Tuple(items) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.fmt_item(f)?;
+                }
+                //a single item tuple keeps its trailing comma, like it is written
+                if items.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Self::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    key.fmt_item(f)?;
+                    write!(f, ": ")?;
+                    value.fmt_item(f)?;
+                }
+                write!(f, "}}")
+            }
+            Self::Function(closure) => write!(f, "fn({})", closure.function.params.join(", ")),
+            Self::Range(start, end) => write!(f, "{}..{}", start, end),
+            Self::Channel(_) => write!(f, "channel"),
+            Self::Record(record) => {
+                write!(f, "{} {{", record.name())?;
+                for (i, (name, value)) in record.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, " {}: ", name)?;
+                    value.fmt_item(f)?;
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+impl Literal {
+    //Format the value as an item of a tuple or map, keeping the precision of the outer value
+    //strings are quoted so ("a, b", 1) can be told apart from ("a", "b", 1)
+    fn fmt_item(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self, f.precision()) {
+            (Self::String(string), _) => write!(f, "{:?}", string),
+            (_, Some(precision)) => write!(f, "{:.*}", precision, self),
+            (_, None) => write!(f, "{}", self),
+        }
+    }
+}
+
+//The operations can fail with a LiteralOpError, so they are methods instead of std::ops traits
+#[allow(clippy::should_implement_trait)]
 impl Literal {
-    pub fn to_string(&self) -> String {
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Self::Number(_) => ValueType::Number,
+            Self::String(_) => ValueType::String,
+            Self::Float(_) => ValueType::Float,
+            Self::Bool(_) => ValueType::Bool,
+            Self::Tuple(_) => ValueType::Tuple,
+            Self::Map(_) => ValueType::Map,
+            Self::Function(_) => ValueType::Function,
+            Self::Range(..) => ValueType::Range,
+            Self::Channel(_) => ValueType::Channel,
+            Self::Record(_) => ValueType::Record,
+            Self::None => ValueType::None,
+        }
+    }
+
+    //The name of the value's type, as returned by the type() builtin
+    pub fn type_name(&self) -> &'static str {
+        self.value_type().name()
+    }
+
+    //The number of items in a tuple, entries in a map or characters in a string
+    //characters are unicode scalar values, so "é" has one character but two bytes
+    pub fn length(&self) -> Result<usize, LiteralOpError> {
+        match self {
+            Self::String(string) => Ok(string.chars().count()),
+            Self::Tuple(items) => Ok(items.len()),
+            Self::Map(map) => Ok(map.len()),
+            Self::Range(start, end) => Ok((*start..*end).len()),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //The size of a string in bytes, when encoded as UTF-8
+    pub fn byte_len(&self) -> Result<usize, LiteralOpError> {
+        match self {
+            Self::String(string) => Ok(string.len()),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //The values a loop over the value goes through
+    //a string gives each of its characters as a string of its own, and a map gives its keys
+    pub fn items(self) -> Result<Vec<Literal>, LiteralOpError> {
+        match self {
+            Self::String(string) => Ok(string
+                .chars()
+                .map(|ch| Self::String(ch.to_string()))
+                .collect()),
+            Self::Tuple(items) => Ok(items),
+            Self::Map(map) => Ok(map.keys().cloned().collect()),
+            Self::Range(start, end) => Ok((start..end).map(Self::Number).collect()),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //Read an item of the value, eg- m["a"], t[0] or s[0]
+    //maps are indexed by key, tuples by position like a dot index
+    //and strings by the position of a character, which is returned as a string
+    pub fn index(self, index: Literal) -> Result<Literal, LiteralOpError> {
+        match (self, index) {
+            (Self::None, _) => Err(LiteralOpError::NoneValueError),
+            (Self::Map(map), key) => map
+                .get(&key)
+                .cloned()
+                .ok_or(LiteralOpError::UndefinedKeyError),
+            (Self::Tuple(items), Self::Number(position)) => usize::try_from(position)
+                .ok()
+                .and_then(|position| items.into_iter().nth(position))
+                .ok_or(LiteralOpError::IndexOutOfBoundsError),
+            (Self::String(string), Self::Number(position)) => usize::try_from(position)
+                .ok()
+                .and_then(|position| string.chars().nth(position))
+                .map(|ch| Self::String(ch.to_string()))
+                .ok_or(LiteralOpError::IndexOutOfBoundsError),
+            (Self::Range(start, end), Self::Number(position)) => start
+                .checked_add(position)
+                .filter(|num| position >= 0 && *num < end)
+                .map(Self::Number)
+                .ok_or(LiteralOpError::IndexOutOfBoundsError),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //Whether a value is in another, a number in a range, an item of a tuple, a key of a map
+    //or a string in a string
+    pub fn contains(&self, value: &Literal) -> Result<bool, LiteralOpError> {
+        match (self, value) {
+            (Self::None, _) => Err(LiteralOpError::NoneValueError),
+            (Self::Range(start, end), Self::Number(num)) => Ok((*start..*end).contains(num)),
+            (Self::Range(..), _) => Ok(false),
+            (Self::Tuple(items), value) => Ok(items.contains(value)),
+            (Self::Map(map), key) => Ok(map.get(key).is_some()),
+            (Self::String(string), Self::String(part)) => Ok(string.contains(part.as_str())),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //Make a range from this start to an end, eg- the value of 1..10
+    pub fn range(self, end: Literal, inclusive: bool) -> Result<Literal, LiteralOpError> {
+        self.check_none(&end)?;
+        match (self, end) {
+            (Self::Number(start), Self::Number(end)) if inclusive => end
+                .checked_add(1)
+                .map(|end| Self::Range(start, end))
+                .ok_or(LiteralOpError::OverflowError),
+            (Self::Number(start), Self::Number(end)) => Ok(Self::Range(start, end)),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //Take the characters of a string or the items of a tuple from start up to end, eg- s[1:4]
+    //a missing start or end is the start or end of the value
+    pub fn slice(
+        self,
+        start: Option<Literal>,
+        end: Option<Literal>,
+    ) -> Result<Literal, LiteralOpError> {
+        let len = match &self {
+            Self::String(string) => string.chars().count(),
+            Self::Tuple(items) => items.len(),
+            _ => return Err(LiteralOpError::InvalidTypeError),
+        };
+        let position = |bound: Option<Literal>, default: usize| match bound {
+            None => Ok(default),
+            Some(Self::Number(position)) => usize::try_from(position)
+                .ok()
+                .filter(|position| *position <= len)
+                .ok_or(LiteralOpError::IndexOutOfBoundsError),
+            Some(_) => Err(LiteralOpError::InvalidTypeError),
+        };
+        let start = position(start, 0)?;
+        let end = position(end, len)?;
+        if start > end {
+            return Err(LiteralOpError::IndexOutOfBoundsError);
+        }
         match self {
-            Self::Number(num) => num.to_string(),
-            Self::String(string) => string.to_owned(),
-            Self::Float(float) => float.to_string(),
-            Self::Bool(boolean) => boolean.to_string(),
+            Self::String(string) => Ok(Self::String(
+                string.chars().skip(start).take(end - start).collect(),
+            )),
+            Self::Tuple(items) => Ok(Self::Tuple(items[start..end].to_vec())),
+            _ => unreachable!(),
+        }
+    }
+
+    //Read a field of a record, eg- p.x
+    pub fn field(self, name: &str) -> Result<Literal, LiteralOpError> {
+        match self {
+            Self::None => Err(LiteralOpError::NoneValueError),
+            Self::Record(record) => record.get(name).cloned(),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //Get a field of a record to change in place, eg- the p.pos of p.pos.x = 1
+    pub fn field_mut(&mut self, name: &str) -> Result<&mut Literal, LiteralOpError> {
+        match self {
+            Self::Record(record) => record.get_mut(name),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //Get an item of a map to change in place, eg- the m["a"] of m["a"]["b"] = 1
+    pub fn index_mut(&mut self, key: &Literal) -> Result<&mut Literal, LiteralOpError> {
+        match self {
+            Self::Map(map) => map.get_mut(key).ok_or(LiteralOpError::UndefinedKeyError),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //Set the value of a key, only maps can be changed this way since tuples are immutable
+    pub fn set_index(&mut self, key: Literal, value: Literal) -> Result<(), LiteralOpError> {
+        match self {
+            Self::Map(map) => {
+                map.insert(key, value);
+                Ok(())
+            }
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //Convert the value to another type for an as expression
+    pub fn cast(self, to: &ValueType) -> Result<Literal, LiteralOpError> {
+        match to {
+            ValueType::Number => match self {
+                Literal::Number(_) => Ok(self),
+                //the fraction is dropped, a float outside of the numbers can't be converted
+                Literal::Float(num) if (-2147483648.0..2147483648.0).contains(&num) => {
+                    Ok(Literal::Number(num as i32))
+                }
+                Literal::Float(_) => Err(LiteralOpError::OverflowError),
+                Literal::Bool(boolean) => Ok(Literal::Number(boolean as i32)),
+                Literal::String(str) => str
+                    .trim()
+                    .parse()
+                    .map(Literal::Number)
+                    .map_err(|_| LiteralOpError::ConversionError),
+                Literal::Tuple(_)
+                | Literal::Map(_)
+                | Literal::Function(_)
+                | Literal::Range(..)
+                | Literal::Channel(_)
+                | Literal::Record(_)
+                | Literal::None => Err(LiteralOpError::ConversionError),
+            },
+            ValueType::Float => match self {
+                Literal::Number(num) => Ok(Literal::Float(num as f32)),
+                Literal::Float(_) => Ok(self),
+                Literal::Bool(boolean) => Ok(Literal::Float(boolean as i32 as f32)),
+                Literal::String(str) => str
+                    .trim()
+                    .parse()
+                    .map(Literal::Float)
+                    .map_err(|_| LiteralOpError::ConversionError),
+                Literal::Tuple(_)
+                | Literal::Map(_)
+                | Literal::Function(_)
+                | Literal::Range(..)
+                | Literal::Channel(_)
+                | Literal::Record(_)
+                | Literal::None => Err(LiteralOpError::ConversionError),
+            },
+            ValueType::String => Ok(Literal::String(self.to_string())),
+            ValueType::Bool => Ok(Literal::Bool(self.is_truthy())),
+            //A range becomes a tuple of its numbers
+            ValueType::Tuple => match self {
+                Literal::Tuple(_) => Ok(self),
+                Literal::Range(..) => Ok(Literal::Tuple(self.items()?)),
+                _ => Err(LiteralOpError::ConversionError),
+            },
+            ValueType::Range => match self {
+                Literal::Range(..) => Ok(self),
+                _ => Err(LiteralOpError::ConversionError),
+            },
+            ValueType::Channel => match self {
+                Literal::Channel(_) => Ok(self),
+                _ => Err(LiteralOpError::ConversionError),
+            },
+            ValueType::Map => match self {
+                Literal::Map(_) => Ok(self),
+                _ => Err(LiteralOpError::ConversionError),
+            },
+            ValueType::Function => match self {
+                Literal::Function(_) => Ok(self),
+                _ => Err(LiteralOpError::ConversionError),
+            },
+            ValueType::Record => match self {
+                Literal::Record(_) => Ok(self),
+                _ => Err(LiteralOpError::ConversionError),
+            },
+            ValueType::None => match self {
+                Literal::None => Ok(self),
+                _ => Err(LiteralOpError::ConversionError),
+            },
+        }
+    }
+
+    //none stands for a missing value, so operations on it report that instead of a type error
+    fn check_none(&self, other: &Literal) -> Result<(), LiteralOpError> {
+        match (self, other) {
+            (Literal::None, _) | (_, Literal::None) => Err(LiteralOpError::NoneValueError),
+            _ => Ok(()),
+        }
+    }
+
+    //Add without converting the other value when one of them is a string, for the strict semantics
+    pub fn add_strings(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        match (&self, &other) {
+            (Literal::String(_), Literal::String(_)) => self.add(other),
+            (Literal::String(_), _) | (_, Literal::String(_)) => {
+                self.check_none(&other)?;
+                Err(LiteralOpError::InvalidTypeError)
+            }
+            _ => self.add(other),
         }
     }
 
     pub fn add(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        self.check_none(&other)?;
         match self {
             //Number can add other numbers, strings and floats
             Literal::Number(num1) => match other {
-                Literal::Number(num2) => Ok(Self::Number(num1 + num2)),
+                Literal::Number(num2) => num1
+                    .checked_add(num2)
+                    .map(Self::Number)
+                    .ok_or(LiteralOpError::OverflowError),
                 Literal::String(str) => Ok(Self::String(num1.to_string() + &str)),
                 Literal::Float(num2) => Ok(Self::Float(num1 as f32 + num2)),
                 _ => Err(LiteralOpError::InvalidTypeError),
@@ -118,6 +553,13 @@ impl Literal {
                 Literal::String(str2) => Ok(Self::String(str1 + &str2)),
                 Literal::Float(num) => Ok(Self::String(str1 + &num.to_string())),
                 Literal::Bool(boolean) => Ok(Self::String(str1 + &boolean.to_string())),
+                Literal::Tuple(_)
+                | Literal::Map(_)
+                | Literal::Function(_)
+                | Literal::Range(..)
+                | Literal::Channel(_)
+                | Literal::Record(_) => Ok(Self::String(str1 + &other.to_string())),
+                Literal::None => Err(LiteralOpError::NoneValueError),
             },
             //Floats are similar to numbers and can be added to strings, numbers and other floats
             Literal::Float(num1) => match other {
@@ -131,14 +573,29 @@ impl Literal {
                 Literal::String(str) => Ok(Self::String(boolean.to_string() + &str)),
                 _ => Err(LiteralOpError::InvalidTypeError),
             },
+            //Tuples, maps, functions, ranges, channels and records can also only be added to a string
+            Literal::Tuple(_)
+            | Literal::Map(_)
+            | Literal::Function(_)
+            | Literal::Range(..)
+            | Literal::Channel(_)
+            | Literal::Record(_) => match other {
+                Literal::String(str) => Ok(Self::String(self.to_string() + &str)),
+                _ => Err(LiteralOpError::InvalidTypeError),
+            },
+            Literal::None => Err(LiteralOpError::NoneValueError),
         }
     }
 
     pub fn sub(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        self.check_none(&other)?;
         //can only substract numbers and floats
         match self {
             Literal::Number(num1) => match other {
-                Literal::Number(num2) => Ok(Literal::Number(num1 - num2)),
+                Literal::Number(num2) => num1
+                    .checked_sub(num2)
+                    .map(Literal::Number)
+                    .ok_or(LiteralOpError::OverflowError),
                 Literal::Float(num2) => Ok(Literal::Float(num1 as f32 - num2)),
                 _ => Err(LiteralOpError::InvalidTypeError),
             },
@@ -151,11 +608,25 @@ impl Literal {
         }
     }
 
+    //Multiply without repeating strings, for the strict semantics
+    pub fn mul_numbers(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        match (&self, &other) {
+            (Literal::String(_), _) | (_, Literal::String(_)) => {
+                Err(LiteralOpError::InvalidTypeError)
+            }
+            _ => self.mul(other),
+        }
+    }
+
     pub fn mul(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        self.check_none(&other)?;
         match self {
             //Number can be multiplied to numbers, floats and strings
             Literal::Number(num1) => match other {
-                Literal::Number(num2) => Ok(Self::Number(num1 * num2)),
+                Literal::Number(num2) => num1
+                    .checked_mul(num2)
+                    .map(Self::Number)
+                    .ok_or(LiteralOpError::OverflowError),
                 Literal::String(str) => {
                     let mut new_string = String::new();
                     for _ in 0..num1 {
@@ -188,6 +659,13 @@ impl Literal {
     }
 
     pub fn div(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        self.check_none(&other)?;
+        //dividing by zero is an error rather than inf or NaN
+        if let (Literal::Number(_) | Literal::Float(_), Literal::Number(0) | Literal::Float(0.0)) =
+            (&self, &other)
+        {
+            return Err(LiteralOpError::DivByZeroError);
+        }
         //can only divide numbers and floats
         match self {
             Literal::Number(num1) => {
@@ -207,7 +685,77 @@ impl Literal {
         }
     }
 
+    //Raise a number to a power
+    //numbers stay numbers unless the power is negative, results too large for a number are errors
+    pub fn pow(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        self.check_none(&other)?;
+        match (self, other) {
+            (Literal::Number(num1), Literal::Number(num2)) => match u32::try_from(num2) {
+                Ok(power) => num1
+                    .checked_pow(power)
+                    .map(Literal::Number)
+                    .ok_or(LiteralOpError::OverflowError),
+                Err(_) => Ok(Literal::Float((num1 as f32).powi(num2))),
+            },
+            (Literal::Number(num1), Literal::Float(num2)) => {
+                Ok(Literal::Float((num1 as f32).powf(num2)))
+            }
+            (Literal::Float(num1), Literal::Number(num2)) => Ok(Literal::Float(num1.powi(num2))),
+            (Literal::Float(num1), Literal::Float(num2)) => Ok(Literal::Float(num1.powf(num2))),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //Divide and round down, towards negative infinity
+    //two numbers give a number, a float on either side gives a float holding a whole number
+    pub fn floor_div(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        self.check_none(&other)?;
+        match (self, other) {
+            (Literal::Number(_) | Literal::Float(_), Literal::Number(0))
+            | (Literal::Number(_) | Literal::Float(_), Literal::Float(0.0)) => {
+                Err(LiteralOpError::DivByZeroError)
+            }
+            (Literal::Number(num1), Literal::Number(num2)) => {
+                let quotient = num1
+                    .checked_div(num2)
+                    .ok_or(LiteralOpError::OverflowError)?;
+                //integer division rounds towards zero, so a negative inexact quotient is one too high
+                if num1 % num2 != 0 && (num1 < 0) != (num2 < 0) {
+                    Ok(Literal::Number(quotient - 1))
+                } else {
+                    Ok(Literal::Number(quotient))
+                }
+            }
+            (left @ (Literal::Number(_) | Literal::Float(_)), right) => match left.div(right)? {
+                Literal::Float(quotient) => Ok(Literal::Float(quotient.floor())),
+                quotient => Ok(quotient),
+            },
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
+    //The remainder of dividing two numbers, it has the sign of the left number
+    //two numbers give a number, a float on either side gives a float
+    pub fn modulo(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        self.check_none(&other)?;
+        match (self, other) {
+            (Literal::Number(_) | Literal::Float(_), Literal::Number(0))
+            | (Literal::Number(_) | Literal::Float(_), Literal::Float(0.0)) => {
+                Err(LiteralOpError::DivByZeroError)
+            }
+            //wrapping only matters for i32::MIN % -1, which is 0
+            (Literal::Number(num1), Literal::Number(num2)) => {
+                Ok(Literal::Number(num1.wrapping_rem(num2)))
+            }
+            (Literal::Number(num1), Literal::Float(num2)) => Ok(Literal::Float(num1 as f32 % num2)),
+            (Literal::Float(num1), Literal::Number(num2)) => Ok(Literal::Float(num1 % num2 as f32)),
+            (Literal::Float(num1), Literal::Float(num2)) => Ok(Literal::Float(num1 % num2)),
+            _ => Err(LiteralOpError::InvalidTypeError),
+        }
+    }
+
     pub fn greater(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        self.check_none(&other)?;
         match self {
             Literal::Number(num1) => match other {
                 Literal::Number(num2) => Ok(Literal::Bool(num1 > num2)),
@@ -224,6 +772,7 @@ impl Literal {
     }
 
     pub fn less(self, other: Literal) -> Result<Literal, LiteralOpError> {
+        self.check_none(&other)?;
         match self {
             Literal::Number(num1) => match other {
                 Literal::Number(num2) => Ok(Literal::Bool(num1 < num2)),
@@ -269,12 +818,26 @@ impl Literal {
 
     pub fn negate(self) -> Result<Literal, LiteralOpError> {
         match self {
-            Literal::Number(num) => Ok(Literal::Number(-num)),
+            Literal::Number(num) => num
+                .checked_neg()
+                .map(Literal::Number)
+                .ok_or(LiteralOpError::OverflowError),
             Literal::Float(num) => Ok(Literal::Float(-num)),
+            Literal::None => Err(LiteralOpError::NoneValueError),
             _ => Err(LiteralOpError::InvalidTypeError),
         }
     }
 
+    //The truthiness of the value when used as a condition
+    //strict conditions only accept booleans
+    pub fn condition(&self, semantics: &Semantics) -> Result<bool, LiteralOpError> {
+        match self {
+            Literal::Bool(boolean) => Ok(*boolean),
+            _ if semantics.strict_conditions => Err(LiteralOpError::InvalidTypeError),
+            _ => Ok(self.is_truthy()),
+        }
+    }
+
     pub fn is_truthy(&self) -> bool {
         //Numbers and floats are false if they are 0
         //Empty string are false
@@ -283,6 +846,63 @@ impl Literal {
             Literal::String(str) => !str.is_empty(),
             Literal::Float(num) => *num != 0.0,
             Literal::Bool(boolean) => boolean.to_owned(),
+            //Tuples and maps are false when they have no items
+            Literal::Tuple(items) => !items.is_empty(),
+            Literal::Map(map) => !map.is_empty(),
+            Literal::Function(_) | Literal::Channel(_) | Literal::Record(_) => true,
+            Literal::Range(start, end) => start < end,
+            Literal::None => false,
+        }
+    }
+}
+
+//The types a value can have, used by is and as expressions
+#[derive(Debug, PartialEq, Clone)]
+pub enum ValueType {
+    Number,
+    Float,
+    String,
+    Bool,
+    Tuple,
+    Map,
+    Function,
+    Range,
+    Channel,
+    Record,
+    None,
+}
+
+impl ValueType {
+    pub fn new_value_type(name: &str) -> Option<Self> {
+        match name {
+            "number" => Some(Self::Number),
+            "float" => Some(Self::Float),
+            "string" => Some(Self::String),
+            "bool" => Some(Self::Bool),
+            "tuple" => Some(Self::Tuple),
+            "map" => Some(Self::Map),
+            "function" => Some(Self::Function),
+            "range" => Some(Self::Range),
+            "channel" => Some(Self::Channel),
+            "record" => Some(Self::Record),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Number => "number",
+            Self::Float => "float",
+            Self::String => "string",
+            Self::Bool => "bool",
+            Self::Tuple => "tuple",
+            Self::Map => "map",
+            Self::Function => "function",
+            Self::Range => "range",
+            Self::Channel => "channel",
+            Self::Record => "record",
+            Self::None => "none",
         }
     }
 }
@@ -293,6 +913,11 @@ pub enum Operator {
     Add,
     Mul,
     Div,
+    //The remainder of dividing integers
+    Mod,
+    //Division rounded down to a whole number, eg- 7 // 2 is 3
+    FloorDiv,
+    Pow,
     Greater,
     Less,
     GreaterEqual,
@@ -301,19 +926,48 @@ pub enum Operator {
     NotEqual,
     Or,
     And,
+    //The numbers from a start up to an end, eg- 1..10, the end is included with ..=
+    Range,
+    RangeInclusive,
 }
 
 impl Operator {
-    pub fn precedence(&self) -> u8{
+    pub fn precedence(&self) -> u8 {
         match self {
             Self::Or => 1,
             Self::And => 2,
             Self::Equal | Self::NotEqual => 3,
             Self::Greater | Self::Less | Self::GreaterEqual | Self::LessEqual => 4,
-            Self::Add | Self::Sub => 5,
-            Self::Mul | Self::Div => 6,
+            Self::Range | Self::RangeInclusive => 5,
+            Self::Add | Self::Sub => 6,
+            Self::Mul | Self::Div | Self::Mod | Self::FloorDiv => 7,
+            Self::Pow => 8,
+        }
+    }
+
+    //The comparisons which can be chained, eg- 1 < x <= 10
+    pub fn is_ordering(&self) -> bool {
+        matches!(
+            self,
+            Self::Greater | Self::Less | Self::GreaterEqual | Self::LessEqual
+        )
+    }
+
+    //Compare two values with an ordering operator
+    pub fn compare(&self, left: Literal, right: Literal) -> Result<Literal, LiteralOpError> {
+        match self {
+            Self::Greater => left.greater(right),
+            Self::Less => left.less(right),
+            Self::GreaterEqual => left.greater_equal(right),
+            Self::LessEqual => left.less_equal(right),
+            _ => unreachable!("only ordering operators compare values"),
         }
     }
+
+    //Right associative operators group from the right, so 2 ** 3 ** 2 is 2 ** (3 ** 2)
+    pub fn is_right_associative(&self) -> bool {
+        *self == Self::Pow
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -327,6 +981,37 @@ pub enum Keyword {
     Print,
     //Keyword to declare identifier
     Let,
+    //Declare a variable which can't be assigned again
+    Const,
+    //Type check, eg- x is number
+    Is,
+    //Type conversion, eg- x as string
+    As,
+    //Keyword to declare a function
+    Fn,
+    //Leave a function, with an optional value
+    Return,
+    If,
+    Else,
+    While,
+    //Start a loop whose condition is checked after its body, eg- do { ... } while a
+    Do,
+    For,
+    //Leave the innermost loop
+    Break,
+    //Skip to the next iteration of the innermost loop
+    Continue,
+    //Stop the program, with an optional exit code
+    Exit,
+    //Stop the program if a condition is false, eg- assert len(t) == 2, "t is a pair"
+    Assert,
+    //Run a block and recover from its runtime errors, eg- try { ... } catch (e) { ... }
+    Try,
+    Catch,
+    //Raise an error with any value, eg- throw "no such user"
+    Throw,
+    //Declare a struct, eg- struct Point { x, y }
+    Struct,
 }
 
 impl Keyword {
@@ -334,9 +1019,80 @@ impl Keyword {
         match text {
             "print" => Some(Self::Print),
             "let" => Some(Self::Let),
+            "const" => Some(Self::Const),
+            "is" => Some(Self::Is),
+            "as" => Some(Self::As),
+            "fn" => Some(Self::Fn),
+            "return" => Some(Self::Return),
+            "if" => Some(Self::If),
+            "else" => Some(Self::Else),
+            "while" => Some(Self::While),
+            "do" => Some(Self::Do),
+            "for" => Some(Self::For),
+            "break" => Some(Self::Break),
+            "continue" => Some(Self::Continue),
+            "exit" => Some(Self::Exit),
+            "assert" => Some(Self::Assert),
+            "try" => Some(Self::Try),
+            "catch" => Some(Self::Catch),
+            "throw" => Some(Self::Throw),
+            "struct" => Some(Self::Struct),
             _ => None,
         }
     }
+
+    //The spelling of the keyword in the language, also used for its aliases in error messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Print => "print",
+            Self::Let => "let",
+            Self::Const => "const",
+            Self::Is => "is",
+            Self::As => "as",
+            Self::Fn => "fn",
+            Self::Return => "return",
+            Self::If => "if",
+            Self::Else => "else",
+            Self::While => "while",
+            Self::Do => "do",
+            Self::For => "for",
+            Self::Break => "break",
+            Self::Continue => "continue",
+            Self::Exit => "exit",
+            Self::Assert => "assert",
+            Self::Try => "try",
+            Self::Catch => "catch",
+            Self::Throw => "throw",
+            Self::Struct => "struct",
+        }
+    }
+}
+
+//Other spellings of keywords, eg- to teach programming with keywords in the students' language
+//an alias is lexed as the keyword it stands for, so errors name the keyword and not the alias
+#[derive(Debug, Default, Clone)]
+pub struct KeywordAliases {
+    aliases: HashMap<String, Keyword>,
+}
+
+impl KeywordAliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //Add an alias, returns false if it was not added
+    //the alias has to be a valid identifier name which is not already reserved by the language
+    pub fn add(&mut self, alias: &str, keyword: Keyword) -> bool {
+        if !is_identifier(alias) {
+            return false;
+        }
+        self.aliases.insert(alias.to_owned(), keyword);
+        true
+    }
+
+    pub fn get(&self, alias: &str) -> Option<&Keyword> {
+        self.aliases.get(alias)
+    }
 }
 
 #[cfg(test)]
@@ -350,4 +1106,229 @@ mod tests {
             TokenType::new_number_literal("17")
         );
     }
+
+    #[test]
+    fn power_and_floor_division() {
+        let number = Literal::Number;
+        let float = Literal::Float;
+        assert_eq!(number(2).pow(number(10)), Ok(number(1024)));
+        assert_eq!(number(-3).pow(number(3)), Ok(number(-27)));
+        assert_eq!(number(2).pow(number(-2)), Ok(float(0.25)));
+        assert_eq!(number(4).pow(float(0.5)), Ok(float(2.0)));
+        assert_eq!(float(1.5).pow(number(2)), Ok(float(2.25)));
+        assert_eq!(
+            number(2).pow(number(31)),
+            Err(LiteralOpError::OverflowError)
+        );
+        assert_eq!(
+            Literal::String("a".to_owned()).pow(number(2)),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+
+        //rounding is towards negative infinity, not zero
+        assert_eq!(number(7).floor_div(number(2)), Ok(number(3)));
+        assert_eq!(number(-7).floor_div(number(2)), Ok(number(-4)));
+        assert_eq!(number(7).floor_div(number(-2)), Ok(number(-4)));
+        assert_eq!(number(-6).floor_div(number(2)), Ok(number(-3)));
+        assert_eq!(float(7.5).floor_div(number(2)), Ok(float(3.0)));
+        assert_eq!(number(-7).floor_div(float(2.0)), Ok(float(-4.0)));
+        assert_eq!(
+            number(1).floor_div(number(0)),
+            Err(LiteralOpError::DivByZeroError)
+        );
+        assert_eq!(
+            float(1.0).floor_div(float(0.0)),
+            Err(LiteralOpError::DivByZeroError)
+        );
+        assert_eq!(
+            number(i32::MIN).floor_div(number(-1)),
+            Err(LiteralOpError::OverflowError)
+        );
+    }
+
+    #[test]
+    fn integer_overflow() {
+        let number = Literal::Number;
+        let overflow = Err(LiteralOpError::OverflowError);
+        assert_eq!(number(i32::MAX).add(number(1)), overflow);
+        assert_eq!(number(i32::MIN).sub(number(1)), overflow);
+        assert_eq!(number(i32::MAX).mul(number(2)), overflow);
+        assert_eq!(number(i32::MIN).negate(), overflow);
+        assert_eq!(number(i32::MAX - 1).add(number(1)), Ok(number(i32::MAX)));
+        assert_eq!(number(i32::MIN + 1).negate(), Ok(number(i32::MAX)));
+    }
+
+    #[test]
+    fn division_and_modulo() {
+        let number = Literal::Number;
+        let float = Literal::Float;
+        assert_eq!(number(7).div(number(2)), Ok(float(3.5)));
+        assert_eq!(
+            number(1).div(number(0)),
+            Err(LiteralOpError::DivByZeroError)
+        );
+        assert_eq!(
+            float(1.5).div(float(-0.0)),
+            Err(LiteralOpError::DivByZeroError)
+        );
+        assert_eq!(
+            number(0).div(float(0.0)),
+            Err(LiteralOpError::DivByZeroError)
+        );
+
+        //the remainder has the sign of the left number
+        assert_eq!(number(-7).modulo(number(3)), Ok(number(-1)));
+        assert_eq!(float(7.5).modulo(number(2)), Ok(float(1.5)));
+        assert_eq!(number(-7).modulo(float(2.5)), Ok(float(-2.0)));
+        assert_eq!(float(5.25).modulo(float(-2.0)), Ok(float(1.25)));
+        assert_eq!(number(i32::MIN).modulo(number(-1)), Ok(number(0)));
+        assert_eq!(
+            number(1).modulo(number(0)),
+            Err(LiteralOpError::DivByZeroError)
+        );
+        assert_eq!(
+            float(1.0).modulo(float(0.0)),
+            Err(LiteralOpError::DivByZeroError)
+        );
+        assert_eq!(
+            Literal::String("a".to_owned()).modulo(number(2)),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+    }
+
+    #[test]
+    fn cast_literals() {
+        let string = |s: &str| Literal::String(s.to_owned());
+        assert_eq!(
+            string(" 12 ").cast(&ValueType::Number),
+            Ok(Literal::Number(12))
+        );
+        assert_eq!(
+            string("2.5").cast(&ValueType::Float),
+            Ok(Literal::Float(2.5))
+        );
+        assert_eq!(
+            Literal::Float(2.9).cast(&ValueType::Number),
+            Ok(Literal::Number(2))
+        );
+        assert_eq!(
+            Literal::Bool(true).cast(&ValueType::Number),
+            Ok(Literal::Number(1))
+        );
+        assert_eq!(Literal::Number(3).cast(&ValueType::String), Ok(string("3")));
+        assert_eq!(string("").cast(&ValueType::Bool), Ok(Literal::Bool(false)));
+        assert_eq!(
+            string("2.5").cast(&ValueType::Number),
+            Err(LiteralOpError::ConversionError)
+        );
+        assert_eq!(
+            Literal::Number(1).cast(&ValueType::Tuple),
+            Err(LiteralOpError::ConversionError)
+        );
+    }
+
+    #[test]
+    fn none_values() {
+        let none = Literal::None;
+        assert_eq!(none.to_string(), "none");
+        assert!(!none.is_truthy());
+        assert_eq!(none.clone().equal(Literal::None), Literal::Bool(true));
+        assert_eq!(
+            none.clone().equal(Literal::Bool(false)),
+            Literal::Bool(false)
+        );
+
+        //arithmetic and comparisons on none report it, whatever the other operand is
+        assert_eq!(
+            none.clone().add(Literal::Number(1)),
+            Err(LiteralOpError::NoneValueError)
+        );
+        assert_eq!(
+            Literal::String("a".to_owned()).add(none.clone()),
+            Err(LiteralOpError::NoneValueError)
+        );
+        assert_eq!(
+            Literal::Number(1).less_equal(none.clone()),
+            Err(LiteralOpError::NoneValueError)
+        );
+        assert_eq!(none.clone().negate(), Err(LiteralOpError::NoneValueError));
+
+        assert_eq!(
+            none.clone().cast(&ValueType::String),
+            Ok(Literal::String("none".to_owned()))
+        );
+        assert_eq!(
+            none.cast(&ValueType::Number),
+            Err(LiteralOpError::ConversionError)
+        );
+        assert_eq!(ValueType::new_value_type("none"), Some(ValueType::None));
+    }
+
+    #[test]
+    fn string_indexes() {
+        let string = |s: &str| Literal::String(s.to_owned());
+        let number = |n: i32| Some(Literal::Number(n));
+        //positions count characters, not bytes
+        assert_eq!(string("héllo").index(Literal::Number(1)), Ok(string("é")));
+        assert_eq!(
+            string("héllo").index(Literal::Number(5)),
+            Err(LiteralOpError::IndexOutOfBoundsError)
+        );
+        assert_eq!(
+            string("héllo").index(Literal::Number(-1)),
+            Err(LiteralOpError::IndexOutOfBoundsError)
+        );
+        assert_eq!(
+            string("héllo").index(string("h")),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+
+        assert_eq!(
+            string("héllo").slice(number(1), number(4)),
+            Ok(string("éll"))
+        );
+        assert_eq!(string("héllo").slice(None, number(2)), Ok(string("hé")));
+        assert_eq!(string("héllo").slice(number(5), None), Ok(string("")));
+        assert_eq!(
+            string("héllo").slice(number(3), number(2)),
+            Err(LiteralOpError::IndexOutOfBoundsError)
+        );
+        assert_eq!(
+            string("héllo").slice(None, number(6)),
+            Err(LiteralOpError::IndexOutOfBoundsError)
+        );
+        let tuple = Literal::Tuple(vec![Literal::Number(1), Literal::Bool(true)]);
+        assert_eq!(
+            tuple.slice(number(1), None),
+            Ok(Literal::Tuple(vec![Literal::Bool(true)]))
+        );
+        assert_eq!(
+            Literal::Number(5).slice(None, None),
+            Err(LiteralOpError::InvalidTypeError)
+        );
+    }
+
+    #[test]
+    fn float_precision() {
+        assert_eq!(format!("{:.2}", Literal::Float(2.0 / 3.0)), "0.67");
+        assert_eq!(format!("{:.0}", Literal::Float(2.5)), "2");
+        assert_eq!(format!("{}", Literal::Float(0.1)), "0.1");
+        //only floats are affected, inside tuples too
+        let tuple = Literal::Tuple(vec![
+            Literal::Float(1.0),
+            Literal::Number(7),
+            Literal::String(String::from("text")),
+        ]);
+        assert_eq!(format!("{:.3}", tuple), "(1.000, 7, \"text\")");
+    }
+
+    #[test]
+    fn token_kinds() {
+        let ident = |s: &str| TokenType::Ident(s.to_owned());
+        assert!(ident("a").is_kind(&ident("")));
+        assert!(TokenType::new_number_literal("1").is_kind(&TokenType::new_string_literal("")));
+        assert!(TokenType::Assign.is_kind(&TokenType::Assign));
+        assert!(!TokenType::Assign.is_kind(&ident("")));
+        assert!(!TokenType::Keyword(Keyword::Let).is_kind(&TokenType::Keyword(Keyword::Print)));
+    }
 }
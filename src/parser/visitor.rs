@@ -0,0 +1,187 @@
+use super::expr::Expr;
+use super::stmt::{Accessor, Block, Stmt};
+
+//Traverses expressions, override visit_expr to inspect nodes and call walk_expr to visit the children
+pub trait ExprVisitor {
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+}
+
+//Traverses statements and the expressions inside them
+pub trait StmtVisitor: ExprVisitor {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+}
+
+//Visit the direct children of an expression
+pub fn walk_expr<V: ExprVisitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        //the body of a function value is made of statements, which only a StmtVisitor can visit
+        Expr::Ident(_) | Expr::Literal(_) | Expr::Function(_) => {}
+        Expr::Div(left, right, _)
+        | Expr::Mul(left, right, _)
+        | Expr::Mod(left, right, _)
+        | Expr::FloorDiv(left, right, _)
+        | Expr::Pow(left, right, _)
+        | Expr::Add(left, right, _)
+        | Expr::Sub(left, right, _)
+        | Expr::Greater(left, right)
+        | Expr::Less(left, right)
+        | Expr::GreaterEqual(left, right)
+        | Expr::LessEqual(left, right)
+        | Expr::Equal(left, right)
+        | Expr::NotEqual(left, right)
+        | Expr::And(left, right)
+        | Expr::Or(left, right)
+        | Expr::Index(left, right)
+        | Expr::Range(left, right, _) => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::Not(expr)
+        | Expr::Negate(expr, _)
+        | Expr::TupleIndex(expr, _)
+        | Expr::Field(expr, _)
+        | Expr::Is(expr, _)
+        | Expr::Cast(expr, _) => visitor.visit_expr(expr),
+        Expr::Record(_, fields) => {
+            for (_, value) in fields {
+                visitor.visit_expr(value);
+            }
+        }
+        Expr::Compare(first, comparisons) => {
+            visitor.visit_expr(first);
+            for (_, operand) in comparisons {
+                visitor.visit_expr(operand);
+            }
+        }
+        Expr::Tuple(exprs) | Expr::Interpolation(exprs) | Expr::Call(_, exprs) => {
+            for expr in exprs {
+                visitor.visit_expr(expr);
+            }
+        }
+        Expr::Slice(value, start, end) => {
+            visitor.visit_expr(value);
+            for bound in [start, end].into_iter().flatten() {
+                visitor.visit_expr(bound);
+            }
+        }
+        Expr::Map(entries) => {
+            for (key, value) in entries {
+                visitor.visit_expr(key);
+                visitor.visit_expr(value);
+            }
+        }
+    }
+}
+
+//Visit the expressions of a statement
+pub fn walk_stmt<V: StmtVisitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::Expr(expr)
+        | Stmt::Print(expr)
+        | Stmt::Assign(_, expr)
+        | Stmt::Const(_, expr)
+        | Stmt::Destructure(_, expr)
+        | Stmt::Reassign(_, expr) => visitor.visit_expr(expr),
+        Stmt::IndexAssign(_, accessors, expr) => {
+            for index in accessors.iter().filter_map(Accessor::index) {
+                visitor.visit_expr(index);
+            }
+            visitor.visit_expr(expr);
+        }
+        Stmt::Block(block) => visitor.visit_block(block),
+        Stmt::FnDecl(function) => visitor.visit_block(&function.body),
+        Stmt::Return(Some(expr)) | Stmt::Exit(Some(expr)) | Stmt::Throw(expr) => {
+            visitor.visit_expr(expr)
+        }
+        Stmt::If(condition, then, otherwise) => {
+            visitor.visit_expr(condition);
+            visitor.visit_block(then);
+            if let Some(otherwise) = otherwise {
+                visitor.visit_block(otherwise);
+            }
+        }
+        Stmt::While(condition, body) => {
+            visitor.visit_expr(condition);
+            visitor.visit_block(body);
+        }
+        Stmt::DoWhile(condition, body) => {
+            visitor.visit_block(body);
+            visitor.visit_expr(condition);
+        }
+        Stmt::For(init, condition, step, body) => {
+            if let Some(init) = init {
+                visitor.visit_stmt(init);
+            }
+            if let Some(condition) = condition {
+                visitor.visit_expr(condition);
+            }
+            if let Some(step) = step {
+                visitor.visit_stmt(step);
+            }
+            visitor.visit_block(body);
+        }
+        Stmt::Assert(condition, message) => {
+            visitor.visit_expr(condition);
+            if let Some(message) = message {
+                visitor.visit_expr(message);
+            }
+        }
+        Stmt::Try(body, _, catch) => {
+            visitor.visit_block(body);
+            visitor.visit_block(catch);
+        }
+        Stmt::Struct(_) | Stmt::Return(None) | Stmt::Exit(None) | Stmt::Break | Stmt::Continue => {}
+    }
+}
+
+//Visit every statement of a block in order
+pub fn walk_block<V: StmtVisitor + ?Sized>(visitor: &mut V, block: &Block) {
+    for stmt in block.stmts.iter() {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{lexer::Lexer, parser::Parser};
+    use super::*;
+
+    //Collects the names of the variables read by a program
+    struct IdentCollector {
+        names: Vec<String>,
+    }
+
+    impl ExprVisitor for IdentCollector {
+        fn visit_expr(&mut self, expr: &Expr) {
+            if let Expr::Ident(name) = expr {
+                self.names.push(name.to_owned());
+            }
+            walk_expr(self, expr);
+        }
+    }
+
+    impl StmtVisitor for IdentCollector {}
+
+    #[test]
+    fn collect_idents() {
+        let tokens = Lexer::new(
+            "let a = b + (c, -d.0)\nprint type(e) is string\nf = g\nfn h() { return i }\nwhile j { if k { l } else { m } }",
+        )
+        .lex();
+        let block = Parser::new(&tokens).parse().unwrap();
+        let mut collector = IdentCollector { names: Vec::new() };
+        collector.visit_block(&block);
+        assert_eq!(
+            collector.names,
+            ["b", "c", "d", "e", "g", "i", "j", "k", "l", "m"]
+        );
+    }
+}
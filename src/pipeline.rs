@@ -0,0 +1,353 @@
+use crate::errors::{apply_fixes, Fix, Warning};
+use crate::executor::Executor;
+use crate::lexer::Lexer;
+use crate::output::{Event, Phase};
+use crate::parser::analyzer::Analyzer;
+use crate::parser::expr::Expr;
+use crate::parser::linter::{LintConfig, Linter};
+use crate::parser::optimizer::Optimizer;
+use crate::parser::parser::Parser;
+use crate::parser::stmt::{Block, Stmt};
+use crate::parser::visitor::{walk_expr, walk_stmt, ExprVisitor, StmtVisitor};
+use crate::source::normalize_source;
+use crate::token::{Literal, TokenType};
+use std::ops::BitOr;
+use std::time::{Duration, Instant};
+
+//The phases of running a program which pipeline::run goes through
+//each phase needs the ones before it, except analysis which only finds warnings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseMask(u8);
+
+impl PhaseMask {
+    pub const LEX: Self = Self(1);
+    pub const PARSE: Self = Self(1 << 1);
+    pub const ANALYZE: Self = Self(1 << 2);
+    pub const RUN: Self = Self(1 << 3);
+    pub const ALL: Self = Self(0b1111);
+
+    pub fn contains(&self, phase: Phase) -> bool {
+        let bit = match phase {
+            Phase::Lex => Self::LEX,
+            Phase::Parse => Self::PARSE,
+            Phase::Analyze => Self::ANALYZE,
+            Phase::Run => Self::RUN,
+        };
+        self.0 & bit.0 != 0
+    }
+}
+
+impl BitOr for PhaseMask {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+//What happened in each phase of a pipeline::run
+#[derive(Debug, Default)]
+pub struct PhaseReport {
+    //How long each phase took, in the order they ran
+    pub timings: Vec<(Phase, Duration)>,
+    //The number of tokens lexed, not counting the end of the file
+    pub tokens: usize,
+    //The number of statements and expressions parsed
+    pub nodes: usize,
+    //The errors and warnings found before running, in the order they were found
+    //errors found while running are reported by the executor as usual
+    pub diagnostics: Vec<Event>,
+    //The value of the program's final expression statement, if it ran
+    pub value: Option<Literal>,
+    //The exit code of the program, if it was stopped by an exit statement or an uncaught error
+    pub exit_code: Option<i32>,
+}
+
+impl PhaseReport {
+    pub fn duration(&self, phase: Phase) -> Option<Duration> {
+        self.timings
+            .iter()
+            .find(|(timed, _)| *timed == phase)
+            .map(|(_, duration)| *duration)
+    }
+
+    //Whether an error stopped the program before it could run
+    pub fn had_error(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|event| matches!(event, Event::Error(..)))
+    }
+
+    //A line for each phase that ran, with its duration and the counts found in it
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        for (phase, duration) in &self.timings {
+            let count = match phase {
+                Phase::Lex => format!("{} tokens", self.tokens),
+                Phase::Parse => format!("{} nodes", self.nodes),
+                Phase::Analyze | Phase::Run => String::new(),
+            };
+            let line = format!(
+                "{:<8}{:>10.3}ms  {}",
+                phase.name(),
+                duration.as_secs_f64() * 1000.0,
+                count
+            );
+            lines.push(line.trim_end().to_owned());
+        }
+        lines.join("\n")
+    }
+
+    fn add_warnings(&mut self, warnings: Vec<Warning>) {
+        for warning in warnings {
+            self.diagnostics.push(Event::Warning(
+                warning.get_message(),
+                warning.get_hint(),
+                warning.get_position(),
+            ));
+        }
+    }
+
+    //Run a phase, recording how long it took
+    fn time<T>(&mut self, phase: Phase, run: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = run();
+        self.timings.push((phase, start.elapsed()));
+        result
+    }
+}
+
+//Run the phases of a program in the mask, stopping at the first one missing or with errors
+//the program is run on a fresh executor, printing its output as interpret does
+pub fn run(source: &str, mask: PhaseMask) -> PhaseReport {
+    let mut report = PhaseReport::default();
+    let Some(mut block) = parse(&mut report, source, mask) else {
+        return report;
+    };
+
+    if mask.contains(Phase::Analyze) {
+        let warnings = report.time(Phase::Analyze, || Analyzer::analyze(&block));
+        report.add_warnings(warnings);
+    }
+
+    if mask.contains(Phase::Run) {
+        let mut executor = Executor::new();
+        report.value = report.time(Phase::Run, || {
+            Optimizer::optimize(&mut block);
+            executor.execute_code(&block, false)
+        });
+        report.exit_code = executor.exit_code();
+    }
+    report
+}
+
+//Check the style of a program with the rules of the config, the warnings are timed as its analysis
+//the program is not run
+pub fn lint(source: &str, config: &LintConfig) -> PhaseReport {
+    let mut report = PhaseReport::default();
+    if let Some(block) = parse(&mut report, source, PhaseMask::LEX | PhaseMask::PARSE) {
+        let warnings = report.time(Phase::Analyze, || Linter::lint(&block, config));
+        report.add_warnings(warnings);
+    }
+    report
+}
+
+//The most times estel fix parses a program again, as fixing an error can let the ones after it be found
+const MAX_FIX_PASSES: usize = 8;
+
+//Apply the fixes of the errors found while lexing and parsing a program, until none are left to apply
+//returns the fixed source and the fixes applied, in the order they were applied
+pub fn fix(source: &str) -> (String, Vec<Fix>) {
+    let mut source = normalize_source(source);
+    let mut applied = Vec::new();
+    for _ in 0..MAX_FIX_PASSES {
+        let mut report = PhaseReport::default();
+        parse(&mut report, &source, PhaseMask::LEX | PhaseMask::PARSE);
+        let fixes: Vec<Fix> = report
+            .diagnostics
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Error(_, _, _, fix) => fix,
+                _ => None,
+            })
+            .collect();
+        let (fixed, fixes) = apply_fixes(&source, fixes);
+        if fixes.is_empty() {
+            break;
+        }
+        source = fixed;
+        applied.extend(fixes);
+    }
+    (source, applied)
+}
+
+//Lex and parse a program if the mask has both phases, None if one is missing or had errors
+fn parse(report: &mut PhaseReport, source: &str, mask: PhaseMask) -> Option<Block> {
+    let source = normalize_source(source);
+    if !mask.contains(Phase::Lex) {
+        return None;
+    }
+
+    let tokens = report.time(Phase::Lex, || Lexer::new(&source).lex());
+    report.tokens = tokens.len() - 1;
+    for token in &tokens {
+        if let TokenType::Error(error) = &token.class {
+            let message = error.get_message().to_owned();
+            let position = (token.line, token.start);
+            let fix = error.get_fix(position, &source);
+            report
+                .diagnostics
+                .push(Event::Error(Phase::Lex, message, Some(position), fix));
+        }
+    }
+    if report.had_error() || !mask.contains(Phase::Parse) {
+        return None;
+    }
+
+    match report.time(Phase::Parse, || Parser::new(&tokens).parse()) {
+        Ok(block) => {
+            report.nodes = NodeCounter::count(&block);
+            Some(block)
+        }
+        Err(errors) => {
+            for error in errors.errors.iter() {
+                let position = Some(error.get_position());
+                let fix = error.get_fix(&source);
+                report.diagnostics.push(Event::Error(
+                    Phase::Parse,
+                    error.get_message(),
+                    position,
+                    fix,
+                ));
+            }
+            None
+        }
+    }
+}
+
+//Counts the statements and expressions of a program
+struct NodeCounter {
+    nodes: usize,
+}
+
+impl NodeCounter {
+    fn count(block: &Block) -> usize {
+        let mut counter = Self { nodes: 0 };
+        counter.visit_block(block);
+        counter.nodes
+    }
+}
+
+impl ExprVisitor for NodeCounter {
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.nodes += 1;
+        walk_expr(self, expr);
+    }
+}
+
+impl StmtVisitor for NodeCounter {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        self.nodes += 1;
+        walk_stmt(self, stmt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_phases() {
+        let report = run("let a = 1 + 2\na * 2", PhaseMask::ALL);
+        assert_eq!(report.tokens, 10);
+        //two statements, the sum, its two numbers and the product with its operands
+        assert_eq!(report.nodes, 8);
+        assert_eq!(report.value, Some(Literal::Number(6)));
+        let phases: Vec<Phase> = report.timings.iter().map(|(phase, _)| *phase).collect();
+        assert_eq!(
+            phases,
+            [Phase::Lex, Phase::Parse, Phase::Analyze, Phase::Run]
+        );
+
+        //phases after a missing one are not run
+        let report = run("print 1", PhaseMask::LEX | PhaseMask::RUN);
+        assert!(report.duration(Phase::Lex).is_some());
+        assert!(report.duration(Phase::Run).is_none());
+        assert_eq!(report.nodes, 0);
+
+        //running does not need analysis
+        let report = run("1 + 1", PhaseMask::LEX | PhaseMask::PARSE | PhaseMask::RUN);
+        assert!(report.duration(Phase::Analyze).is_none());
+        assert_eq!(report.value, Some(Literal::Number(2)));
+        assert_eq!(report.exit_code, None);
+
+        //the exit code of the program is kept for the process to exit with
+        assert_eq!(run("exit(3)", PhaseMask::ALL).exit_code, Some(3));
+        assert_eq!(run("throw \"failed\"", PhaseMask::ALL).exit_code, Some(1));
+    }
+
+    #[test]
+    fn run_diagnostics() {
+        let report = run("let a = 1 @ 2", PhaseMask::ALL);
+        assert!(report.had_error());
+        assert!(matches!(
+            report.diagnostics[0],
+            Event::Error(Phase::Lex, _, Some((1, 10)), _)
+        ));
+        assert!(report.duration(Phase::Parse).is_none());
+
+        let report = run("let = 1", PhaseMask::ALL);
+        assert!(matches!(
+            report.diagnostics[0],
+            Event::Error(Phase::Parse, _, Some(_), _)
+        ));
+        assert!(report.duration(Phase::Run).is_none());
+
+        //warnings do not stop the program
+        let report = run("let a = 1\nlet a = 2\na", PhaseMask::ALL);
+        assert!(!report.had_error());
+        assert!(matches!(report.diagnostics[0], Event::Warning(..)));
+        assert_eq!(report.value, Some(Literal::Number(2)));
+    }
+
+    #[test]
+    fn lint_diagnostics() {
+        let report = lint("let x = 1\nprint x * 60", &LintConfig::default());
+        let positions: Vec<_> = report
+            .diagnostics
+            .iter()
+            .map(|event| match event {
+                Event::Warning(_, _, position) => *position,
+                event => panic!("Expected a warning, got {:?}", event),
+            })
+            .collect();
+        assert_eq!(positions, [(1, 0), (2, 0)]);
+        assert!(report.duration(Phase::Run).is_none());
+        assert!(lint("let = 1", &LintConfig::default()).had_error());
+    }
+
+    #[test]
+    fn fix_errors() {
+        let source = "let a = 1 print a\nif a = 1 { print a }\nprint \"one\nlet b = 2 b += a\n";
+        let (fixed, fixes) = fix(source);
+        assert_eq!(
+            fixed,
+            "let a = 1; print a\nif a == 1 { print a }\nprint \"one\"\nlet b = 2; b += a\n"
+        );
+        let messages: Vec<&str> = fixes.iter().map(|fix| fix.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Close the string with \"",
+                "Insert ; before the statement",
+                "Replace = with ==",
+                "Insert ; before the statement",
+            ]
+        );
+
+        //errors without a clear fix are left for the programmer
+        let (fixed, fixes) = fix("let = 1\nprint (1");
+        assert_eq!(fixed, "let = 1\nprint (1");
+        assert!(fixes.is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::parser::map::Map;
+use crate::parser::record::Record;
+use crate::token::Literal;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{
+    Arc, Condvar, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+use std::time::{Duration, Instant};
+
+//A copy of a value which can be sent to other threads
+//functions can't be shared, they hold the scopes of the executor which made them
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedValue {
+    Number(i32),
+    Float(f32),
+    String(String),
+    Bool(bool),
+    Tuple(Vec<SharedValue>),
+    //Map(Entries), in the order of the map
+    Map(Vec<(SharedValue, SharedValue)>),
+    Range(i32, i32),
+    Channel(Channel),
+    //Record(Struct name, Fields)
+    Record(String, Vec<(String, SharedValue)>),
+    None,
+}
+
+impl SharedValue {
+    //None if the value is a function or holds one
+    pub fn from_literal(value: &Literal) -> Option<Self> {
+        Some(match value {
+            Literal::Number(num) => Self::Number(*num),
+            Literal::Float(num) => Self::Float(*num),
+            Literal::String(string) => Self::String(string.to_owned()),
+            Literal::Bool(boolean) => Self::Bool(*boolean),
+            Literal::Tuple(items) => Self::Tuple(
+                items
+                    .iter()
+                    .map(Self::from_literal)
+                    .collect::<Option<_>>()?,
+            ),
+            Literal::Map(map) => Self::Map(
+                map.iter()
+                    .map(|(key, value)| {
+                        Some((Self::from_literal(key)?, Self::from_literal(value)?))
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            Literal::Range(start, end) => Self::Range(*start, *end),
+            Literal::Channel(channel) => Self::Channel(channel.clone()),
+            Literal::Record(record) => Self::Record(
+                record.name().to_owned(),
+                record
+                    .iter()
+                    .map(|(name, value)| Some((name.to_owned(), Self::from_literal(value)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            Literal::Function(_) => return None,
+            Literal::None => Self::None,
+        })
+    }
+
+    pub fn to_literal(&self) -> Literal {
+        match self {
+            Self::Number(num) => Literal::Number(*num),
+            Self::Float(num) => Literal::Float(*num),
+            Self::String(string) => Literal::String(string.to_owned()),
+            Self::Bool(boolean) => Literal::Bool(*boolean),
+            Self::Tuple(items) => Literal::Tuple(items.iter().map(Self::to_literal).collect()),
+            Self::Map(entries) => Literal::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_literal(), value.to_literal()))
+                    .collect::<Map>(),
+            ),
+            Self::Range(start, end) => Literal::Range(*start, *end),
+            Self::Channel(channel) => Literal::Channel(channel.clone()),
+            Self::Record(name, fields) => Literal::Record(Record::new(
+                name,
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.to_owned(), value.to_literal()))
+                    .collect(),
+            )),
+            Self::None => Literal::None,
+        }
+    }
+}
+
+//Global variables shared by several executors, eg- the configuration read by the scripts of a server
+//clones share the same variables, so each executor can be given a clone, on any thread
+//Locking:
+//programs only read the shared variables, holding the read lock just while a value is copied out
+//set and remove take the write lock, they wait for reads in progress and are seen by the next read
+//a program can shadow a shared variable with let, but not assign to it
+#[derive(Debug, Clone, Default)]
+pub struct SharedGlobals {
+    vars: Arc<RwLock<HashMap<String, SharedValue>>>,
+}
+
+impl SharedGlobals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //Set a variable, returns false if the value can't be shared because it holds a function
+    pub fn set(&self, name: &str, value: &Literal) -> bool {
+        match SharedValue::from_literal(value) {
+            Some(value) => {
+                self.write().insert(name.to_owned(), value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.write().remove(name);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Literal> {
+        self.read().get(name).map(SharedValue::to_literal)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.read().contains_key(name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.read().keys().cloned().collect()
+    }
+
+    //the values are plain data, so a thread panicking while holding the lock can't leave them half changed
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, SharedValue>> {
+        self.vars.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, SharedValue>> {
+        self.vars.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+//A queue of values sent between programs running on different threads, made by the host
+//each program is given a clone, eg- with Interpreter::define, and clones send to the same queue
+//send never blocks, recv waits for a value, up to a timeout when one is given
+#[derive(Clone, Default)]
+pub struct Channel {
+    queue: Arc<(Mutex<VecDeque<SharedValue>>, Condvar)>,
+}
+
+impl Channel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //Add a value to the queue, returns false if the value can't be shared because it holds a function
+    pub fn send(&self, value: &Literal) -> bool {
+        match SharedValue::from_literal(value) {
+            Some(value) => {
+                self.lock().push_back(value);
+                self.queue.1.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    //Take the oldest value, waiting for one to be sent if the queue is empty
+    //None if the timeout passes first
+    pub fn recv(&self, timeout: Option<Duration>) -> Option<Literal> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut queue = self.lock();
+        loop {
+            if let Some(value) = queue.pop_front() {
+                return Some(value.to_literal());
+            }
+            let ready = &self.queue.1;
+            queue = match deadline {
+                None => ready.wait(queue).unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let left = deadline.checked_duration_since(Instant::now())?;
+                    let waited = ready.wait_timeout(queue, left);
+                    waited.unwrap_or_else(PoisonError::into_inner).0
+                }
+            };
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<SharedValue>> {
+        self.queue.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+//Channels are equal when they are clones of the same channel
+impl PartialEq for Channel {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.queue, &other.queue)
+    }
+}
+
+impl PartialOrd for Channel {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self == other).then_some(Ordering::Equal)
+    }
+}
+
+impl fmt::Debug for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Channel({} queued)", self.lock().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn share_globals() {
+        let shared = SharedGlobals::new();
+        let map = Literal::Map(Map::from_iter([(
+            Literal::String(String::from("port")),
+            Literal::Tuple(vec![Literal::Number(80), Literal::Range(0, 2)]),
+        )]));
+        assert!(shared.set("config", &map));
+
+        //the values are read from other threads, values can't be sent back since they may hold functions
+        let reader = shared.clone();
+        let text = thread::spawn(move || reader.get("config").map(|value| value.to_string()));
+        assert_eq!(text.join().unwrap(), Some(map.to_string()));
+        assert_eq!(shared.get("config"), Some(map));
+        assert!(!shared.contains("missing"));
+
+        shared.remove("config");
+        assert_eq!(shared.get("config"), None);
+    }
+
+    #[test]
+    fn channels() {
+        let channel = Channel::new();
+        let sender = channel.clone();
+        let sent = thread::spawn(move || (1..=3).all(|num| sender.send(&Literal::Number(num))));
+        let received: Vec<Option<Literal>> = (0..3).map(|_| channel.recv(None)).collect();
+        assert!(sent.join().unwrap());
+        assert_eq!(received, [1, 2, 3].map(|num| Some(Literal::Number(num))));
+        assert_eq!(channel.recv(Some(Duration::from_millis(1))), None);
+        assert_eq!(channel, channel.clone());
+        assert_ne!(channel, Channel::new());
+    }
+}
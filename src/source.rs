@@ -0,0 +1,62 @@
+use crate::errors::SourceError;
+use std::fs;
+
+const UTF8_BOM: char = '\u{feff}';
+
+//Read a source file, rejecting encodings other than UTF-8 with a clear error
+pub fn read_source(path: &str) -> Result<String, SourceError> {
+    let bytes = fs::read(path).map_err(SourceError::ReadError)?;
+    decode_source(bytes)
+}
+
+pub fn decode_source(bytes: Vec<u8>) -> Result<String, SourceError> {
+    //UTF-16 files start with a byte order mark, or have null bytes between ascii characters
+    if bytes.starts_with(&[0xff, 0xfe]) {
+        return Err(SourceError::UnsupportedEncoding("UTF-16 LE"));
+    }
+    if bytes.starts_with(&[0xfe, 0xff]) {
+        return Err(SourceError::UnsupportedEncoding("UTF-16 BE"));
+    }
+    if bytes.contains(&0) {
+        return Err(SourceError::UnsupportedEncoding("UTF-16"));
+    }
+    match String::from_utf8(bytes) {
+        Ok(source) => Ok(normalize_source(&source)),
+        Err(_) => Err(SourceError::UnsupportedEncoding("other than UTF-8")),
+    }
+}
+
+//Strip a UTF-8 byte order mark and turn CRLF line endings into LF
+//the lexer and the error snippets then see the same characters on every platform
+pub fn normalize_source(source: &str) -> String {
+    source
+        .strip_prefix(UTF8_BOM)
+        .unwrap_or(source)
+        .replace("\r\n", "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_sources() {
+        let source = decode_source(b"\xef\xbb\xbfprint 1\r\nprint 2\r\n".to_vec()).unwrap();
+        assert_eq!(source, "print 1\nprint 2\n");
+
+        //"p" in UTF-16, with and without a byte order mark
+        assert!(matches!(
+            decode_source(vec![0xff, 0xfe, b'p', 0]),
+            Err(SourceError::UnsupportedEncoding("UTF-16 LE"))
+        ));
+        assert!(matches!(
+            decode_source(vec![0xfe, 0xff, 0, b'p']),
+            Err(SourceError::UnsupportedEncoding("UTF-16 BE"))
+        ));
+        assert!(matches!(
+            decode_source(vec![b'p', 0]),
+            Err(SourceError::UnsupportedEncoding("UTF-16"))
+        ));
+        assert!(decode_source(vec![0xc3, b'p']).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use crate::errors::LiteralOpError;
+use crate::output::json_string;
+use crate::parser::map::Map;
+use crate::token::Literal;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+//Where store_get() and store_set() keep values, so scripts can keep state between runs
+//embedders can give the executor their own store, eg- one backed by a database
+pub trait Store {
+    fn get(&self, key: &str) -> Option<Literal>;
+    fn set(&mut self, key: &str, value: Literal) -> Result<(), LiteralOpError>;
+}
+
+//A store which only lasts as long as the executor, used when no other store is given
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    values: HashMap<String, Literal>,
+}
+
+impl Store for MemoryStore {
+    fn get(&self, key: &str) -> Option<Literal> {
+        self.values.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: Literal) -> Result<(), LiteralOpError> {
+        self.values.insert(key.to_owned(), value);
+        Ok(())
+    }
+}
+
+//A store kept in a JSON object, written again after every change
+//only values JSON can hold are stored, maps need string keys and tuples become arrays
+#[derive(Debug)]
+pub struct JsonFileStore {
+    path: PathBuf,
+    values: Map,
+}
+
+impl JsonFileStore {
+    //Read the store at the path, a missing file is an empty store
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let values = match fs::read_to_string(&path) {
+            Ok(json) => match parse_json(&json) {
+                Some(Literal::Map(values)) => values,
+                _ => return Err(format!("{} is not a JSON object", path.display())),
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Map::new(),
+            Err(err) => return Err(format!("Failed to read {}: {}", path.display(), err)),
+        };
+        Ok(Self { path, values })
+    }
+}
+
+impl Store for JsonFileStore {
+    fn get(&self, key: &str) -> Option<Literal> {
+        self.values.get(&Literal::String(key.to_owned())).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: Literal) -> Result<(), LiteralOpError> {
+        //the value is checked before the store changes, so a failed set changes nothing
+        to_json(&value)?;
+        self.values.insert(Literal::String(key.to_owned()), value);
+        let json = to_json(&Literal::Map(self.values.clone()))?;
+        fs::write(&self.path, json + "\n").map_err(|_| LiteralOpError::StoreError)
+    }
+}
+
+fn to_json(value: &Literal) -> Result<String, LiteralOpError> {
+    Ok(match value {
+        Literal::None => String::from("null"),
+        Literal::Bool(boolean) => boolean.to_string(),
+        Literal::Number(num) => num.to_string(),
+        //floats keep their point, so they are read back as floats
+        Literal::Float(num) if num.is_finite() => format!("{:?}", num),
+        Literal::String(string) => json_string(string),
+        Literal::Tuple(items) => {
+            let items: Result<Vec<String>, _> = items.iter().map(to_json).collect();
+            format!("[{}]", items?.join(","))
+        }
+        Literal::Map(map) => {
+            let mut entries = Vec::new();
+            for (key, value) in map.iter() {
+                let Literal::String(key) = key else {
+                    return Err(LiteralOpError::InvalidArgumentError);
+                };
+                entries.push(format!("{}:{}", json_string(key), to_json(value)?));
+            }
+            format!("{{{}}}", entries.join(","))
+        }
+        _ => return Err(LiteralOpError::InvalidArgumentError),
+    })
+}
+
+//Parse a JSON document into a value, None if it is not valid JSON
+fn parse_json(json: &str) -> Option<Literal> {
+    let mut parser = JsonParser {
+        chars: json.chars().collect(),
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    (parser.pos == parser.chars.len()).then_some(value)
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonParser {
+    fn value(&mut self) -> Option<Literal> {
+        self.skip_whitespace();
+        match self.peek()? {
+            '{' => self.object(),
+            '[' => self.array(),
+            '"' => self.string().map(Literal::String),
+            't' => self.word("true", Literal::Bool(true)),
+            'f' => self.word("false", Literal::Bool(false)),
+            'n' => self.word("null", Literal::None),
+            _ => self.number(),
+        }
+    }
+
+    fn object(&mut self) -> Option<Literal> {
+        self.pos += 1;
+        let mut map = Map::new();
+        if self.consume('}') {
+            return Some(Literal::Map(map));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            if !self.consume(':') {
+                return None;
+            }
+            map.insert(Literal::String(key), self.value()?);
+            if self.consume('}') {
+                return Some(Literal::Map(map));
+            }
+            if !self.consume(',') {
+                return None;
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<Literal> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        if self.consume(']') {
+            return Some(Literal::Tuple(items));
+        }
+        loop {
+            items.push(self.value()?);
+            if self.consume(']') {
+                return Some(Literal::Tuple(items));
+            }
+            if !self.consume(',') {
+                return None;
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.peek()? != '"' {
+            return None;
+        }
+        self.pos += 1;
+        let mut string = String::new();
+        loop {
+            let ch = *self.chars.get(self.pos)?;
+            self.pos += 1;
+            match ch {
+                '"' => return Some(string),
+                '\\' => {
+                    let escape = *self.chars.get(self.pos)?;
+                    self.pos += 1;
+                    string.push(match escape {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => {
+                            let hex: String =
+                                self.chars.get(self.pos..self.pos + 4)?.iter().collect();
+                            self.pos += 4;
+                            char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                        }
+                        '"' | '\\' | '/' => escape,
+                        _ => return None,
+                    });
+                }
+                ch => string.push(ch),
+            }
+        }
+    }
+
+    //whole numbers which fit are numbers, the rest are floats
+    fn number(&mut self) -> Option<Literal> {
+        let start = self.pos;
+        while let Some(ch) = self.peek() {
+            if !(ch.is_ascii_digit() || matches!(ch, '-' | '+' | '.' | 'e' | 'E')) {
+                break;
+            }
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match text.parse() {
+            Ok(num) => Some(Literal::Number(num)),
+            Err(_) => text.parse().ok().map(Literal::Float),
+        }
+    }
+
+    fn word(&mut self, word: &str, value: Literal) -> Option<Literal> {
+        let end = self.pos + word.len();
+        let found: String = self.chars.get(self.pos..end)?.iter().collect();
+        self.pos = end;
+        (found == word).then_some(value)
+    }
+
+    fn consume(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(expected);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn json_values() {
+        let values = Literal::Map(Map::from_iter([
+            (
+                Literal::String(String::from("runs")),
+                Literal::Tuple(vec![
+                    Literal::Number(-3),
+                    Literal::Float(2.0),
+                    Literal::None,
+                ]),
+            ),
+            (
+                Literal::String(String::from("name \"a\"\n")),
+                Literal::Bool(true),
+            ),
+        ]));
+        let json = to_json(&values).unwrap();
+        assert_eq!(json, r#"{"runs":[-3,2.0,null],"name \"a\"\n":true}"#);
+        assert_eq!(parse_json(&json), Some(values));
+        assert_eq!(
+            parse_json(" { \"a\" : [ 1e3 , \"\\u00e9\" ] } "),
+            Some(Literal::Map(Map::from_iter([(
+                Literal::String(String::from("a")),
+                Literal::Tuple(vec![
+                    Literal::Float(1000.0),
+                    Literal::String(String::from("é"))
+                ]),
+            )])))
+        );
+        for json in ["{", "[1,]", "{\"a\" 1}", "tru", "1 2"] {
+            assert_eq!(parse_json(json), None, "{}", json);
+        }
+        assert_eq!(
+            to_json(&Literal::Range(0, 1)),
+            Err(LiteralOpError::InvalidArgumentError)
+        );
+    }
+
+    #[test]
+    fn json_file_store() {
+        let path = env::temp_dir().join(format!("estel-store-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut store = JsonFileStore::open(&path).unwrap();
+        assert_eq!(store.get("count"), None);
+        store.set("count", Literal::Number(1)).unwrap();
+        assert_eq!(
+            store.set("bad", Literal::Range(0, 1)),
+            Err(LiteralOpError::InvalidArgumentError)
+        );
+
+        //the values are there the next time the store is opened
+        let store = JsonFileStore::open(&path).unwrap();
+        assert_eq!(store.get("count"), Some(Literal::Number(1)));
+        assert_eq!(store.get("bad"), None);
+        fs::write(&path, "[]").unwrap();
+        assert!(JsonFileStore::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+use estel::examples::EXAMPLES;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+//Run every example through the binary and compare its output to the snapshot in code/examples
+//set UPDATE_SNAPSHOTS=1 to rewrite the snapshots after an intended change
+#[test]
+fn example_snapshots() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("code/examples");
+    for example in EXAMPLES {
+        let output = Command::new(env!("CARGO_BIN_EXE_estel"))
+            .args(["examples", example.name])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.is_empty(), "{} failed:\n{}", example.name, stderr);
+
+        let snapshot = dir.join(format!("{}.out", example.name));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            fs::write(&snapshot, &stdout).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&snapshot).unwrap_or_default();
+        assert_eq!(stdout, expected, "{} output changed", example.name);
+    }
+}
+
+//Fizzbuzz is checked against its rules as well, so a wrong snapshot can't hide a wrong program
+#[test]
+fn fizzbuzz_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_estel"))
+        .args(["examples", "fizzbuzz"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let expected: Vec<String> = (1..=20)
+        .map(|i| match (i % 3, i % 5) {
+            (0, 0) => String::from("fizzbuzz"),
+            (0, _) => String::from("fizz"),
+            (_, 0) => String::from("buzz"),
+            _ => i.to_string(),
+        })
+        .collect();
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), expected);
+}
@@ -0,0 +1,80 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+//Run the binary with the arguments and input, returning its stdout and stderr
+fn run(args: &[&str], input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_estel"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+//Only program output and expression results go to stdout, everything else to stderr
+#[test]
+fn prompt_streams() {
+    let (stdout, stderr) = run(&[], "print 1\n1 + 1\nlet x =\nx\n:paste\nprint 3\n:end\n");
+    assert_eq!(stdout, "1\n2\n3\n");
+    assert!(stderr.contains(">>>>"));
+    assert!(stderr.contains("Expected an expression"));
+    assert!(stderr.contains("UndefinedVariableError"));
+}
+
+#[test]
+fn example_list_streams() {
+    let (stdout, stderr) = run(&["examples"], "");
+    assert!(stdout.contains("fibonacci") && stderr.is_empty());
+
+    //after an error the list is part of the error output
+    let (stdout, stderr) = run(&["examples", "missing"], "");
+    assert!(stdout.is_empty());
+    assert!(stderr.contains("No example named missing") && stderr.contains("fibonacci"));
+}
+
+//Every event of a program run with --json-output is a line of JSON on stdout
+#[test]
+fn json_output_events() {
+    let path = std::env::temp_dir().join(format!("estel-json-{}.estel", std::process::id()));
+    std::fs::write(&path, "let a = 1\nlet a = 2\nprint \"a\\tb\"\nprint x").unwrap();
+    let (stdout, stderr) = run(&["--json-output", path.to_str().unwrap()], "");
+    std::fs::remove_file(&path).unwrap();
+    assert!(stderr.is_empty());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(
+        lines[0].starts_with(r#"{"event":"warning","message":"Value assigned to a is never read""#)
+    );
+    assert_eq!(lines[1], r#"{"event":"print","text":"a\tb"}"#);
+    assert_eq!(
+        lines[2],
+        r#"{"event":"error","phase":"run","message":"UndefinedVariableError"}"#
+    );
+
+    let (stdout, stderr) = run(&["--json-output"], "");
+    assert!(stdout.is_empty() && stderr.contains("needs a file"));
+}
+
+//Reaching the call depth limit ends every running call, so it is reported once and the program goes on
+#[test]
+fn call_depth_reported_once() {
+    let path = std::env::temp_dir().join(format!("estel-depth-{}.estel", std::process::id()));
+    let source = "fn sum(n) {\n  if n == 0 { return 0 }\n  return n + sum(n - 1)\n}\nprint sum(1001)\nprint sum(3)";
+    std::fs::write(&path, source).unwrap();
+    let (stdout, stderr) = run(&[path.to_str().unwrap()], "");
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(stdout, "6\n");
+    assert_eq!(stderr, "CallDepthError\n");
+}